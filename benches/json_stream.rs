@@ -0,0 +1,111 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::prelude::*;
+use reqwest_streams::*;
+use serde::{Deserialize, Serialize};
+
+const ITEMS_LEN: usize = 20_000;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyTestStructure {
+    some_test_field: String,
+    test_arr: Vec<MyChildTest>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MyChildTest {
+    test_field: String,
+}
+
+fn generate_test_structures() -> Vec<MyTestStructure> {
+    vec![
+        MyTestStructure {
+            some_test_field: "TestValue".to_string(),
+            test_arr: vec![
+                MyChildTest {
+                    test_field: "TestValue1".to_string()
+                },
+                MyChildTest {
+                    test_field: "TestValue2".to_string()
+                }
+            ]
+        };
+        ITEMS_LEN
+    ]
+}
+
+// The responses are built without a server, so only the decoding is measured
+fn response(body: &bytes::Bytes) -> reqwest::Response {
+    reqwest::Response::from(axum::http::Response::new(body.clone()))
+}
+
+fn json_nl_stream(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let body: bytes::Bytes = generate_test_structures()
+        .iter()
+        .map(|item| serde_json::to_string(item).unwrap() + "\n")
+        .collect::<String>()
+        .into();
+
+    let mut group = c.benchmark_group("json_nl_stream");
+    group.throughput(Throughput::Elements(ITEMS_LEN as u64));
+    group.bench_with_input(BenchmarkId::new("lines", ITEMS_LEN), &body, |b, body| {
+        b.to_async(&runtime).iter(|| async {
+            let items: Vec<MyTestStructure> = response(body)
+                .json_nl_stream(1024)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(items.len(), ITEMS_LEN);
+        })
+    });
+    group.bench_with_input(BenchmarkId::new("fast", ITEMS_LEN), &body, |b, body| {
+        b.to_async(&runtime).iter(|| async {
+            let items: Vec<MyTestStructure> = response(body)
+                .json_nl_fast_stream(1024)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(items.len(), ITEMS_LEN);
+        })
+    });
+    group.finish();
+}
+
+fn json_array_stream(c: &mut Criterion) {
+    // The buffered stream deserializes the items on the blocking threads of the runtime
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let body: bytes::Bytes = serde_json::to_string(&generate_test_structures())
+        .unwrap()
+        .into();
+
+    let mut group = c.benchmark_group("json_array_stream");
+    group.throughput(Throughput::Elements(ITEMS_LEN as u64));
+    group.bench_with_input(
+        BenchmarkId::new("sequential", ITEMS_LEN),
+        &body,
+        |b, body| {
+            b.to_async(&runtime).iter(|| async {
+                let items: Vec<MyTestStructure> = response(body)
+                    .json_array_stream(1024)
+                    .try_collect()
+                    .await
+                    .unwrap();
+                assert_eq!(items.len(), ITEMS_LEN);
+            })
+        },
+    );
+    group.bench_with_input(BenchmarkId::new("buffered", ITEMS_LEN), &body, |b, body| {
+        b.to_async(&runtime).iter(|| async {
+            let items: Vec<MyTestStructure> = response(body)
+                .json_array_stream_buffered(1024, 4)
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(items.len(), ITEMS_LEN);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, json_nl_stream, json_array_stream);
+criterion_main!(benches);
@@ -1,14 +1,28 @@
 use crate::error::StreamBodyKind;
 use crate::StreamBodyError;
 use arrow::array::RecordBatch;
+use arrow::datatypes::SchemaRef;
 use arrow::ipc::reader::StreamDecoder;
+use arrow::ipc::MessageHeader;
 use bytes::{Buf, BytesMut};
+use std::sync::Arc;
 
+/// Decodes the record batches of an Arrow IPC stream.
+///
+/// The messages are framed here and passed one by one to the [`StreamDecoder`]. A schema
+/// message after the first one starts a new IPC stream, as when a producer restarts the stream
+/// within the same body, with or without the end-of-stream marker of the previous one: the
+/// decoder is replaced, so the following batches and dictionaries use the new schema.
 #[derive(Debug)]
 pub struct ArrowIpcCodec {
     max_length: usize,
     decoder: StreamDecoder,
     current_obj_len: usize,
+    consumed_len: usize,
+    // The bytes of the current message not passed to the decoder yet
+    message_remaining: usize,
+    message_is_schema: bool,
+    stream_ended: bool,
 }
 
 impl ArrowIpcCodec {
@@ -17,50 +31,185 @@ impl ArrowIpcCodec {
             max_length,
             decoder: StreamDecoder::new(),
             current_obj_len: 0,
+            consumed_len: 0,
+            message_remaining: 0,
+            message_is_schema: false,
+            stream_ended: false,
         }
     }
 }
 
-impl tokio_util::codec::Decoder for ArrowIpcCodec {
-    type Item = RecordBatch;
-    type Error = StreamBodyError;
+/// Returns the position and the length of the metadata of the first message in `buf`, or
+/// `None` if the message isn't complete yet.
+fn message_metadata(buf: &[u8]) -> Option<(usize, usize)> {
+    // Messages are prefixed with an optional continuation marker and a 32-bit length
+    const CONTINUATION_MARKER: [u8; 4] = [0xff; 4];
+    let (len_pos, msg_pos) = if buf.starts_with(&CONTINUATION_MARKER) {
+        (4, 8)
+    } else {
+        (0, 4)
+    };
+    if buf.len() < msg_pos {
+        return None;
+    }
+    let msg_len = i32::from_le_bytes([
+        buf[len_pos],
+        buf[len_pos + 1],
+        buf[len_pos + 2],
+        buf[len_pos + 3],
+    ])
+    .max(0) as usize;
+    if buf.len() < msg_pos + msg_len {
+        return None;
+    }
+    Some((msg_pos, msg_len))
+}
 
-    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<RecordBatch>, StreamBodyError> {
-        let buf_len = buf.len();
-        if buf_len == 0 {
-            return Ok(None);
-        }
+/// Decodes the schema from the first message of an Arrow IPC stream in `buf`, without
+/// consuming it. Returns `None` if the message isn't complete yet.
+pub fn decode_schema(buf: &[u8]) -> Result<Option<SchemaRef>, StreamBodyError> {
+    let (msg_pos, msg_len) = match message_metadata(buf) {
+        Some(metadata) => metadata,
+        None => return Ok(None),
+    };
 
-        let obj_bytes = buf.as_ref();
-        let obj_bytes_len = obj_bytes.len();
-        let mut buffer = arrow::buffer::Buffer::from(obj_bytes);
-        let maybe_record = self.decoder.decode(&mut buffer).map_err(|e| {
+    let message = arrow::ipc::root_as_message(&buf[msg_pos..msg_pos + msg_len]).map_err(|e| {
+        StreamBodyError::new(
+            StreamBodyKind::CodecError,
+            None,
+            Some(format!("Decode arrow IPC message error: {}", e)),
+        )
+    })?;
+    match message.header_as_schema() {
+        Some(schema) => Ok(Some(Arc::new(arrow::ipc::convert::fb_to_schema(schema)))),
+        None => Err(StreamBodyError::new(
+            StreamBodyKind::CodecError,
+            None,
+            Some("Arrow IPC stream doesn't start with a schema".into()),
+        )),
+    }
+}
+
+impl ArrowIpcCodec {
+    fn decode_buffer(
+        &mut self,
+        buffer: &mut arrow::buffer::Buffer,
+    ) -> Result<Option<RecordBatch>, StreamBodyError> {
+        self.decoder.decode(buffer).map_err(|e| {
             StreamBodyError::new(
                 StreamBodyKind::CodecError,
                 Some(Box::new(e)),
                 Some("Decode arrow IPC record error".into()),
             )
-        })?;
+        })
+    }
 
-        if maybe_record.is_none() {
-            self.current_obj_len += obj_bytes_len;
-        } else {
-            self.current_obj_len = 0;
-        }
+    /// Reads the metadata of the next message in `buf`, consuming the end-of-stream markers.
+    /// Returns `false` if the metadata isn't complete yet.
+    fn start_message(&mut self, buf: &mut BytesMut) -> Result<bool, StreamBodyError> {
+        loop {
+            let (msg_pos, msg_len) = match message_metadata(buf) {
+                Some(metadata) => metadata,
+                None if buf.len() > self.max_length => {
+                    return Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.consumed_len + buf.len(),
+                    ));
+                }
+                None => return Ok(false),
+            };
 
-        if self.current_obj_len > self.max_length {
-            return Err(StreamBodyError::new(
-                StreamBodyKind::CodecError,
-                None,
-                Some("Object length exceeds the maximum length".into()),
-            ));
+            // The end-of-stream marker has an empty message
+            if msg_len == 0 {
+                buf.advance(msg_pos);
+                self.consumed_len += msg_pos;
+                self.stream_ended = true;
+                if buf.is_empty() {
+                    return Ok(false);
+                }
+                continue;
+            }
+
+            let message =
+                arrow::ipc::root_as_message(&buf[msg_pos..msg_pos + msg_len]).map_err(|e| {
+                    StreamBodyError::new(
+                        StreamBodyKind::CodecError,
+                        None,
+                        Some(format!("Decode arrow IPC message error: {}", e)),
+                    )
+                })?;
+            let message_is_schema = message.header_type() == MessageHeader::Schema;
+            if message_is_schema {
+                // A new IPC stream starts, possibly with another schema
+                self.decoder = StreamDecoder::new();
+                self.stream_ended = false;
+            } else if self.stream_ended {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some("Arrow IPC message after the end of the stream".into()),
+                ));
+            }
+
+            self.message_remaining = msg_pos + msg_len + message.bodyLength().max(0) as usize;
+            self.message_is_schema = message_is_schema;
+            return Ok(true);
         }
+    }
+}
+
+impl tokio_util::codec::Decoder for ArrowIpcCodec {
+    type Item = RecordBatch;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<RecordBatch>, StreamBodyError> {
+        loop {
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            if self.message_remaining == 0 && !self.start_message(buf)? {
+                return Ok(None);
+            }
+
+            // The decoder buffers the partial messages itself
+            let obj_bytes_len = buf.len().min(self.message_remaining);
+            let mut buffer = arrow::buffer::Buffer::from(&buf[..obj_bytes_len]);
+            let maybe_record = self.decode_buffer(&mut buffer)?;
+            let decoded_len = obj_bytes_len - buffer.len();
+            buf.advance(decoded_len);
+            self.consumed_len += decoded_len;
+            self.message_remaining -= decoded_len;
 
-        buf.advance(obj_bytes_len - buffer.len());
-        Ok(maybe_record)
+            // The schema messages have no body, and don't count for the length of the batches
+            if !self.message_is_schema {
+                self.current_obj_len += decoded_len;
+                if self.current_obj_len > self.max_length {
+                    return Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.consumed_len,
+                    ));
+                }
+            }
+
+            if maybe_record.is_some() {
+                self.current_obj_len = 0;
+                return Ok(maybe_record);
+            }
+        }
     }
 
     fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<RecordBatch>, StreamBodyError> {
-        self.decode(buf)
+        match self.decode(buf)? {
+            Some(record) => Ok(Some(record)),
+            // A partial message metadata is still in the buffer, or the decoder has a partial
+            // message
+            None if !buf.is_empty() || self.message_remaining > 0 => {
+                Err(StreamBodyError::unexpected_eof(
+                    self.consumed_len + buf.len(),
+                    "The stream ended inside an Arrow IPC message",
+                ))
+            }
+            None => Ok(None),
+        }
     }
 }
@@ -1,9 +1,24 @@
-use crate::arrow_ipc_len_codec::ArrowIpcCodec;
-use crate::StreamBodyResult;
+use crate::arrow_ipc_len_codec::{decode_schema, ArrowIpcCodec};
+use crate::codec_stream::stream_with_codec_and_capacity;
+use crate::error::StreamBodyKind;
+use crate::response_reader::{check_content_type, response_reader, INITIAL_CAPACITY};
+use crate::stream_tracing::traced_codec;
+use crate::{StreamBodyError, StreamBodyResult};
 use arrow::array::RecordBatch;
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
 use async_trait::*;
+use bytes::BytesMut;
 use futures::stream::BoxStream;
 use futures::TryStreamExt;
+#[cfg(feature = "arrow-serde")]
+use serde::Deserialize;
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+pub(crate) const ARROW_IPC_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
 
 /// Extension trait for [`reqwest::Response`] that provides streaming support for the [Apache Arrow
 /// IPC format].
@@ -15,6 +30,179 @@ pub trait ArrowIpcStreamResponse {
         self,
         max_obj_len: usize,
     ) -> BoxStream<'a, StreamBodyResult<RecordBatch>>;
+
+    /// Streams the response as batches of Arrow IPC messages.
+    ///
+    /// See [`ArrowIpcStreamResponse::arrow_ipc_stream`] for the details. `buf_capacity` is the
+    /// initial capacity of the stream's decoding buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use arrow::array::RecordBatch;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ArrowIpcStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const INITIAL_BUF_CAPACITY: usize = 128 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/arrow")
+    ///         .await?
+    ///         .arrow_ipc_stream_with_capacity(MAX_OBJ_LEN, INITIAL_BUF_CAPACITY);
+    ///     let _items: Vec<RecordBatch> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn arrow_ipc_stream_with_capacity<'a>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>>;
+
+    /// Streams the response as batches of Arrow IPC messages, checking the content type of the
+    /// response first.
+    ///
+    /// See [`ArrowIpcStreamResponse::arrow_ipc_stream`] for the details. If the media type of
+    /// the response isn't `content_type` (`application/vnd.apache.arrow.stream` if `None`), the
+    /// stream yields a single [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error
+    /// instead of decoding the body.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use arrow::array::RecordBatch;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ArrowIpcStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/arrow")
+    ///         .await?
+    ///         .arrow_ipc_stream_strict(MAX_OBJ_LEN, None);
+    ///     let _items: Vec<RecordBatch> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn arrow_ipc_stream_strict<'a>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>>;
+
+    async fn arrow_ipc_stream_with_schema<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> StreamBodyResult<(SchemaRef, BoxStream<'a, StreamBodyResult<RecordBatch>>)>;
+
+    /// Streams the response as batches of Arrow IPC messages, with the dictionary-encoded
+    /// columns decoded.
+    ///
+    /// The batches of [`ArrowIpcStreamResponse::arrow_ipc_stream`] share the dictionaries sent
+    /// earlier in the stream. Here, every top-level column of a dictionary type is cast to the
+    /// type of its values, so every batch is self-contained and can be kept after the stream,
+    /// at the cost of the memory of the decoded values. See
+    /// [`ArrowIpcStreamResponse::arrow_ipc_stream`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use arrow::array::RecordBatch;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ArrowIpcStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/arrow")
+    ///         .await?
+    ///         .arrow_ipc_stream_with_decoded_dictionaries(MAX_OBJ_LEN);
+    ///     let _items: Vec<RecordBatch> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn arrow_ipc_stream_with_decoded_dictionaries<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>>;
+
+    /// Streams the response as batches of the [Arrow IPC file format].
+    ///
+    /// Unlike the stream format, the file format can't be decoded incrementally, because the
+    /// footer at the end of the file is needed to read the batches. So the whole response is
+    /// buffered first, up to `max_obj_len` bytes, and the batches are yielded afterwards.
+    ///
+    /// [Arrow IPC file format]: https://arrow.apache.org/docs/format/Columnar.html#ipc-file-format
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use arrow::array::RecordBatch;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ArrowIpcStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_FILE_LEN: usize = 16 * 1024 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/arrow-file")
+    ///         .await?
+    ///         .arrow_file_stream(MAX_FILE_LEN);
+    ///     let _items: Vec<RecordBatch> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn arrow_file_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>>;
+
+    /// Streams the response as rows of Arrow IPC record batches.
+    ///
+    /// Every row of the record batches is deserialized as type `T` using [`serde_arrow`],
+    /// with a maximum size of `max_obj_len` bytes per record batch.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ArrowIpcStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct City {
+    ///     id: i64,
+    ///     city: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/arrow")
+    ///         .await?
+    ///         .arrow_ipc_rows_stream::<City>(MAX_OBJ_LEN);
+    ///     let _items: Vec<City> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "arrow-serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "arrow-serde")))]
+    fn arrow_ipc_rows_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
 }
 
 #[async_trait]
@@ -24,6 +212,14 @@ impl ArrowIpcStreamResponse for reqwest::Response {
     /// The stream will deserialize entries into [`RecordBatch`]es with a maximum object size of
     /// `max_obj_len` bytes.
     ///
+    /// Some producers restart the IPC stream within the same response, sending a new schema
+    /// message, with or without the end-of-stream marker of the previous stream. The batches
+    /// after it are decoded with the new schema, which may differ: compare
+    /// [`RecordBatch::schema`] to the one of the previous batch to detect the change. The
+    /// dictionaries of the previous stream are discarded.
+    ///
+    /// Decoding batches compressed with LZ4 or ZSTD requires the `arrow-compression` feature.
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -47,28 +243,204 @@ impl ArrowIpcStreamResponse for reqwest::Response {
         self,
         max_obj_len: usize,
     ) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
-        let reader = tokio_util::io::StreamReader::new(
-            self.bytes_stream()
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)),
+        self.arrow_ipc_stream_with_capacity(max_obj_len, INITIAL_CAPACITY)
+    }
+
+    fn arrow_ipc_stream_with_capacity<'a>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
+        let codec = traced_codec(
+            ArrowIpcCodec::new_with_max_length(max_obj_len),
+            "arrow_ipc",
+            max_obj_len,
         );
 
-        let codec = ArrowIpcCodec::new_with_max_length(max_obj_len);
-        let frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+        stream_with_codec_and_capacity(self, codec, buf_capacity)
+    }
 
-        Box::pin(frames_reader.into_stream())
+    fn arrow_ipc_stream_strict<'a>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
+        match check_content_type(&self, content_type.unwrap_or(ARROW_IPC_CONTENT_TYPE)) {
+            Ok(()) => self.arrow_ipc_stream(max_obj_len),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    /// Streams the response as batches of Arrow IPC messages, returning the schema of the
+    /// stream along with the batches.
+    ///
+    /// The schema message is read before returning, so the schema is available even if the
+    /// stream has no batches. If the IPC stream is restarted with another schema within the
+    /// response, the schema returned is the first one. See
+    /// [`ArrowIpcStreamResponse::arrow_ipc_stream`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use arrow::array::RecordBatch;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ArrowIpcStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let (schema, stream) = reqwest::get("http://localhost:8080/arrow")
+    ///         .await?
+    ///         .arrow_ipc_stream_with_schema(MAX_OBJ_LEN)
+    ///         .await?;
+    ///     println!("Arrow schema: {:?}", schema);
+    ///     let _items: Vec<RecordBatch> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn arrow_ipc_stream_with_schema<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> StreamBodyResult<(SchemaRef, BoxStream<'a, StreamBodyResult<RecordBatch>>)> {
+        let mut reader = response_reader(self);
+
+        let mut buf = BytesMut::new();
+        let schema = loop {
+            if let Some(schema) = decode_schema(&buf)? {
+                break schema;
+            }
+            if buf.len() > max_obj_len {
+                return Err(StreamBodyError::max_len_reached(max_obj_len, max_obj_len));
+            }
+            if reader.read_buf(&mut buf).await? == 0 {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some("Arrow IPC stream ended before the schema".into()),
+                ));
+            }
+        };
+
+        let codec = traced_codec(
+            ArrowIpcCodec::new_with_max_length(max_obj_len),
+            "arrow_ipc",
+            max_obj_len,
+        );
+        let mut frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+        *frames_reader.read_buffer_mut() = buf;
+
+        Ok((schema, Box::pin(frames_reader.into_stream())))
+    }
+
+    fn arrow_ipc_stream_with_decoded_dictionaries<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
+        Box::pin(
+            self.arrow_ipc_stream(max_obj_len)
+                .and_then(|batch| futures::future::ready(decode_dictionaries(batch))),
+        )
+    }
+
+    fn arrow_file_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
+        let reader = response_reader(self);
+
+        let batches = async move {
+            let mut buf = Vec::new();
+            reader
+                .take(max_obj_len as u64 + 1)
+                .read_to_end(&mut buf)
+                .await?;
+            if buf.len() > max_obj_len {
+                return Err(StreamBodyError::max_len_reached(max_obj_len, max_obj_len));
+            }
+
+            let file_reader = FileReader::try_new(Cursor::new(buf), None).map_err(arrow_error)?;
+            Ok(futures::stream::iter(
+                file_reader.map(|batch| batch.map_err(arrow_error)),
+            ))
+        };
+
+        Box::pin(futures::stream::once(batches).try_flatten())
+    }
+
+    #[cfg(feature = "arrow-serde")]
+    fn arrow_ipc_rows_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let rows_stream = self
+            .arrow_ipc_stream(max_obj_len)
+            .and_then(|batch| async move {
+                serde_arrow::from_record_batch::<Vec<T>>(&batch).map_err(|err| {
+                    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+                })
+            })
+            .map_ok(|rows| futures::stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten();
+
+        Box::pin(rows_stream)
     }
 }
 
+/// Casts the dictionary-encoded columns of `batch` to the types of their values.
+fn decode_dictionaries(batch: RecordBatch) -> StreamBodyResult<RecordBatch> {
+    let schema = batch.schema();
+    if !schema
+        .fields()
+        .iter()
+        .any(|field| matches!(field.data_type(), DataType::Dictionary(_, _)))
+    {
+        return Ok(batch);
+    }
+
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        match field.data_type() {
+            DataType::Dictionary(_, value_type) => {
+                columns.push(arrow::compute::cast(column, value_type).map_err(arrow_error)?);
+                fields.push(
+                    field
+                        .as_ref()
+                        .clone()
+                        .with_data_type(value_type.as_ref().clone()),
+                );
+            }
+            _ => {
+                columns.push(column.clone());
+                fields.push(field.as_ref().clone());
+            }
+        }
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+
+    RecordBatch::try_new(Arc::new(schema), columns).map_err(arrow_error)
+}
+
+fn arrow_error(err: ArrowError) -> StreamBodyError {
+    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test_client::*;
-    use arrow::array::{Float64Array, Int64Array, StringArray};
-    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use arrow::array::{DictionaryArray, Float64Array, Int64Array, StringArray};
+    use arrow::datatypes::{Field, Int32Type};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+    use axum::body::Body;
     use axum::{routing::*, Router};
     use axum_streams::*;
-    use futures::stream;
-    use std::sync::Arc;
+    use futures::{stream, StreamExt};
 
     fn generate_test_schema() -> SchemaRef {
         Arc::new(Schema::new(vec![
@@ -117,6 +489,70 @@ mod tests {
         assert_eq!(items, test_stream_vec);
     }
 
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_with_capacity() {
+        let test_stream_vec = generate_test_batches();
+
+        let test_schema = generate_test_schema();
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::arrow_ipc(test_schema, test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_ipc_stream_with_capacity(1024, 8);
+
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_strict() {
+        let test_stream_vec = generate_test_batches();
+
+        let test_schema = generate_test_schema();
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::arrow_ipc(test_schema, test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_ipc_stream_strict(1024, None);
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_ipc_stream_strict(1024, Some("application/vnd.apache.arrow.file"));
+        let err = res
+            .try_collect::<Vec<RecordBatch>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+    }
+
     #[tokio::test]
     async fn deserialize_arrow_ipc_stream_check_max_len() {
         let test_stream_vec = generate_test_batches();
@@ -132,8 +568,342 @@ mod tests {
         let client = TestClient::new(app).await;
 
         let res = client.get("/").send().await.unwrap().arrow_ipc_stream(10);
-        res.try_collect::<Vec<RecordBatch>>()
+        let err = res
+            .try_collect::<Vec<RecordBatch>>()
             .await
             .expect_err("MaxLenReachedError");
+
+        assert_eq!(err.limit(), Some(10));
+        assert!(err.position().is_some());
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_empty_body() {
+        let app = Router::new().route("/", get(|| async { "" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap().arrow_ipc_stream(1024);
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert!(items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_with_schema_change() {
+        let test_stream_vec = generate_test_batches();
+        let other_schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let other_batch = RecordBatch::try_new(
+            other_schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+
+        // Three IPC streams, the second one without its end-of-stream marker
+        let mut test_body = Vec::new();
+        let mut writer = StreamWriter::try_new(&mut test_body, &generate_test_schema()).unwrap();
+        writer.write(&test_stream_vec[0]).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        let mut writer = StreamWriter::try_new(&mut test_body, &other_schema).unwrap();
+        writer.write(&other_batch).unwrap();
+        writer.write(&other_batch).unwrap();
+        drop(writer);
+        let mut writer = StreamWriter::try_new(&mut test_body, &generate_test_schema()).unwrap();
+        writer.write(&test_stream_vec[1]).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let app = Router::new().route(
+            "/",
+            get(|| async move {
+                let chunks: Vec<Vec<u8>> =
+                    test_body.chunks(5).map(|chunk| chunk.to_vec()).collect();
+                Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>)))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap().arrow_ipc_stream(1024);
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                test_stream_vec[0].clone(),
+                other_batch.clone(),
+                other_batch,
+                test_stream_vec[1].clone()
+            ]
+        );
+        assert_eq!(items[1].schema(), other_schema);
+        assert_eq!(items[3].schema(), generate_test_schema());
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_truncated() {
+        let test_stream_vec = generate_test_batches();
+
+        let mut test_body = Vec::new();
+        let mut writer = StreamWriter::try_new(&mut test_body, &generate_test_schema()).unwrap();
+        for batch in &test_stream_vec[..2] {
+            writer.write(batch).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        // Cut the second batch, before the end of stream marker
+        test_body.truncate(test_body.len() - 8 - 16);
+
+        let app = Router::new().route("/", get(|| async { test_body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap().arrow_ipc_stream(1024);
+        let results: Vec<StreamBodyResult<RecordBatch>> = res.collect().await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &test_stream_vec[0]);
+        assert!(results[1].as_ref().unwrap_err().is_unexpected_eof());
+    }
+
+    fn generate_test_file(batches: &[RecordBatch]) -> Vec<u8> {
+        let mut file = Vec::new();
+        let mut writer = FileWriter::try_new(&mut file, &generate_test_schema()).unwrap();
+        for batch in batches {
+            writer.write(batch).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        file
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_file_stream() {
+        let test_stream_vec = generate_test_batches();
+        let test_file = generate_test_file(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { test_file }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_file_stream(1024 * 1024);
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_file_stream(1024);
+        let err = res
+            .try_collect::<Vec<RecordBatch>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_file_stream_from_ipc_stream() {
+        let test_schema = generate_test_schema();
+        let test_stream = Box::pin(stream::iter(generate_test_batches()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::arrow_ipc(test_schema, test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_file_stream(1024 * 1024);
+        let err = res
+            .try_collect::<Vec<RecordBatch>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_with_schema() {
+        let test_stream_vec = generate_test_batches();
+
+        let test_schema = generate_test_schema();
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::arrow_ipc(test_schema, test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let (schema, res) = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_ipc_stream_with_schema(1024)
+            .await
+            .unwrap();
+
+        assert_eq!(schema.fields(), generate_test_schema().fields());
+
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_with_decoded_dictionaries() {
+        let dictionary_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new(
+                "city",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                false,
+            ),
+        ]));
+        let cities = ["New York", "London", "Gothenburg"];
+        let dictionary_batches: Vec<RecordBatch> = (0i64..10i64)
+            .map(|idx| {
+                let city: DictionaryArray<Int32Type> = (0..4)
+                    .map(|row| cities[(idx as usize + row) % cities.len()])
+                    .collect();
+                RecordBatch::try_new(
+                    dictionary_schema.clone(),
+                    vec![Arc::new(Int64Array::from(vec![idx; 4])), Arc::new(city)],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let expected_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("city", DataType::Utf8, false),
+        ]));
+        let expected_batches: Vec<RecordBatch> = (0i64..10i64)
+            .map(|idx| {
+                let city: StringArray = (0..4)
+                    .map(|row| Some(cities[(idx as usize + row) % cities.len()]))
+                    .collect();
+                RecordBatch::try_new(
+                    expected_schema.clone(),
+                    vec![Arc::new(Int64Array::from(vec![idx; 4])), Arc::new(city)],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let test_stream = Box::pin(stream::iter(dictionary_batches));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::arrow_ipc(dictionary_schema, test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_ipc_stream_with_decoded_dictionaries(1024);
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, expected_batches);
+    }
+
+    #[cfg(feature = "arrow-serde")]
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_rows_stream() {
+        #[derive(Debug, Clone, serde::Deserialize, PartialEq)]
+        struct City {
+            id: i64,
+            city: String,
+            lat: f64,
+            lng: f64,
+        }
+
+        let test_stream_vec = generate_test_batches();
+
+        let test_schema = generate_test_schema();
+        let test_stream = Box::pin(stream::iter(test_stream_vec));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::arrow_ipc(test_schema, test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .arrow_ipc_rows_stream::<City>(1024);
+
+        let items: Vec<City> = res.try_collect().await.unwrap();
+
+        assert_eq!(items.len(), 300);
+        assert_eq!(
+            items[4],
+            City {
+                id: 2,
+                city: "London".to_string(),
+                lat: 51.5074,
+                lng: -0.1278,
+            }
+        );
+    }
+
+    #[cfg(feature = "arrow-compression")]
+    #[tokio::test]
+    async fn deserialize_arrow_ipc_stream_compressed() {
+        use arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
+        use arrow::ipc::CompressionType;
+
+        for compression in [CompressionType::LZ4_FRAME, CompressionType::ZSTD] {
+            let test_stream_vec = generate_test_batches();
+
+            let write_options = IpcWriteOptions::default()
+                .try_with_compression(Some(compression))
+                .unwrap();
+            let mut writer = StreamWriter::try_new_with_options(
+                Vec::new(),
+                &generate_test_schema(),
+                write_options,
+            )
+            .unwrap();
+            for batch in test_stream_vec.iter() {
+                writer.write(batch).unwrap();
+            }
+            let body = writer.into_inner().unwrap();
+
+            let app = Router::new().route("/", get(|| async { body }));
+
+            let client = TestClient::new(app).await;
+
+            let res = client.get("/").send().await.unwrap().arrow_ipc_stream(1024);
+
+            let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+            assert_eq!(items, test_stream_vec);
+        }
     }
 }
@@ -0,0 +1,221 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use apache_avro::{GenericSingleObjectReader, Schema};
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Decodes concatenated Avro records of the [single-object encoding], each starting with the
+/// `C3 01` marker and the fingerprint of the schema.
+///
+/// The records aren't prefixed with their length, so a record is decoded again from its start
+/// when more bytes are needed to complete it.
+///
+/// [single-object encoding]: https://avro.apache.org/docs/1.11.1/specification/#single-object-encoding
+pub struct AvroSingleObjectCodec<T> {
+    reader: GenericSingleObjectReader,
+    max_length: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> AvroSingleObjectCodec<T> {
+    pub fn new_with_max_length(
+        max_length: usize,
+        schema: &Schema,
+    ) -> Result<Self, StreamBodyError> {
+        let reader = GenericSingleObjectReader::new(schema.clone()).map_err(avro_error)?;
+
+        Ok(AvroSingleObjectCodec {
+            reader,
+            max_length,
+            consumed_len: 0,
+            _ph: PhantomData,
+        })
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for AvroSingleObjectCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        let mut record = RecordReader::new(buf.as_ref());
+        let result = self.reader.read_value(&mut record);
+        if record.exhausted {
+            // The decoder returns the values read so far for some truncated fields, so the
+            // record is incomplete regardless of the result
+            if buf.len() > self.max_length {
+                return Err(StreamBodyError::max_len_reached(
+                    self.max_length,
+                    self.consumed_len,
+                ));
+            }
+            return Ok(None); // wait more bytes for the record
+        }
+
+        match result {
+            Ok(value) => {
+                let record_len = buf.len() - record.bytes.len();
+                if record_len > self.max_length {
+                    return Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.consumed_len,
+                    ));
+                }
+                buf.advance(record_len);
+                self.consumed_len += record_len;
+                apache_avro::from_value::<T>(&value)
+                    .map(Some)
+                    .map_err(avro_error)
+            }
+            Err(err) => Err(avro_error(err)),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            None if !buf.is_empty() => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some("Incomplete Avro record at the end of the stream".into()),
+            )),
+            result => Ok(result),
+        }
+    }
+}
+
+/// Reads a record from the buffered bytes, noting whether more bytes than buffered were needed.
+struct RecordReader<'a> {
+    bytes: &'a [u8],
+    exhausted: bool,
+}
+
+impl<'a> RecordReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        RecordReader {
+            bytes,
+            exhausted: false,
+        }
+    }
+}
+
+impl std::io::Read for RecordReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if out.len() > self.bytes.len() {
+            self.exhausted = true;
+        }
+        self.bytes.read(out)
+    }
+}
+
+fn avro_error(err: apache_avro::Error) -> StreamBodyError {
+    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apache_avro::GenericSingleObjectWriter;
+    use serde::Serialize;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+    struct MyTestStructure {
+        some_test_field: String,
+        some_test_num: f64,
+    }
+
+    fn generate_test_schema() -> Schema {
+        Schema::parse_str(
+            r#"{
+                "type": "record",
+                "name": "MyTestStructure",
+                "fields": [
+                    {"name": "some_test_field", "type": "string"},
+                    {"name": "some_test_num", "type": "double"}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn generate_test_records() -> (Vec<MyTestStructure>, Vec<u8>) {
+        let items: Vec<MyTestStructure> = (0..3)
+            .map(|idx| MyTestStructure {
+                some_test_field: format!("TestValue{}", idx),
+                some_test_num: idx as f64 * 1.5,
+            })
+            .collect();
+
+        let mut writer =
+            GenericSingleObjectWriter::new_with_capacity(&generate_test_schema(), 1024).unwrap();
+        let mut records = Vec::new();
+        for item in &items {
+            writer
+                .write_value(apache_avro::to_value(item).unwrap(), &mut records)
+                .unwrap();
+        }
+        (items, records)
+    }
+
+    #[test]
+    fn decode_avro_records_across_chunks() {
+        let (expected_items, records) = generate_test_records();
+
+        for chunk_size in [1, 7, records.len()] {
+            let mut codec = AvroSingleObjectCodec::<MyTestStructure>::new_with_max_length(
+                1024,
+                &generate_test_schema(),
+            )
+            .unwrap();
+            let mut buf = BytesMut::new();
+            let mut items = Vec::new();
+            for chunk in records.chunks(chunk_size) {
+                buf.extend_from_slice(chunk);
+                while let Some(item) = codec.decode(&mut buf).unwrap() {
+                    items.push(item);
+                }
+            }
+            assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+
+            assert_eq!(items, expected_items);
+        }
+    }
+
+    #[test]
+    fn decode_avro_records_truncated() {
+        let (_, records) = generate_test_records();
+
+        let mut codec = AvroSingleObjectCodec::<MyTestStructure>::new_with_max_length(
+            1024,
+            &generate_test_schema(),
+        )
+        .unwrap();
+        let mut buf = BytesMut::from(&records[..records.len() - 1]);
+        while codec.decode(&mut buf).unwrap().is_some() {}
+        let err = codec.decode_eof(&mut buf).expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[test]
+    fn decode_avro_records_schema_mismatch() {
+        let (_, records) = generate_test_records();
+        let other_schema = Schema::parse_str(r#"{"type": "string"}"#).unwrap();
+
+        let mut codec =
+            AvroSingleObjectCodec::<String>::new_with_max_length(1024, &other_schema).unwrap();
+        let mut buf = BytesMut::from(&records[..]);
+        let err = codec.decode(&mut buf).expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+}
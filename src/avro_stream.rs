@@ -0,0 +1,187 @@
+use crate::avro_codec::AvroSingleObjectCodec;
+use crate::CodecStreamResponse;
+use crate::StreamBodyResult;
+use apache_avro::Schema;
+use async_trait::*;
+use futures::stream::BoxStream;
+use serde::Deserialize;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for [Apache Avro]
+/// records.
+///
+/// [Apache Avro]: https://avro.apache.org/docs/1.11.1/specification/
+#[async_trait]
+pub trait AvroStreamResponse {
+    /// Streams the response as concatenated Avro records of the single-object encoding.
+    ///
+    /// Every record starts with the `C3 01` marker and the Rabin fingerprint of the `schema`
+    /// the record was written with, so the records written with other schemas are codec
+    /// errors. The stream will [`Deserialize`] entries as type `T` with a maximum size of
+    /// `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use apache_avro::Schema;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::AvroStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let schema = Schema::parse_str(
+    ///         r#"{
+    ///             "type": "record",
+    ///             "name": "MyTestStructure",
+    ///             "fields": [{"name": "some_test_field", "type": "string"}]
+    ///         }"#,
+    ///     )?;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/avro")
+    ///         .await?
+    ///         .avro_stream::<MyTestStructure>(MAX_OBJ_LEN, &schema);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn avro_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        schema: &Schema,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+#[async_trait]
+impl AvroStreamResponse for reqwest::Response {
+    fn avro_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        schema: &Schema,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        match AvroSingleObjectCodec::<T>::new_with_max_length(max_obj_len, schema) {
+            Ok(codec) => self.stream_with_codec(codec),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use apache_avro::GenericSingleObjectWriter;
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use futures::{stream, TryStreamExt};
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field1: String,
+        some_test_field2: String,
+    }
+
+    fn generate_test_schema() -> Schema {
+        Schema::parse_str(
+            r#"{
+                "type": "record",
+                "name": "MyTestStructure",
+                "fields": [
+                    {"name": "some_test_field1", "type": "string"},
+                    {"name": "some_test_field2", "type": "string"}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    fn encode_records(items: &[MyTestStructure]) -> Vec<u8> {
+        let mut writer =
+            GenericSingleObjectWriter::new_with_capacity(&generate_test_schema(), 1024).unwrap();
+        let mut body = Vec::new();
+        for item in items {
+            writer
+                .write_value(apache_avro::to_value(item).unwrap(), &mut body)
+                .unwrap();
+        }
+        body
+    }
+
+    #[tokio::test]
+    async fn deserialize_avro_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        // The chunks don't match the records, so the headers and the records are split
+        let chunks: Vec<Vec<u8>> = encode_records(&test_stream_vec)
+            .chunks(7)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>)))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .avro_stream::<MyTestStructure>(1024, &generate_test_schema());
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_avro_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let body = encode_records(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .avro_stream::<MyTestStructure>(10, &generate_test_schema());
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert_eq!(err.limit(), Some(10));
+        assert_eq!(err.position(), Some(0));
+    }
+}
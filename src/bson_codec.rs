@@ -0,0 +1,152 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use bytes::BytesMut;
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+// The smallest BSON document: the length and the trailing zero byte
+const MIN_DOCUMENT_LEN: usize = 5;
+
+/// Decodes concatenated BSON documents, each starting with its little-endian 32-bit length
+/// including the length itself.
+#[derive(Clone, Debug)]
+pub struct BsonDocCodec<T> {
+    max_length: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> BsonDocCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        BsonDocCodec {
+            max_length,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for BsonDocCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if buf.len() < 4 {
+            return Ok(None); // wait more bytes for len
+        }
+
+        let doc_len = i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+        let doc_len = match usize::try_from(doc_len) {
+            Ok(doc_len) if doc_len >= MIN_DOCUMENT_LEN => doc_len,
+            _ => {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some(format!("Invalid BSON document length: {}", doc_len)),
+                ))
+            }
+        };
+
+        if doc_len > self.max_length {
+            Err(StreamBodyError::max_len_reached(
+                self.max_length,
+                self.consumed_len,
+            ))
+        } else if buf.len() >= doc_len {
+            let doc_bytes = buf.split_to(doc_len);
+            self.consumed_len += doc_len;
+            bson::from_slice::<T>(&doc_bytes).map(Some).map_err(|err| {
+                StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+            })
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            None if !buf.is_empty() => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some("Incomplete BSON document at the end of the stream".into()),
+            )),
+            result => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+    struct MyTestStructure {
+        some_test_field: String,
+        some_test_num: f64,
+    }
+
+    fn generate_test_documents() -> (Vec<MyTestStructure>, Vec<u8>) {
+        let items: Vec<MyTestStructure> = (0..10)
+            .map(|idx| MyTestStructure {
+                some_test_field: format!("TestValue{}", idx),
+                some_test_num: idx as f64 * 1.5,
+            })
+            .collect();
+        let mut documents = Vec::new();
+        for item in items.iter() {
+            documents.extend_from_slice(&bson::to_vec(item).unwrap());
+        }
+        (items, documents)
+    }
+
+    #[test]
+    fn decode_bson_documents_across_chunks() {
+        let (expected_items, documents) = generate_test_documents();
+
+        for chunk_size in [1, 3, 7, 64] {
+            let mut codec = BsonDocCodec::<MyTestStructure>::new_with_max_length(1024);
+            let mut buf = BytesMut::new();
+            let mut items = Vec::new();
+            for chunk in documents.chunks(chunk_size) {
+                buf.extend_from_slice(chunk);
+                while let Some(item) = codec.decode(&mut buf).unwrap() {
+                    items.push(item);
+                }
+            }
+            while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+                items.push(item);
+            }
+
+            assert_eq!(items, expected_items);
+        }
+    }
+
+    #[test]
+    fn decode_bson_documents_truncated() {
+        let (_, documents) = generate_test_documents();
+
+        let mut codec = BsonDocCodec::<MyTestStructure>::new_with_max_length(1024);
+        let mut buf = BytesMut::from(&documents[..documents.len() - 1]);
+        let mut result = Ok(None);
+        for _ in 0..10 {
+            result = codec.decode_eof(&mut buf);
+        }
+
+        result.expect_err("Incomplete BSON document");
+    }
+
+    #[test]
+    fn decode_bson_invalid_length() {
+        let mut codec = BsonDocCodec::<MyTestStructure>::new_with_max_length(1024);
+        let mut buf = BytesMut::from(&(-1i32).to_le_bytes()[..]);
+
+        let err = codec.decode(&mut buf).expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+}
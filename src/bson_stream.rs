@@ -0,0 +1,152 @@
+use crate::bson_codec::BsonDocCodec;
+use crate::stream_tracing::traced_codec;
+use crate::CodecStreamResponse;
+use crate::StreamBodyResult;
+use async_trait::*;
+use futures::stream::BoxStream;
+use serde::Deserialize;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for sequences of
+/// [BSON] documents.
+///
+/// [BSON]: https://bsonspec.org/spec.html
+#[async_trait]
+pub trait BsonStreamResponse {
+    /// Streams the response as concatenated BSON documents, each prefixed with its length.
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::BsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/bson")
+    ///         .await?
+    ///         .bson_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn bson_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+#[async_trait]
+impl BsonStreamResponse for reqwest::Response {
+    fn bson_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            BsonDocCodec::<T>::new_with_max_length(max_obj_len),
+            "bson",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use futures::{stream, TryStreamExt};
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field1: String,
+        some_test_field2: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    fn encode_documents(items: &[MyTestStructure]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for item in items {
+            body.extend_from_slice(&bson::to_vec(item).unwrap());
+        }
+        body
+    }
+
+    #[tokio::test]
+    async fn deserialize_bson_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        // The chunks don't match the documents, so the lengths and the documents are split
+        let chunks: Vec<Vec<u8>> = encode_documents(&test_stream_vec)
+            .chunks(7)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>)))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .bson_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_bson_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let body = encode_documents(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .bson_stream::<MyTestStructure>(10);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert_eq!(err.limit(), Some(10));
+        assert_eq!(err.position(), Some(0));
+    }
+}
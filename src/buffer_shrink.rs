@@ -0,0 +1,120 @@
+use crate::StreamBodyError;
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+/// Wraps the `codec` of a stream to shrink the decoding buffer back to `buf_capacity` at the
+/// frame boundaries, once the buffer has grown over `shrink_threshold` bytes.
+///
+/// The buffer of a [`tokio_util::codec::FramedRead`] grows to fit the largest frame and keeps
+/// that capacity for the rest of the stream. When a frame is decoded, the bytes still buffered
+/// for the next frames are moved to a new buffer of the initial capacity, so the allocation of a
+/// large frame is released as soon as the frame itself is dropped.
+#[derive(Clone, Debug)]
+pub(crate) struct ShrinkingCodec<D> {
+    codec: D,
+    buf_capacity: usize,
+    shrink_threshold: Option<usize>,
+}
+
+impl<D> ShrinkingCodec<D> {
+    pub(crate) fn new(codec: D, buf_capacity: usize, shrink_threshold: Option<usize>) -> Self {
+        ShrinkingCodec {
+            codec,
+            buf_capacity,
+            shrink_threshold,
+        }
+    }
+
+    fn shrink(&self, buf: &mut BytesMut) {
+        if let Some(shrink_threshold) = self.shrink_threshold {
+            if buf.capacity() > shrink_threshold.max(self.buf_capacity) {
+                let mut shrunk = BytesMut::with_capacity(self.buf_capacity.max(buf.len()));
+                shrunk.extend_from_slice(buf);
+                *buf = shrunk;
+            }
+        }
+    }
+}
+
+impl<D> Decoder for ShrinkingCodec<D>
+where
+    D: Decoder<Error = StreamBodyError>,
+{
+    type Item = D::Item;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<D::Item>, StreamBodyError> {
+        let result = self.codec.decode(buf);
+        if let Ok(Some(_)) = result {
+            self.shrink(buf);
+        }
+        result
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<D::Item>, StreamBodyError> {
+        let result = self.codec.decode_eof(buf);
+        if let Ok(Some(_)) = result {
+            self.shrink(buf);
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::json_nl_codec::JsonNlCodec;
+    use crate::JsonLineEnding;
+    use futures::StreamExt;
+    use tokio_util::codec::FramedRead;
+
+    /// Decodes a large line followed by small ones, returning the capacities of the buffer
+    /// after every small line.
+    async fn decode_large_then_small_lines(shrink_threshold: Option<usize>) -> Vec<usize> {
+        let mut body = format!("\"{}\"\n", "a".repeat(64 * 1024));
+        for idx in 0..100 {
+            body.push_str(&format!("{}\n", idx));
+        }
+        let codec = ShrinkingCodec::new(
+            JsonNlCodec::new_with_max_length(1024 * 1024, JsonLineEnding::Lf, false),
+            64,
+            shrink_threshold,
+        );
+        let mut frames_reader = FramedRead::with_capacity(body.as_bytes(), codec, 64);
+
+        let large_line = frames_reader.next().await.unwrap().unwrap();
+        assert_eq!(large_line.len(), 64 * 1024 + 2);
+
+        let mut capacities = Vec::new();
+        let mut idx = 0;
+        while let Some(line) = frames_reader.next().await {
+            assert_eq!(line.unwrap(), idx.to_string());
+            capacities.push(frames_reader.read_buffer().capacity());
+            idx += 1;
+        }
+        assert_eq!(idx, 100);
+        capacities
+    }
+
+    #[tokio::test]
+    async fn shrink_buffer_after_large_frame() {
+        let capacities = decode_large_then_small_lines(Some(1024)).await;
+
+        assert!(
+            capacities.iter().all(|capacity| *capacity <= 1024),
+            "{:?}",
+            capacities
+        );
+    }
+
+    #[tokio::test]
+    async fn keep_buffer_without_threshold() {
+        let capacities = decode_large_then_small_lines(None).await;
+
+        assert!(
+            capacities.iter().any(|capacity| *capacity > 1024),
+            "{:?}",
+            capacities
+        );
+    }
+}
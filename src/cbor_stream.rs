@@ -0,0 +1,139 @@
+use crate::cbor_seq_codec::CborSeqCodec;
+use crate::stream_tracing::traced_codec;
+use crate::CodecStreamResponse;
+use crate::StreamBodyResult;
+use async_trait::*;
+use futures::stream::BoxStream;
+use serde::Deserialize;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for the
+/// [CBOR sequence format].
+///
+/// [CBOR sequence format]: https://www.rfc-editor.org/rfc/rfc8742.html
+#[async_trait]
+pub trait CborStreamResponse {
+    /// Streams the response as a CBOR sequence, where self-delimiting CBOR items are
+    /// concatenated without any separator.
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::CborStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/cbor")
+    ///         .await?
+    ///         .cbor_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn cbor_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+#[async_trait]
+impl CborStreamResponse for reqwest::Response {
+    fn cbor_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            CborSeqCodec::<T>::new_with_max_length(max_obj_len),
+            "cbor",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::{routing::*, Router};
+    use futures::TryStreamExt;
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field1: String,
+        some_test_field2: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    fn encode_sequence(items: &[MyTestStructure]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for item in items {
+            ciborium::ser::into_writer(item, &mut body).unwrap();
+        }
+        body
+    }
+
+    #[tokio::test]
+    async fn deserialize_cbor_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let body = encode_sequence(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .cbor_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_cbor_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let body = encode_sequence(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .cbor_stream::<MyTestStructure>(10);
+        res.try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+    }
+}
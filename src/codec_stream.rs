@@ -0,0 +1,143 @@
+use crate::response_reader::{response_reader, ResponseReader, INITIAL_CAPACITY};
+use crate::{StreamBodyError, StreamBodyResult};
+use async_trait::*;
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use tokio_util::codec::Decoder;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for custom formats.
+#[async_trait]
+pub trait CodecStreamResponse {
+    /// Streams the response decoding the frames with the given [`Decoder`].
+    ///
+    /// This is the building block of all the formats of this crate. The response body is
+    /// read (and decompressed with the `compression` feature) and fed to `codec`, which
+    /// yields an item for every frame decoded.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use bytes::{Buf, BytesMut};
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::error::StreamBodyError;
+    /// use reqwest_streams::CodecStreamResponse as _;
+    /// use tokio_util::codec::Decoder;
+    ///
+    /// // Frames of a single byte length followed by the payload
+    /// struct ShortFramesCodec;
+    ///
+    /// impl Decoder for ShortFramesCodec {
+    ///     type Item = Vec<u8>;
+    ///     type Error = StreamBodyError;
+    ///
+    ///     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Vec<u8>>, StreamBodyError> {
+    ///         match buf.first() {
+    ///             Some(len) if buf.len() > *len as usize => {
+    ///                 let frame = buf.split_to(*len as usize + 1);
+    ///                 Ok(Some(frame[1..].to_vec()))
+    ///             }
+    ///             _ => Ok(None),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let stream = reqwest::get("http://localhost:8080/short-frames")
+    ///         .await?
+    ///         .stream_with_codec(ShortFramesCodec);
+    ///     let _frames: Vec<Vec<u8>> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn stream_with_codec<'a, D>(self, codec: D) -> BoxStream<'a, StreamBodyResult<D::Item>>
+    where
+        D: Decoder<Error = StreamBodyError> + Send + 'a,
+        D::Item: Send + 'a;
+}
+
+#[async_trait]
+impl CodecStreamResponse for reqwest::Response {
+    fn stream_with_codec<'a, D>(self, codec: D) -> BoxStream<'a, StreamBodyResult<D::Item>>
+    where
+        D: Decoder<Error = StreamBodyError> + Send + 'a,
+        D::Item: Send + 'a,
+    {
+        stream_with_codec_and_capacity(self, codec, INITIAL_CAPACITY)
+    }
+}
+
+/// Streams the response decoding the frames with the given [`Decoder`], starting with a
+/// decoding buffer of `buf_capacity` bytes.
+pub(crate) fn stream_with_codec_and_capacity<'a, D>(
+    response: reqwest::Response,
+    codec: D,
+    buf_capacity: usize,
+) -> BoxStream<'a, StreamBodyResult<D::Item>>
+where
+    D: Decoder<Error = StreamBodyError> + Send + 'a,
+    D::Item: Send + 'a,
+{
+    reader_stream_with_codec_and_capacity(response_reader(response), codec, buf_capacity)
+}
+
+/// Streams the body read by `reader` as [`stream_with_codec_and_capacity`] does, for the
+/// readers with options of their own, such as a read timeout.
+pub(crate) fn reader_stream_with_codec_and_capacity<'a, D>(
+    reader: ResponseReader,
+    codec: D,
+    buf_capacity: usize,
+) -> BoxStream<'a, StreamBodyResult<D::Item>>
+where
+    D: Decoder<Error = StreamBodyError> + Send + 'a,
+    D::Item: Send + 'a,
+{
+    let frames_reader = tokio_util::codec::FramedRead::with_capacity(reader, codec, buf_capacity);
+
+    Box::pin(frames_reader.into_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::{routing::*, Router};
+    use bytes::{Buf, BytesMut};
+
+    // Frames of the payloads terminated by `;`
+    struct SemicolonCodec;
+
+    impl Decoder for SemicolonCodec {
+        type Item = String;
+        type Error = StreamBodyError;
+
+        fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<String>, StreamBodyError> {
+            match buf.iter().position(|ch| *ch == b';') {
+                Some(pos) => {
+                    let frame = buf.split_to(pos);
+                    buf.advance(1);
+                    Ok(Some(String::from_utf8_lossy(&frame).into_owned()))
+                }
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_with_custom_codec() {
+        let app = Router::new().route("/", get(|| async { "first;second;third;" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_with_codec(SemicolonCodec);
+        let items: Vec<String> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, vec!["first", "second", "third"]);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::error::StreamBodyKind;
+use crate::utf8_bom::strip_utf8_bom;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use csv::{ByteRecord, StringRecord};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+pub struct CsvCodec<T> {
+    record_codec: CsvRecordCodec,
+    with_csv_header: bool,
+    headers: Option<ByteRecord>,
+    _ph: PhantomData<fn() -> T>,
+}
+
+impl<T> CsvCodec<T> {
+    pub fn new_with_max_length(
+        max_length: usize,
+        with_csv_header: bool,
+        flexible: bool,
+        reader: csv_core::Reader,
+    ) -> Self {
+        CsvCodec {
+            record_codec: CsvRecordCodec::new_with_max_length(max_length, flexible, reader),
+            with_csv_header,
+            headers: None,
+            _ph: PhantomData,
+        }
+    }
+
+    /// Set whether a UTF-8 byte order mark at the start of the stream is stripped, see
+    /// [`CsvRecordCodec::with_strip_bom`].
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.record_codec = self.record_codec.with_strip_bom(strip_bom);
+        self
+    }
+
+    /// Continues decoding records of `record_codec` whose header record was already read.
+    pub fn with_headers(record_codec: CsvRecordCodec, headers: ByteRecord) -> Self {
+        CsvCodec {
+            record_codec,
+            with_csv_header: true,
+            headers: Some(headers),
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<T> CsvCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    fn decode_record(&mut self, record: Option<ByteRecord>) -> Result<Option<T>, StreamBodyError> {
+        match record {
+            Some(record) if self.with_csv_header && self.headers.is_none() => {
+                self.headers = Some(record);
+                Ok(None)
+            }
+            // Records are deserialized by the header names if there is a header
+            Some(record) => record
+                .deserialize::<T>(self.headers.as_ref())
+                .map(Some)
+                .map_err(|err| {
+                    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+                }),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for CsvCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        loop {
+            let record = self.record_codec.decode(buf)?;
+            let record_found = record.is_some();
+            match self.decode_record(record)? {
+                Some(item) => return Ok(Some(item)),
+                None if record_found => continue, // the header
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        loop {
+            let record = self.record_codec.decode_eof(buf)?;
+            let record_found = record.is_some();
+            match self.decode_record(record)? {
+                Some(item) => return Ok(Some(item)),
+                None if record_found => continue, // the header
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Decodes CSV records as [`StringRecord`]s, without deserializing them.
+pub struct CsvStringRecordCodec {
+    record_codec: CsvRecordCodec,
+    header_skipped: bool,
+}
+
+impl CsvStringRecordCodec {
+    pub fn new_with_max_length(
+        max_length: usize,
+        with_csv_header: bool,
+        flexible: bool,
+        reader: csv_core::Reader,
+    ) -> Self {
+        CsvStringRecordCodec {
+            record_codec: CsvRecordCodec::new_with_max_length(max_length, flexible, reader),
+            header_skipped: !with_csv_header,
+        }
+    }
+
+    fn decode_record(
+        &mut self,
+        record: Option<ByteRecord>,
+    ) -> Result<Option<StringRecord>, StreamBodyError> {
+        match record {
+            Some(_) if !self.header_skipped => {
+                self.header_skipped = true;
+                Ok(None)
+            }
+            Some(record) => StringRecord::from_byte_record(record)
+                .map(Some)
+                .map_err(|err| {
+                    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+                }),
+            None => Ok(None),
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for CsvStringRecordCodec {
+    type Item = StringRecord;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<StringRecord>, StreamBodyError> {
+        loop {
+            let record = self.record_codec.decode(buf)?;
+            let record_found = record.is_some();
+            match self.decode_record(record)? {
+                Some(item) => return Ok(Some(item)),
+                None if record_found => continue, // the header
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<StringRecord>, StreamBodyError> {
+        loop {
+            let record = self.record_codec.decode_eof(buf)?;
+            let record_found = record.is_some();
+            match self.decode_record(record)? {
+                Some(item) => return Ok(Some(item)),
+                None if record_found => continue, // the header
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Frames CSV records respecting the quoting rules, so a quoted field may contain
+/// delimiters and newlines.
+///
+/// A UTF-8 byte order mark at the start of the stream is stripped, even if it is split
+/// across the chunks of the body, unless disabled with [`CsvRecordCodec::with_strip_bom`].
+pub struct CsvRecordCodec {
+    max_length: usize,
+    flexible: bool,
+    fields_len: Option<usize>,
+    reader: csv_core::Reader,
+    output: Vec<u8>,
+    output_len: usize,
+    ends: Vec<usize>,
+    ends_len: usize,
+    record_len: usize,
+    consumed_len: usize,
+    strip_bom: bool,
+    bom_checked: bool,
+}
+
+impl CsvRecordCodec {
+    pub fn new_with_max_length(
+        max_length: usize,
+        flexible: bool,
+        reader: csv_core::Reader,
+    ) -> Self {
+        CsvRecordCodec {
+            max_length,
+            flexible,
+            fields_len: None,
+            reader,
+            output: vec![0; 1024],
+            output_len: 0,
+            ends: vec![0; 32],
+            ends_len: 0,
+            record_len: 0,
+            consumed_len: 0,
+            strip_bom: true,
+            bom_checked: false,
+        }
+    }
+
+    /// Set whether a UTF-8 byte order mark at the start of the stream is stripped, which is
+    /// the default.
+    ///
+    /// If not, the mark is a part of the first field.
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.strip_bom = strip_bom;
+        self.bom_checked = !strip_bom;
+        self
+    }
+
+    fn read_record(
+        &mut self,
+        buf: &mut BytesMut,
+        eof: bool,
+    ) -> Result<Option<ByteRecord>, StreamBodyError> {
+        // The CSV reader strips the mark only if it is read at once
+        if !self.bom_checked {
+            match strip_utf8_bom(buf, eof) {
+                Some(bom_len) => self.consumed_len += bom_len,
+                None => return Ok(None),
+            }
+            self.bom_checked = true;
+        }
+
+        loop {
+            // An empty input means the end of data for the CSV reader
+            if buf.is_empty() && !eof {
+                return Ok(None);
+            }
+
+            // The CSV reader strips a mark at the start of its first input, so the first byte
+            // is read alone when the mark is kept
+            let input = if !self.strip_bom && self.consumed_len == 0 {
+                &buf[..buf.len().min(1)]
+            } else {
+                buf.as_ref()
+            };
+            let (result, nin, nout, nend) = self.reader.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            buf.advance(nin);
+            self.record_len += nin;
+            self.consumed_len += nin;
+            self.output_len += nout;
+            self.ends_len += nend;
+
+            if self.record_len > self.max_length {
+                return Err(StreamBodyError::max_len_reached(
+                    self.max_length,
+                    (self.consumed_len - self.record_len).saturating_add(self.max_length),
+                ));
+            }
+
+            match result {
+                csv_core::ReadRecordResult::InputEmpty => {
+                    if eof {
+                        continue;
+                    }
+                    return Ok(None);
+                }
+                csv_core::ReadRecordResult::OutputFull => {
+                    let new_len = self.output.len() * 2;
+                    self.output.resize(new_len, 0);
+                }
+                csv_core::ReadRecordResult::OutputEndsFull => {
+                    let new_len = self.ends.len() * 2;
+                    self.ends.resize(new_len, 0);
+                }
+                csv_core::ReadRecordResult::Record => {
+                    let mut record = ByteRecord::with_capacity(self.output_len, self.ends_len);
+                    let mut field_start = 0;
+                    for field_end in self.ends[..self.ends_len].iter() {
+                        record.push_field(&self.output[field_start..*field_end]);
+                        field_start = *field_end;
+                    }
+                    self.output_len = 0;
+                    self.ends_len = 0;
+                    self.record_len = 0;
+                    self.check_fields_len(&record)?;
+                    return Ok(Some(record));
+                }
+                csv_core::ReadRecordResult::End => return Ok(None),
+            }
+        }
+    }
+
+    /// Unless flexible, every record must have the same number of fields as the first one.
+    fn check_fields_len(&mut self, record: &ByteRecord) -> Result<(), StreamBodyError> {
+        if self.flexible {
+            return Ok(());
+        }
+        match self.fields_len {
+            Some(fields_len) if fields_len != record.len() => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some(format!(
+                    "Found record with {} fields, but the previous record has {} fields",
+                    record.len(),
+                    fields_len
+                )),
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.fields_len = Some(record.len());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for CsvRecordCodec {
+    type Item = ByteRecord;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<ByteRecord>, StreamBodyError> {
+        self.read_record(buf, false)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<ByteRecord>, StreamBodyError> {
+        self.read_record(buf, true)
+    }
+}
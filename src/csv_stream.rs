@@ -1,21 +1,28 @@
+use crate::buffer_shrink::ShrinkingCodec;
+use crate::csv_codec::{CsvCodec, CsvRecordCodec, CsvStringRecordCodec};
 use crate::error::StreamBodyKind;
+use crate::response_reader::{check_content_type, response_reader, INITIAL_CAPACITY};
+use crate::stream_tracing::traced_codec;
 use crate::{StreamBodyError, StreamBodyResult};
 use async_trait::*;
 use futures::stream::BoxStream;
 use futures::{StreamExt, TryStreamExt};
 use serde::Deserialize;
-use tokio_util::io::StreamReader;
+
+pub(crate) const CSV_CONTENT_TYPE: &str = "text/csv";
 
 /// Extension trait for [`reqwest::Response`] that provides streaming support for the CSV format.
 #[async_trait]
 pub trait CsvStreamResponse {
-    /// Streams the response as CSV, where each line is a CSV row.
+    /// Streams the response as CSV, where each record is a CSV row.
     ///
     /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
-    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a newline (`\n`)
-    /// character is reached.
+    /// bytes per record. Quoted fields may contain delimiters and newlines.
     ///
-    /// If `with_csv_header` is `true`, the stream will skip the first row (the CSV header).
+    /// If `with_csv_header` is `true`, the first record is read as the CSV header and the
+    /// remaining records are deserialized by the header names, so the order of the fields
+    /// of `T` doesn't need to match the order of the columns, and the columns without a
+    /// matching field are ignored. Otherwise, the fields are deserialized by their position.
     ///
     /// The `delimiter` is the byte value of the delimiter character.
     ///
@@ -49,94 +56,997 @@ pub trait CsvStreamResponse {
         delimiter: u8,
     ) -> BoxStream<'b, StreamBodyResult<T>>
     where
-        T: for<'de> Deserialize<'de>;
+        T: for<'de> Deserialize<'de> + 'b;
+
+    /// Streams the response as CSV, checking the content type of the response first.
+    ///
+    /// See [`CsvStreamResponse::csv_stream`] for the details. If the media type of the response
+    /// isn't `content_type` (`text/csv` if `None`), the stream yields a single
+    /// [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error instead of decoding the
+    /// body.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::CsvStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/csv")
+    ///         .await?
+    ///         .csv_stream_strict::<MyTestStructure>(MAX_OBJ_LEN, true, b',', None);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn csv_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_csv_header: bool,
+        delimiter: u8,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b;
+
+    /// Streams the response as CSV, where each record is a CSV row.
+    ///
+    /// See [`CsvStreamResponse::csv_stream`] for the details.
+    ///
+    /// `buf_capacity` is the initial capacity of the stream's decoding buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::CsvStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const INITIAL_BUF_CAPACITY: usize = 16 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/csv")
+    ///         .await?
+    ///         .csv_stream_with_capacity::<MyTestStructure>(
+    ///             MAX_OBJ_LEN,
+    ///             true,
+    ///             b',',
+    ///             INITIAL_BUF_CAPACITY,
+    ///         );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn csv_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_csv_header: bool,
+        delimiter: u8,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b;
+
+    /// Streams the response as TSV (tab-separated values), where each record is a row.
+    ///
+    /// This is the same as [`CsvStreamResponse::csv_stream`] with the `\t` delimiter.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::CsvStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/tsv")
+    ///         .await?
+    ///         .tsv_stream::<MyTestStructure>(MAX_OBJ_LEN, true);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn tsv_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_header: bool,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b;
+
+    /// Streams the response as CSV, where each record is a CSV row, using the given options.
+    ///
+    /// See [`CsvStreamResponse::csv_stream`] and [`CsvOptions`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::{CsvOptions, CsvStreamResponse as _};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/csv")
+    ///         .await?
+    ///         .csv_stream_with_options::<MyTestStructure>(
+    ///             MAX_OBJ_LEN,
+    ///             CsvOptions::new()
+    ///                 .with_quote(b'\'')
+    ///                 .with_escape(Some(b'\\'))
+    ///                 .with_comment(Some(b'#')),
+    ///         );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn csv_stream_with_options<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        options: CsvOptions,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b;
+
+    /// Streams the response as CSV with a header, returning the header names along with the
+    /// stream of the remaining records.
+    ///
+    /// The header record is read before returning, and the remaining records are deserialized
+    /// as type `T` by the header names with a maximum size of `max_obj_len` bytes per record.
+    /// An empty response results in empty headers and an empty stream.
+    ///
+    /// The `delimiter` is the byte value of the delimiter character.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::CsvStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let (headers, _stream) = reqwest::get("http://localhost:8080/csv")
+    ///         .await?
+    ///         .csv_stream_with_headers::<MyTestStructure>(MAX_OBJ_LEN, b',')
+    ///         .await?;
+    ///     println!("CSV columns: {:?}", headers);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn csv_stream_with_headers<'b, T>(
+        self,
+        max_obj_len: usize,
+        delimiter: u8,
+    ) -> StreamBodyResult<(Vec<String>, BoxStream<'b, StreamBodyResult<T>>)>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as CSV, where each record is a CSV row decoded as a
+    /// [`csv::StringRecord`] without deserializing it.
+    ///
+    /// This is useful when the columns are known only at runtime, so the fields are accessed
+    /// by their index, or by their name together with the header from
+    /// [`CsvStreamResponse::csv_stream_with_headers`]. The records have a maximum size of
+    /// `max_obj_len` bytes, and a field that isn't valid UTF-8 is a codec error.
+    ///
+    /// If `has_headers` is `true`, the first record is the CSV header and it is skipped.
+    ///
+    /// The `delimiter` is the byte value of the delimiter character.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::CsvStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/csv")
+    ///         .await?
+    ///         .csv_record_stream(MAX_OBJ_LEN, b',', true);
+    ///     let records: Vec<csv::StringRecord> = stream.try_collect().await?;
+    ///     for record in records {
+    ///         println!("First field: {:?}", record.get(0));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn csv_record_stream<'b>(
+        self,
+        max_obj_len: usize,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> BoxStream<'b, StreamBodyResult<csv::StringRecord>>;
+}
+
+/// Options for streaming the CSV format.
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    escape: Option<u8>,
+    has_headers: bool,
+    flexible: bool,
+    comment: Option<u8>,
+    buf_capacity: usize,
+    buf_shrink_threshold: Option<usize>,
+    strip_bom: bool,
+}
+
+impl CsvOptions {
+    /// Create the default options: the `,` delimiter, the `"` quote, no escape character,
+    /// a header record, records of equal length, no comments, the default buffer capacity and
+    /// a stripped UTF-8 byte order mark.
+    pub fn new() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            has_headers: true,
+            flexible: false,
+            comment: None,
+            buf_capacity: INITIAL_CAPACITY,
+            buf_shrink_threshold: None,
+            strip_bom: true,
+        }
+    }
+
+    /// Set the byte value of the field delimiter.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set the byte value of the quote character.
+    pub fn with_quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Set the byte value of the escape character for quotes inside quoted fields.
+    ///
+    /// Without an escape character, quotes are escaped by doubling them (`""`).
+    pub fn with_escape(mut self, escape: Option<u8>) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// Set whether the first record is the CSV header.
+    pub fn with_has_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Set whether records may have a different number of fields.
+    ///
+    /// If not flexible, a record with a different number of fields than the first one
+    /// is a codec error. If flexible, the fields missing at the end of a short record are
+    /// deserialized as `None` for `Option` fields when the records are deserialized by the
+    /// header names. When they are deserialized by their position, the missing fields need
+    /// `#[serde(default)]`.
+    pub fn with_flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    /// Set the byte value that starts a comment line. Comment lines are ignored.
+    pub fn with_comment(mut self, comment: Option<u8>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Set the initial capacity of the stream's decoding buffer.
+    pub fn with_buf_capacity(mut self, buf_capacity: usize) -> Self {
+        self.buf_capacity = buf_capacity;
+        self
+    }
+
+    /// Shrink the decoding buffer back to its initial capacity after a record, once it has grown
+    /// over `buf_shrink_threshold` bytes.
+    ///
+    /// By default, the buffer grows to fit the longest record and keeps that capacity until the
+    /// end of the stream.
+    pub fn with_buf_shrink_threshold(mut self, buf_shrink_threshold: usize) -> Self {
+        self.buf_shrink_threshold = Some(buf_shrink_threshold);
+        self
+    }
+
+    /// Set whether a UTF-8 byte order mark at the start of the body is stripped.
+    ///
+    /// If not, the mark is a part of the first field of the first record.
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.strip_bom = strip_bom;
+        self
+    }
+
+    fn csv_reader(&self) -> csv_core::Reader {
+        csv_core::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape)
+            .double_quote(self.escape.is_none())
+            .comment(self.comment)
+            .build()
+    }
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl CsvStreamResponse for reqwest::Response {
+    fn csv_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_csv_header: bool,
+        delimiter: u8,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b,
+    {
+        self.csv_stream_with_capacity(max_obj_len, with_csv_header, delimiter, INITIAL_CAPACITY)
+    }
+
+    fn csv_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_csv_header: bool,
+        delimiter: u8,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b,
+    {
+        match check_content_type(&self, content_type.unwrap_or(CSV_CONTENT_TYPE)) {
+            Ok(()) => self.csv_stream(max_obj_len, with_csv_header, delimiter),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    fn csv_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_csv_header: bool,
+        delimiter: u8,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b,
+    {
+        self.csv_stream_with_options(
+            max_obj_len,
+            CsvOptions::new()
+                .with_delimiter(delimiter)
+                .with_has_headers(with_csv_header)
+                .with_buf_capacity(buf_capacity),
+        )
+    }
+
+    fn tsv_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        with_header: bool,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b,
+    {
+        self.csv_stream(max_obj_len, with_header, b'\t')
+    }
+
+    fn csv_stream_with_options<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        options: CsvOptions,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b,
+    {
+        let reader = response_reader(self);
+
+        let codec = traced_codec(
+            ShrinkingCodec::new(
+                CsvCodec::<T>::new_with_max_length(
+                    max_obj_len,
+                    options.has_headers,
+                    options.flexible,
+                    options.csv_reader(),
+                )
+                .with_strip_bom(options.strip_bom),
+                options.buf_capacity,
+                options.buf_shrink_threshold,
+            ),
+            "csv",
+            max_obj_len,
+        );
+        let frames_reader =
+            tokio_util::codec::FramedRead::with_capacity(reader, codec, options.buf_capacity);
+
+        Box::pin(frames_reader.into_stream())
+    }
+
+    async fn csv_stream_with_headers<'b, T>(
+        self,
+        max_obj_len: usize,
+        delimiter: u8,
+    ) -> StreamBodyResult<(Vec<String>, BoxStream<'b, StreamBodyResult<T>>)>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader(self);
+
+        let options = CsvOptions::new().with_delimiter(delimiter);
+        let codec = CsvRecordCodec::new_with_max_length(
+            max_obj_len,
+            options.flexible,
+            options.csv_reader(),
+        );
+        let mut frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+
+        let header_record = match frames_reader.next().await {
+            Some(header_record) => header_record?,
+            None => return Ok((Vec::new(), Box::pin(futures::stream::empty()))),
+        };
+        let headers = csv::StringRecord::from_byte_record(header_record.clone())
+            .map_err(|err| {
+                StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+            })?
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+
+        let frames_reader = frames_reader.map_decoder(|record_codec| {
+            traced_codec(
+                CsvCodec::<T>::with_headers(record_codec, header_record),
+                "csv",
+                max_obj_len,
+            )
+        });
+
+        Ok((headers, Box::pin(frames_reader.into_stream())))
+    }
+
+    fn csv_record_stream<'b>(
+        self,
+        max_obj_len: usize,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> BoxStream<'b, StreamBodyResult<csv::StringRecord>> {
+        let reader = response_reader(self);
+
+        let options = CsvOptions::new()
+            .with_delimiter(delimiter)
+            .with_has_headers(has_headers);
+        let codec = traced_codec(
+            CsvStringRecordCodec::new_with_max_length(
+                max_obj_len,
+                options.has_headers,
+                options.flexible,
+                options.csv_reader(),
+            ),
+            "csv",
+            max_obj_len,
+        );
+        let frames_reader =
+            tokio_util::codec::FramedRead::with_capacity(reader, codec, options.buf_capacity);
+
+        Box::pin(frames_reader.into_stream())
+    }
 }
 
-#[async_trait]
-impl CsvStreamResponse for reqwest::Response {
-    fn csv_stream<'a, 'b, T>(
-        self,
-        max_obj_len: usize,
-        with_csv_header: bool,
-        delimiter: u8,
-    ) -> BoxStream<'b, StreamBodyResult<T>>
-    where
-        T: for<'de> Deserialize<'de>,
-    {
-        let reader = StreamReader::new(
-            self.bytes_stream()
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)),
-        );
-
-        let codec = tokio_util::codec::LinesCodec::new_with_max_length(max_obj_len);
-        let frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
-
-        #[allow(clippy::bool_to_int_with_if)] // false positive: it is not bool to int
-        let skip_header_if_expected = if with_csv_header { 1 } else { 0 };
-
-        Box::pin(
-            frames_reader
-                .into_stream()
-                .skip(skip_header_if_expected)
-                .map(move |frame_res| match frame_res {
-                    Ok(frame_str) => {
-                        let mut csv_reader = csv::ReaderBuilder::new()
-                            .delimiter(delimiter)
-                            .has_headers(false)
-                            .from_reader(frame_str.as_bytes());
-
-                        let mut iter = csv_reader.deserialize::<T>();
-
-                        if let Some(csv_res) = iter.next() {
-                            match csv_res {
-                                Ok(result) => Ok(result),
-                                Err(err) => Err(StreamBodyError::new(
-                                    StreamBodyKind::CodecError,
-                                    Some(Box::new(err)),
-                                    None,
-                                )),
-                            }
-                        } else {
-                            Err(StreamBodyError::new(StreamBodyKind::CodecError, None, None))
-                        }
-                    }
-                    Err(err) => Err(StreamBodyError::new(
-                        StreamBodyKind::CodecError,
-                        Some(Box::new(err)),
-                        None,
-                    )),
-                }),
-        )
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use axum_streams::*;
+    use futures::stream;
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field1: String,
+        some_test_field2: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::csv(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<MyTestStructure>(1024, false, b',');
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_strict() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new()
+            .route("/", get(|| async { StreamBodyAs::csv(test_stream) }))
+            .route("/json", get(|| async { axum::Json(Vec::<String>::new()) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_strict::<MyTestStructure>(1024, false, b',', None);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let res = client
+            .get("/json")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_strict::<MyTestStructure>(1024, false, b',', None);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_with_header() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(
+            test_stream_vec
+                .clone()
+                .into_iter()
+                .map(Ok::<_, axum::Error>),
+        ));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::new(CsvStreamFormat::new(true, b','), test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<MyTestStructure>(1024, true, b',');
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_with_embedded_newlines() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "some_test_field1,some_test_field2\n\"Multi\nline\",TestValue2\nTestValue1,\"Quoted, \"\"with\"\" delimiter\"\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<MyTestStructure>(1024, true, b',');
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                MyTestStructure {
+                    some_test_field1: "Multi\nline".to_string(),
+                    some_test_field2: "TestValue2".to_string(),
+                },
+                MyTestStructure {
+                    some_test_field1: "TestValue1".to_string(),
+                    some_test_field2: "Quoted, \"with\" delimiter".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_with_embedded_newlines_across_chunks() {
+        // The chunks of the body end inside the quoted fields, after their newlines
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = vec![
+                    "some_test_field1,some_test_field2\n\"Multi\r",
+                    "\nline\n",
+                    "field\",TestValue2\r\nTestValue1,\"Ends with newline\n",
+                    "\"\n",
+                ];
+                Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>)))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<MyTestStructure>(1024, true, b',');
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                MyTestStructure {
+                    some_test_field1: "Multi\r\nline\nfield".to_string(),
+                    some_test_field2: "TestValue2".to_string(),
+                },
+                MyTestStructure {
+                    some_test_field1: "TestValue1".to_string(),
+                    some_test_field2: "Ends with newline\n".to_string(),
+                },
+            ]
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::test_client::*;
-    use axum::{routing::*, Router};
-    use axum_streams::*;
-    use futures::stream;
-    use serde::Serialize;
+    #[tokio::test]
+    async fn deserialize_csv_stream_by_header_names() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        struct ReorderedTestStructure {
+            some_test_field2: String,
+            some_test_field1: String,
+        }
 
-    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
-    struct MyTestStructure {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(
+            test_stream_vec
+                .clone()
+                .into_iter()
+                .map(Ok::<_, axum::Error>),
+        ));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::new(CsvStreamFormat::new(true, b','), test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<ReorderedTestStructure>(1024, true, b',');
+        let items: Vec<ReorderedTestStructure> = res.try_collect().await.unwrap();
+
+        let expected_items: Vec<ReorderedTestStructure> = test_stream_vec
+            .into_iter()
+            .map(|item| ReorderedTestStructure {
+                some_test_field2: item.some_test_field2,
+                some_test_field1: item.some_test_field1,
+            })
+            .collect();
+
+        assert_eq!(items, expected_items);
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_column_subset() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        struct CitySubset {
+            name: String,
+            population: u64,
+        }
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "id,name,country,population,area\n\
+                 1,Gothenburg,Sweden,607882,447.8\n\
+                 2,London,United Kingdom,8866180,1572\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<CitySubset>(1024, true, b',');
+        let items: Vec<CitySubset> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                CitySubset {
+                    name: "Gothenburg".to_string(),
+                    population: 607882,
+                },
+                CitySubset {
+                    name: "London".to_string(),
+                    population: 8866180,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_returning_headers() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(
+            test_stream_vec
+                .clone()
+                .into_iter()
+                .map(Ok::<_, axum::Error>),
+        ));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::new(CsvStreamFormat::new(true, b','), test_stream) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let (headers, res) = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_with_headers::<MyTestStructure>(1024, b',')
+            .await
+            .unwrap();
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(headers, vec!["some_test_field1", "some_test_field2"]);
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+    struct MyOptionalFieldsStructure {
         some_test_field1: String,
-        some_test_field2: String,
+        some_test_field2: Option<String>,
+        some_test_field3: Option<u32>,
     }
 
-    fn generate_test_structures() -> Vec<MyTestStructure> {
-        vec![
-            MyTestStructure {
-                some_test_field1: "TestValue1".to_string(),
-                some_test_field2: "TestValue2".to_string()
-            };
-            100
-        ]
+    // Fields deserialized by their position need a default to be missing
+    #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+    struct MyDefaultFieldsStructure {
+        some_test_field1: String,
+        #[serde(default)]
+        some_test_field2: Option<String>,
+        #[serde(default)]
+        some_test_field3: Option<u32>,
     }
 
     #[tokio::test]
-    async fn deserialize_csv_stream() {
+    async fn deserialize_csv_stream_flexible_short_rows() {
+        let app = Router::new().route(
+            "/",
+            get(|| async { "some_test_field1,some_test_field2,some_test_field3\nA,B,1\nC,D\nE\n" }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_with_options::<MyOptionalFieldsStructure>(
+                1024,
+                CsvOptions::new().with_has_headers(true).with_flexible(true),
+            );
+        let items: Vec<MyOptionalFieldsStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                MyOptionalFieldsStructure {
+                    some_test_field1: "A".to_string(),
+                    some_test_field2: Some("B".to_string()),
+                    some_test_field3: Some(1),
+                },
+                MyOptionalFieldsStructure {
+                    some_test_field1: "C".to_string(),
+                    some_test_field2: Some("D".to_string()),
+                    some_test_field3: None,
+                },
+                MyOptionalFieldsStructure {
+                    some_test_field1: "E".to_string(),
+                    some_test_field2: None,
+                    some_test_field3: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_flexible_short_rows_without_header() {
+        let app = Router::new().route("/", get(|| async { "A,B,1\nC,D\nE\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_with_options::<MyDefaultFieldsStructure>(
+                1024,
+                CsvOptions::new()
+                    .with_has_headers(false)
+                    .with_flexible(true),
+            );
+        let items: Vec<MyDefaultFieldsStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].some_test_field3, None);
+        assert_eq!(items[2].some_test_field2, None);
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_short_rows_not_flexible() {
+        let app = Router::new().route("/", get(|| async { "A,B,1\nC,D\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<MyOptionalFieldsStructure>(1024, false, b',');
+        let err = res
+            .try_collect::<Vec<MyOptionalFieldsStructure>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[tokio::test]
+    async fn deserialize_bom_prefixed_csv_stream() {
+        // The BOM is split across the chunks of the body
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = vec![
+                    b"\xEF".to_vec(),
+                    b"\xBB\xBFsome_test_field1,some_test_field2\nA,B\n".to_vec(),
+                ];
+                Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>)))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let (headers, res) = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_with_headers::<MyTestStructure>(1024, b',')
+            .await
+            .unwrap();
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(headers, vec!["some_test_field1", "some_test_field2"]);
+        assert_eq!(
+            items,
+            vec![MyTestStructure {
+                some_test_field1: "A".to_string(),
+                some_test_field2: "B".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_record_stream() {
+        let app = Router::new().route(
+            "/",
+            get(|| async { "name;city\nAlice;\"Paris; France\"\nBob;Berlin\n" }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_record_stream(1024, b';', true);
+        let records: Vec<csv::StringRecord> = res.try_collect().await.unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], vec!["Alice", "Paris; France"]);
+        assert_eq!(records[1].get(0), Some("Bob"));
+        assert_eq!(records[1].get(1), Some("Berlin"));
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_check_len_capacity() {
         let test_stream_vec = generate_test_structures();
 
         let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
@@ -150,14 +1060,14 @@ mod tests {
             .send()
             .await
             .unwrap()
-            .csv_stream::<MyTestStructure>(1024, false, b',');
+            .csv_stream_with_capacity::<MyTestStructure>(1024, false, b',', 5);
         let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
 
         assert_eq!(items, test_stream_vec);
     }
 
     #[tokio::test]
-    async fn deserialize_csv_stream_with_header() {
+    async fn deserialize_tsv_stream() {
         let test_stream_vec = generate_test_structures();
 
         let test_stream = Box::pin(stream::iter(
@@ -169,7 +1079,7 @@ mod tests {
 
         let app = Router::new().route(
             "/",
-            get(|| async { StreamBodyAs::new(CsvStreamFormat::new(true, b','), test_stream) }),
+            get(|| async { StreamBodyAs::new(CsvStreamFormat::new(true, b'\t'), test_stream) }),
         );
 
         let client = TestClient::new(app).await;
@@ -179,12 +1089,97 @@ mod tests {
             .send()
             .await
             .unwrap()
-            .csv_stream::<MyTestStructure>(1024, true, b',');
+            .tsv_stream::<MyTestStructure>(1024, true);
         let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
 
         assert_eq!(items, test_stream_vec);
     }
 
+    #[tokio::test]
+    async fn deserialize_csv_stream_with_single_quotes_and_escape() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "some_test_field1,some_test_field2\n'Quoted, \\'with\\' delimiter',TestValue2\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_with_options::<MyTestStructure>(
+                1024,
+                CsvOptions::new().with_quote(b'\'').with_escape(Some(b'\\')),
+            );
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![MyTestStructure {
+                some_test_field1: "Quoted, 'with' delimiter".to_string(),
+                some_test_field2: "TestValue2".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_with_comments() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "some_test_field1,some_test_field2\n# A comment, with a delimiter\nTestValue1,TestValue2\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream_with_options::<MyTestStructure>(
+                1024,
+                CsvOptions::new().with_comment(Some(b'#')),
+            );
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_unequal_lengths() {
+        let app = Router::new().route(
+            "/",
+            get(|| async { "TestValue1,TestValue2\nTestValue1,TestValue2,TestValue3\n" }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .csv_stream::<MyTestStructure>(1024, false, b',');
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
     #[tokio::test]
     async fn deserialize_csv_check_max_len() {
         let test_stream_vec = generate_test_structures();
@@ -205,4 +1200,23 @@ mod tests {
             .await
             .expect_err("MaxLenReachedError");
     }
+
+    #[tokio::test]
+    async fn deserialize_csv_stream_empty_body() {
+        let app = Router::new().route("/", get(|| async { "" }));
+
+        let client = TestClient::new(app).await;
+
+        for with_csv_header in [false, true] {
+            let res = client
+                .get("/")
+                .send()
+                .await
+                .unwrap()
+                .csv_stream::<MyTestStructure>(1024, with_csv_header, b',');
+            let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+            assert!(items.is_empty());
+        }
+    }
 }
@@ -9,6 +9,10 @@ pub struct StreamBodyError {
     kind: StreamBodyKind,
     source: Option<BoxedError>,
     message: Option<String>,
+    limit: Option<usize>,
+    position: Option<usize>,
+    status: Option<reqwest::StatusCode>,
+    item_index: Option<usize>,
 }
 
 impl StreamBodyError {
@@ -18,6 +22,100 @@ impl StreamBodyError {
             kind,
             source,
             message,
+            limit: None,
+            position: None,
+            status: None,
+            item_index: None,
+        }
+    }
+
+    /// Create a new [`StreamBodyKind::MaxLenReachedError`] error for the configured `limit`
+    /// exceeded at the byte offset `position` of the stream.
+    pub fn max_len_reached(limit: usize, position: usize) -> Self {
+        Self {
+            kind: StreamBodyKind::MaxLenReachedError,
+            source: None,
+            message: Some(format!(
+                "Max object length {} reached at byte offset {}",
+                limit, position
+            )),
+            limit: Some(limit),
+            position: Some(position),
+            status: None,
+            item_index: None,
+        }
+    }
+
+    /// Create a new [`StreamBodyKind::TotalLimitReachedError`] error for the configured `limit`
+    /// of bytes read from the response body.
+    pub fn total_limit_reached(limit: usize) -> Self {
+        Self {
+            kind: StreamBodyKind::TotalLimitReachedError,
+            source: None,
+            message: Some(format!(
+                "Max total length {} of the response body reached",
+                limit
+            )),
+            limit: Some(limit),
+            position: Some(limit),
+            status: None,
+            item_index: None,
+        }
+    }
+
+    /// Create a new [`StreamBodyKind::MaxItemsReachedError`] error for the configured `limit`
+    /// of items collected from a stream.
+    pub fn max_items_reached(limit: usize) -> Self {
+        Self {
+            kind: StreamBodyKind::MaxItemsReachedError,
+            source: None,
+            message: Some(format!("The stream has more than {} items", limit)),
+            limit: Some(limit),
+            position: None,
+            status: None,
+            item_index: None,
+        }
+    }
+
+    /// Create a new [`StreamBodyKind::EncodingError`] error for the invalid UTF-8 `source`
+    /// found at the byte offset `position` of the stream.
+    pub fn invalid_utf8(source: std::str::Utf8Error, position: usize) -> Self {
+        Self {
+            kind: StreamBodyKind::EncodingError,
+            source: Some(Box::new(source)),
+            message: Some(format!("Invalid UTF-8 at byte offset {}", position)),
+            limit: None,
+            position: Some(position),
+            status: None,
+            item_index: None,
+        }
+    }
+
+    /// Create a new [`StreamBodyKind::UnexpectedEofError`] error for a stream ending at the
+    /// byte offset `position` before the format was complete.
+    pub fn unexpected_eof(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            kind: StreamBodyKind::UnexpectedEofError,
+            source: None,
+            message: Some(message.into()),
+            limit: None,
+            position: Some(position),
+            status: None,
+            item_index: None,
+        }
+    }
+
+    /// Create a new [`StreamBodyKind::HttpStatusError`] error for a response with the
+    /// unsuccessful `status`.
+    pub fn http_status(status: reqwest::StatusCode) -> Self {
+        Self {
+            kind: StreamBodyKind::HttpStatusError,
+            source: None,
+            message: Some(format!("Unexpected HTTP status {}", status)),
+            limit: None,
+            position: None,
+            status: Some(status),
+            item_index: None,
         }
     }
 
@@ -35,10 +133,276 @@ impl StreamBodyError {
     pub fn message(&self) -> Option<&str> {
         self.message.as_deref()
     }
+
+    /// Create a new [`StreamBodyKind::ValidationError`] error for an item rejected by the
+    /// consumer of the stream.
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(StreamBodyKind::ValidationError, None, Some(message.into()))
+    }
+
+    /// Returns true if the maximum object length was exceeded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::max_len_reached(1024, 2048);
+    /// assert!(err.is_max_len());
+    /// ```
+    pub fn is_max_len(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::MaxLenReachedError)
+    }
+
+    /// Returns true if the error occurred while reading the stream.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+    /// assert!(err.is_io());
+    /// ```
+    pub fn is_io(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::InputOutputError)
+    }
+
+    /// Returns true if the error occurred while decoding a frame or format.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::{StreamBodyError, StreamBodyKind};
+    ///
+    /// let err = StreamBodyError::new(StreamBodyKind::CodecError, None, None);
+    /// assert!(err.is_codec());
+    /// ```
+    pub fn is_codec(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::CodecError)
+    }
+
+    /// Returns true if the response or an item of the stream timed out.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::{StreamBodyError, StreamBodyKind};
+    ///
+    /// let err = StreamBodyError::new(StreamBodyKind::TimeoutError, None, None);
+    /// assert!(err.is_timeout());
+    /// ```
+    pub fn is_timeout(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::TimeoutError)
+    }
+
+    /// Returns true if the response doesn't have a success status.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::http_status(reqwest::StatusCode::NOT_FOUND);
+    /// assert!(err.is_http_status());
+    /// ```
+    pub fn is_http_status(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::HttpStatusError)
+    }
+
+    /// Returns true if the maximum total length of the response body was exceeded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::total_limit_reached(1024 * 1024);
+    /// assert!(err.is_total_limit());
+    /// ```
+    pub fn is_total_limit(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::TotalLimitReachedError)
+    }
+
+    /// Returns true if the text of the stream isn't valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let utf8_err = String::from_utf8(vec![0xff]).unwrap_err().utf8_error();
+    /// let err = StreamBodyError::invalid_utf8(utf8_err, 0);
+    /// assert!(err.is_encoding());
+    /// ```
+    pub fn is_encoding(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::EncodingError)
+    }
+
+    /// Returns true if the stream ended before the format was complete, such as a JSON array
+    /// without its closing bracket.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::unexpected_eof(1024, "The JSON array isn't closed");
+    /// assert!(err.is_unexpected_eof());
+    /// ```
+    pub fn is_unexpected_eof(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::UnexpectedEofError)
+    }
+
+    /// Returns true if a stream has more items than the maximum number of items collected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::max_items_reached(1000);
+    /// assert!(err.is_max_items());
+    /// ```
+    pub fn is_max_items(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::MaxItemsReachedError)
+    }
+
+    /// Returns true if the stream can't be resumed from where it failed, such as a request
+    /// that can't be sent again or a resumed response that doesn't start at the requested
+    /// offset.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::{StreamBodyError, StreamBodyKind};
+    ///
+    /// let err = StreamBodyError::new(StreamBodyKind::ResumeError, None, None);
+    /// assert!(err.is_resume());
+    /// ```
+    pub fn is_resume(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::ResumeError)
+    }
+
+    /// The configured maximum object length, for [`StreamBodyKind::MaxLenReachedError`] errors,
+    /// the maximum total length, for [`StreamBodyKind::TotalLimitReachedError`] errors, or the
+    /// maximum number of items, for [`StreamBodyKind::MaxItemsReachedError`] errors.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// The byte offset in the stream where the maximum length was exceeded, for
+    /// [`StreamBodyKind::MaxLenReachedError`] and [`StreamBodyKind::TotalLimitReachedError`]
+    /// errors, where the invalid UTF-8 starts, for [`StreamBodyKind::EncodingError`] errors, or
+    /// where the stream ended, for [`StreamBodyKind::UnexpectedEofError`] errors.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Create a new [`StreamBodyKind::ContentTypeMismatchError`] error for a response with the
+    /// `actual` content type instead of the `expected` one.
+    pub fn content_type_mismatch(expected: &str, actual: Option<&str>) -> Self {
+        let message = match actual {
+            Some(actual) => format!(
+                "Expected content type {}, but the response has {}",
+                expected, actual
+            ),
+            None => format!(
+                "Expected content type {}, but the response has none",
+                expected
+            ),
+        };
+        Self::new(
+            StreamBodyKind::ContentTypeMismatchError,
+            None,
+            Some(message),
+        )
+    }
+
+    /// Returns true if the response doesn't have the expected content type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::content_type_mismatch("application/json", Some("text/html"));
+    /// assert!(err.is_content_type_mismatch());
+    /// ```
+    pub fn is_content_type_mismatch(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::ContentTypeMismatchError)
+    }
+
+    /// Returns true if a decoded item was rejected by the validation of the consumer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::validation("Missing identifier");
+    /// assert!(err.is_validation());
+    /// ```
+    pub fn is_validation(&self) -> bool {
+        matches!(self.kind, StreamBodyKind::ValidationError)
+    }
+
+    /// Captures the kind, the message and the text of the source of the error into a
+    /// [`Clone`]-able summary.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::StreamBodyError;
+    ///
+    /// let err = StreamBodyError::validation("Missing identifier");
+    /// let summaries = vec![err.to_owned_summary(); 2];
+    /// assert_eq!(summaries[1].to_string(), err.to_string());
+    /// ```
+    pub fn to_owned_summary(&self) -> StreamBodyErrorSummary {
+        StreamBodyErrorSummary {
+            kind: self.kind,
+            message: self.message.clone(),
+            source: self.source.as_ref().map(|e| e.to_string()),
+        }
+    }
+
+    /// The status of the response, for [`StreamBodyKind::HttpStatusError`] errors.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        self.status
+    }
+
+    /// The zero-based index of the element that failed to deserialize, for the errors of the
+    /// streams enumerating the elements, such as `json_array_enumerated_stream`.
+    pub fn item_index(&self) -> Option<usize> {
+        self.item_index
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn with_item_index(mut self, item_index: usize) -> Self {
+        self.item_index = Some(item_index);
+        self
+    }
+
+    /// Copies the error, keeping only the text of its source.
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            kind: self.kind,
+            source: self
+                .source
+                .as_ref()
+                .map(|e| BoxedError::from(e.to_string())),
+            message: self.message.clone(),
+            limit: self.limit,
+            position: self.position,
+            status: self.status,
+            item_index: self.item_index,
+        }
+    }
 }
 
 /// The kind of error that occurred during streaming.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StreamBodyKind {
     /// An error occured while decoding a frame or format.
     CodecError,
@@ -48,6 +412,122 @@ pub enum StreamBodyKind {
 
     /// The maximum object length was exceeded.
     MaxLenReachedError,
+
+    /// The response timed out, or no item arrived within the configured timeout.
+    TimeoutError,
+
+    /// The response doesn't have a success (2xx) status, or a gRPC response has a non-OK
+    /// `grpc-status`.
+    HttpStatusError,
+
+    /// The response doesn't have the expected content type.
+    ContentTypeMismatchError,
+
+    /// A decoded item was rejected by the validation of the consumer.
+    ValidationError,
+
+    /// The maximum total length of the response body was exceeded.
+    TotalLimitReachedError,
+
+    /// The stream has more items than the maximum number of items collected.
+    MaxItemsReachedError,
+
+    /// The text of the stream isn't valid UTF-8.
+    EncodingError,
+
+    /// The stream ended before the format was complete.
+    UnexpectedEofError,
+
+    /// The stream can't be resumed from where it failed.
+    ResumeError,
+}
+
+impl StreamBodyKind {
+    /// A stable label of the kind, such as `"codec"` or `"max_len"`, to key metrics or logs by
+    /// the category of the errors.
+    ///
+    /// The labels are the names of the predicates of [`StreamBodyError`], such as
+    /// [`StreamBodyError::is_codec`], without the `is_` prefix. Unlike the [`fmt::Display`]
+    /// text of the errors, they won't change.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use reqwest_streams::error::{StreamBodyError, StreamBodyKind};
+    ///
+    /// let err = StreamBodyError::new(StreamBodyKind::MaxLenReachedError, None, None);
+    /// assert_eq!(err.kind().as_str(), "max_len");
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamBodyKind::CodecError => "codec",
+            StreamBodyKind::InputOutputError => "io",
+            StreamBodyKind::MaxLenReachedError => "max_len",
+            StreamBodyKind::TimeoutError => "timeout",
+            StreamBodyKind::HttpStatusError => "http_status",
+            StreamBodyKind::ContentTypeMismatchError => "content_type_mismatch",
+            StreamBodyKind::ValidationError => "validation",
+            StreamBodyKind::TotalLimitReachedError => "total_limit",
+            StreamBodyKind::MaxItemsReachedError => "max_items",
+            StreamBodyKind::EncodingError => "encoding",
+            StreamBodyKind::UnexpectedEofError => "unexpected_eof",
+            StreamBodyKind::ResumeError => "resume",
+        }
+    }
+}
+
+/// A [`Clone`]-able summary of a [`StreamBodyError`], with the source error captured as text.
+///
+/// Created with [`StreamBodyError::to_owned_summary`] to store errors while the stream keeps
+/// the original ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamBodyErrorSummary {
+    kind: StreamBodyKind,
+    message: Option<String>,
+    source: Option<String>,
+}
+
+impl StreamBodyErrorSummary {
+    /// The kind of error that occurred during streaming.
+    pub fn kind(&self) -> StreamBodyKind {
+        self.kind
+    }
+
+    /// The message associated with the error.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The text of the actual error that occurred.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+}
+
+impl fmt::Display for StreamBodyErrorSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_error(
+            f,
+            self.kind,
+            self.message.as_deref(),
+            self.source.as_ref().map(|e| e as &dyn fmt::Display),
+        )
+    }
+}
+
+impl std::error::Error for StreamBodyErrorSummary {}
+
+/// What a stream should do when an individual item fails to deserialize.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnError {
+    /// Yield the error as a stream item. Consumers using `try_collect` stop at the first error.
+    Fail,
+
+    /// Drop the failed item and continue with the next one.
+    ///
+    /// Only the items that were correctly framed but failed to deserialize are skipped,
+    /// framing errors (such as exceeding the maximum object length) still end the stream.
+    Skip,
 }
 
 impl fmt::Debug for StreamBodyError {
@@ -64,34 +544,226 @@ impl fmt::Debug for StreamBodyError {
             builder.field("message", message);
         }
 
+        if let Some(ref limit) = self.limit {
+            builder.field("limit", limit);
+        }
+
+        if let Some(ref position) = self.position {
+            builder.field("position", position);
+        }
+
+        if let Some(ref status) = self.status {
+            builder.field("status", status);
+        }
+
+        if let Some(ref item_index) = self.item_index {
+            builder.field("item_index", item_index);
+        }
+
         builder.finish()
     }
 }
 
 impl fmt::Display for StreamBodyError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.kind {
-            StreamBodyKind::CodecError => f.write_str("Frame/codec error")?,
-            StreamBodyKind::InputOutputError => f.write_str("I/O error")?,
-            StreamBodyKind::MaxLenReachedError => f.write_str("Max object length reached")?,
-        };
+        fmt_error(
+            f,
+            self.kind,
+            self.message.as_deref(),
+            self.source.as_ref().map(|e| e as &dyn fmt::Display),
+        )
+    }
+}
 
-        if let Some(message) = &self.message {
-            write!(f, ": {}", message)?;
-        }
+fn fmt_error(
+    f: &mut fmt::Formatter,
+    kind: StreamBodyKind,
+    message: Option<&str>,
+    source: Option<&dyn fmt::Display>,
+) -> fmt::Result {
+    match kind {
+        StreamBodyKind::CodecError => f.write_str("Frame/codec error")?,
+        StreamBodyKind::InputOutputError => f.write_str("I/O error")?,
+        StreamBodyKind::MaxLenReachedError => f.write_str("Max object length reached")?,
+        StreamBodyKind::TimeoutError => f.write_str("Timeout")?,
+        StreamBodyKind::HttpStatusError => f.write_str("HTTP status error")?,
+        StreamBodyKind::ContentTypeMismatchError => f.write_str("Content type mismatch")?,
+        StreamBodyKind::ValidationError => f.write_str("Validation error")?,
+        StreamBodyKind::TotalLimitReachedError => f.write_str("Max total length reached")?,
+        StreamBodyKind::MaxItemsReachedError => f.write_str("Max items reached")?,
+        StreamBodyKind::EncodingError => f.write_str("Encoding error")?,
+        StreamBodyKind::UnexpectedEofError => f.write_str("Unexpected end of stream")?,
+        StreamBodyKind::ResumeError => f.write_str("Resume error")?,
+    };
 
-        if let Some(e) = &self.source {
-            write!(f, ": {}", e)?;
-        }
+    if let Some(message) = message {
+        write!(f, ": {}", message)?;
+    }
 
-        Ok(())
+    if let Some(e) = source {
+        write!(f, ": {}", e)?;
     }
+
+    Ok(())
 }
 
 impl std::error::Error for StreamBodyError {}
 
 impl From<std::io::Error> for StreamBodyError {
     fn from(err: std::io::Error) -> Self {
-        StreamBodyError::new(StreamBodyKind::InputOutputError, Some(Box::new(err)), None)
+        let kind = match err.kind() {
+            std::io::ErrorKind::TimedOut => StreamBodyKind::TimeoutError,
+            _ => StreamBodyKind::InputOutputError,
+        };
+
+        match err.get_ref() {
+            Some(inner) if inner.is::<StreamBodyError>() || inner.is::<reqwest::Error>() => {}
+            _ => return StreamBodyError::new(kind, Some(Box::new(err)), None),
+        }
+        match err
+            .into_inner()
+            .map(|inner| inner.downcast::<StreamBodyError>())
+        {
+            // The errors of this crate raised while reading the body are passed through
+            Some(Ok(inner)) => *inner,
+            // The errors of reqwest are unwrapped, so the source can be downcast to them
+            Some(Err(inner)) => StreamBodyError::new(kind, Some(inner), None),
+            None => unreachable!("the I/O error has an inner error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_kind_predicates() {
+        let codec_err = StreamBodyError::new(StreamBodyKind::CodecError, None, None);
+        assert!(codec_err.is_codec());
+        assert!(!codec_err.is_io());
+        assert!(!codec_err.is_max_len());
+
+        let io_err = StreamBodyError::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+        assert!(io_err.is_io());
+        assert!(!io_err.is_codec());
+        assert!(!io_err.is_max_len());
+
+        let max_len_err = StreamBodyError::max_len_reached(10, 20);
+        assert!(max_len_err.is_max_len());
+        assert!(!max_len_err.is_codec());
+        assert!(!max_len_err.is_io());
+
+        let timeout_err = StreamBodyError::new(StreamBodyKind::TimeoutError, None, None);
+        assert!(timeout_err.is_timeout());
+        assert!(!timeout_err.is_io());
+
+        let io_timeout_err =
+            StreamBodyError::from(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(io_timeout_err.is_timeout());
+        assert!(!io_timeout_err.is_io());
+
+        let status_err = StreamBodyError::http_status(reqwest::StatusCode::NOT_FOUND);
+        assert!(status_err.is_http_status());
+        assert!(!status_err.is_io());
+        assert_eq!(status_err.status(), Some(reqwest::StatusCode::NOT_FOUND));
+
+        let content_type_err = StreamBodyError::content_type_mismatch("text/csv", None);
+        assert!(content_type_err.is_content_type_mismatch());
+        assert!(!content_type_err.is_codec());
+
+        let total_limit_err = StreamBodyError::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            StreamBodyError::total_limit_reached(1024),
+        ));
+        assert!(total_limit_err.is_total_limit());
+        assert!(!total_limit_err.is_io());
+        assert!(!total_limit_err.is_max_len());
+        assert_eq!(total_limit_err.limit(), Some(1024));
+
+        let max_items_err = StreamBodyError::max_items_reached(100);
+        assert!(max_items_err.is_max_items());
+        assert!(!max_items_err.is_max_len());
+        assert_eq!(max_items_err.limit(), Some(100));
+
+        let resume_err = StreamBodyError::new(StreamBodyKind::ResumeError, None, None);
+        assert!(resume_err.is_resume());
+        assert!(!resume_err.is_codec());
+    }
+
+    #[test]
+    fn error_kind_labels() {
+        let labels = [
+            (StreamBodyKind::CodecError, "codec"),
+            (StreamBodyKind::InputOutputError, "io"),
+            (StreamBodyKind::MaxLenReachedError, "max_len"),
+            (StreamBodyKind::TimeoutError, "timeout"),
+            (StreamBodyKind::HttpStatusError, "http_status"),
+            (
+                StreamBodyKind::ContentTypeMismatchError,
+                "content_type_mismatch",
+            ),
+            (StreamBodyKind::ValidationError, "validation"),
+            (StreamBodyKind::TotalLimitReachedError, "total_limit"),
+            (StreamBodyKind::MaxItemsReachedError, "max_items"),
+            (StreamBodyKind::EncodingError, "encoding"),
+            (StreamBodyKind::UnexpectedEofError, "unexpected_eof"),
+            (StreamBodyKind::ResumeError, "resume"),
+        ];
+
+        for (kind, label) in labels {
+            assert_eq!(kind.as_str(), label);
+        }
+
+        let io_err = StreamBodyError::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+        assert_eq!(io_err.kind().as_str(), "io");
+    }
+
+    #[test]
+    fn validation_error() {
+        let err = StreamBodyError::validation("Missing identifier");
+
+        assert!(err.is_validation());
+        assert!(!err.is_codec());
+        assert_eq!(err.kind(), StreamBodyKind::ValidationError);
+        assert_eq!(err.message(), Some("Missing identifier"));
+        assert!(err.source().is_none());
+        assert_eq!(err.to_string(), "Validation error: Missing identifier");
+    }
+
+    #[test]
+    fn error_summaries() {
+        let errors = vec![
+            StreamBodyError::new(StreamBodyKind::CodecError, None, Some("Bad frame".into())),
+            StreamBodyError::from(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "connection closed",
+            )),
+            StreamBodyError::max_len_reached(10, 20),
+            StreamBodyError::from(std::io::Error::from(std::io::ErrorKind::TimedOut)),
+            StreamBodyError::http_status(reqwest::StatusCode::NOT_FOUND),
+            StreamBodyError::content_type_mismatch("text/csv", Some("text/html")),
+            StreamBodyError::validation("Missing identifier"),
+            StreamBodyError::total_limit_reached(1024),
+            StreamBodyError::max_items_reached(100),
+            StreamBodyError::unexpected_eof(10, "The JSON array isn't closed"),
+            StreamBodyError::invalid_utf8(
+                String::from_utf8(vec![0xff]).unwrap_err().utf8_error(),
+                10,
+            ),
+        ];
+
+        for err in errors {
+            let summary = err.to_owned_summary();
+
+            assert_eq!(summary.kind(), err.kind());
+            assert_eq!(summary.message(), err.message());
+            assert_eq!(
+                summary.source(),
+                err.source().map(|e| e.to_string()).as_deref()
+            );
+            assert_eq!(summary.to_string(), err.to_string());
+            assert_eq!(summary.clone(), summary);
+        }
     }
 }
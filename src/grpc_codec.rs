@@ -0,0 +1,317 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use bytes::{Buf, Bytes, BytesMut};
+use std::io::Read;
+use std::marker::PhantomData;
+
+// The compressed flag (1 byte) and the message length (4 bytes)
+const GRPC_HEADER_LEN: usize = 5;
+
+/// The message compression announced by the `grpc-encoding` response header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrpcCompression {
+    Identity,
+    Gzip,
+    Deflate,
+}
+
+impl GrpcCompression {
+    pub fn from_encoding(encoding: Option<&str>) -> Result<Self, StreamBodyError> {
+        match encoding {
+            None | Some("identity") => Ok(GrpcCompression::Identity),
+            Some("gzip") => Ok(GrpcCompression::Gzip),
+            Some("deflate") => Ok(GrpcCompression::Deflate),
+            Some(encoding) => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some(format!("Unsupported gRPC message encoding: {}", encoding)),
+            )),
+        }
+    }
+
+    /// Decompresses the message starting at the byte offset `position` of the stream.
+    fn decompress(
+        &self,
+        bytes: Bytes,
+        max_length: usize,
+        position: usize,
+    ) -> Result<Bytes, StreamBodyError> {
+        let mut decoded = Vec::new();
+        let limit = max_length as u64 + 1;
+        let result = match self {
+            GrpcCompression::Identity => {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some("Compressed gRPC message without a message encoding".into()),
+                ))
+            }
+            GrpcCompression::Gzip => flate2::read::GzDecoder::new(bytes.as_ref())
+                .take(limit)
+                .read_to_end(&mut decoded),
+            GrpcCompression::Deflate => flate2::read::ZlibDecoder::new(bytes.as_ref())
+                .take(limit)
+                .read_to_end(&mut decoded),
+        };
+        result.map_err(|err| {
+            StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+        })?;
+        if decoded.len() > max_length {
+            return Err(StreamBodyError::max_len_reached(max_length, position));
+        }
+        Ok(Bytes::from(decoded))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GrpcCodec<T> {
+    max_length: usize,
+    compression: GrpcCompression,
+    cursor: GrpcCursor,
+    _ph: PhantomData<T>,
+}
+
+#[derive(Clone, Debug)]
+struct GrpcCursor {
+    current_header: Option<GrpcHeader>,
+    consumed_len: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GrpcHeader {
+    compressed: bool,
+    obj_len: usize,
+}
+
+impl<T> GrpcCodec<T> {
+    pub fn new_with_max_length(max_length: usize, compression: GrpcCompression) -> Self {
+        let initial_cursor = GrpcCursor {
+            current_header: None,
+            consumed_len: 0,
+        };
+
+        GrpcCodec {
+            max_length,
+            compression,
+            cursor: initial_cursor,
+            _ph: PhantomData,
+        }
+    }
+
+    fn decode_header(&mut self, buf: &mut BytesMut) -> Result<Option<GrpcHeader>, StreamBodyError> {
+        if buf.len() < GRPC_HEADER_LEN {
+            return Ok(None); // wait more bytes for the header
+        }
+        let compressed = match buf[0] {
+            0 => false,
+            1 => true,
+            flag => {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some(format!("Invalid gRPC compressed flag: {}", flag)),
+                ))
+            }
+        };
+        let obj_len = u32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize;
+        buf.advance(GRPC_HEADER_LEN);
+        self.cursor.consumed_len += GRPC_HEADER_LEN;
+        Ok(Some(GrpcHeader {
+            compressed,
+            obj_len,
+        }))
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for GrpcCodec<T>
+where
+    T: prost::Message + Default,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        let header = match self.cursor.current_header {
+            Some(header) => header,
+            None => match self.decode_header(buf)? {
+                Some(header) => {
+                    self.cursor.current_header = Some(header);
+                    header
+                }
+                None => return Ok(None),
+            },
+        };
+
+        if header.obj_len > self.max_length {
+            Err(StreamBodyError::max_len_reached(
+                self.max_length,
+                self.cursor.consumed_len,
+            ))
+        } else if buf.len() >= header.obj_len {
+            let obj_pos = self.cursor.consumed_len;
+            let mut obj_bytes = buf.copy_to_bytes(header.obj_len);
+            self.cursor.consumed_len += header.obj_len;
+            self.cursor.current_header = None;
+            if header.compressed {
+                obj_bytes = self
+                    .compression
+                    .decompress(obj_bytes, self.max_length, obj_pos)?;
+            }
+            prost::Message::decode(obj_bytes).map(Some).map_err(|err| {
+                StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+            })
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            None => match self.cursor.current_header {
+                Some(header) => Err(StreamBodyError::unexpected_eof(
+                    self.cursor.consumed_len + buf.len(),
+                    format!(
+                        "The stream ended inside a gRPC message of {} bytes, after {} bytes",
+                        header.obj_len,
+                        buf.len()
+                    ),
+                )),
+                None if !buf.is_empty() => Err(StreamBodyError::unexpected_eof(
+                    self.cursor.consumed_len + buf.len(),
+                    "The stream ended inside the header of a gRPC message",
+                )),
+                None => Ok(None),
+            },
+            result => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Clone, prost::Message, PartialEq, Eq)]
+    struct MyTestStructure {
+        #[prost(string, tag = "1")]
+        some_test_field: String,
+    }
+
+    fn encode_frame(test_structure: &MyTestStructure, compressed: bool) -> Vec<u8> {
+        let mut bytes = prost::Message::encode_to_vec(test_structure);
+        if compressed {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&bytes).unwrap();
+            bytes = encoder.finish().unwrap();
+        }
+        let mut frame = vec![u8::from(compressed)];
+        frame.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&bytes);
+        frame
+    }
+
+    #[test]
+    fn decode_partial_header() {
+        let test_structure = MyTestStructure {
+            some_test_field: "TestValue1".to_string(),
+        };
+        let frame = encode_frame(&test_structure, false);
+        let mut codec =
+            GrpcCodec::<MyTestStructure>::new_with_max_length(1024, GrpcCompression::Identity);
+
+        let mut buf = BytesMut::from(&frame[..3]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&frame[3..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(test_structure));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decode_compressed_and_uncompressed_frames() {
+        let test_structures = vec![
+            MyTestStructure {
+                some_test_field: "TestValue1".to_string(),
+            },
+            MyTestStructure {
+                some_test_field: "TestValue2".repeat(20),
+            },
+        ];
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&encode_frame(&test_structures[0], false));
+        buf.extend_from_slice(&encode_frame(&test_structures[1], true));
+        let mut codec =
+            GrpcCodec::<MyTestStructure>::new_with_max_length(1024, GrpcCompression::Gzip);
+
+        let mut items = Vec::new();
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+
+        assert_eq!(items, test_structures);
+    }
+
+    #[test]
+    fn decode_compressed_frame_max_len_position() {
+        let first_frame = encode_frame(&MyTestStructure::default(), false);
+        let large_frame = encode_frame(
+            &MyTestStructure {
+                some_test_field: "TestValue2".repeat(200),
+            },
+            true,
+        );
+        // Only the decompressed message exceeds the maximum length
+        assert!(large_frame.len() < 256);
+        let mut buf = BytesMut::from(&first_frame[..]);
+        buf.extend_from_slice(&large_frame);
+        let mut codec =
+            GrpcCodec::<MyTestStructure>::new_with_max_length(256, GrpcCompression::Gzip);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(MyTestStructure::default())
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+        assert_eq!(err.position(), Some(first_frame.len() + GRPC_HEADER_LEN));
+    }
+
+    #[test]
+    fn decode_compressed_frame_without_encoding() {
+        let frame = encode_frame(&MyTestStructure::default(), true);
+        let mut codec =
+            GrpcCodec::<MyTestStructure>::new_with_max_length(1024, GrpcCompression::Identity);
+
+        let err = codec
+            .decode(&mut BytesMut::from(&frame[..]))
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[test]
+    fn decode_eof_stray_trailing_bytes() {
+        let test_structure = MyTestStructure {
+            some_test_field: "TestValue1".to_string(),
+        };
+        let frame = encode_frame(&test_structure, false);
+
+        for stray_len in [1, GRPC_HEADER_LEN, GRPC_HEADER_LEN + 3] {
+            let mut buf = BytesMut::from(&frame[..]);
+            buf.extend_from_slice(&frame[..stray_len]);
+            let mut codec =
+                GrpcCodec::<MyTestStructure>::new_with_max_length(1024, GrpcCompression::Identity);
+
+            assert_eq!(
+                codec.decode_eof(&mut buf).unwrap(),
+                Some(test_structure.clone())
+            );
+            let err = codec.decode_eof(&mut buf).expect_err("UnexpectedEofError");
+            assert!(err.is_unexpected_eof(), "{}", stray_len);
+            assert_eq!(err.position(), Some(frame.len() + stray_len));
+        }
+    }
+}
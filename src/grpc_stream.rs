@@ -0,0 +1,314 @@
+use crate::error::StreamBodyKind;
+use crate::grpc_codec::{GrpcCodec, GrpcCompression};
+use crate::stream_tracing::traced_codec;
+use crate::CodecStreamResponse;
+use crate::{StreamBodyError, StreamBodyResult};
+use async_trait::*;
+use futures::stream::BoxStream;
+use reqwest::header::HeaderMap;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for
+/// [gRPC] server-streaming responses.
+///
+/// [gRPC]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md
+#[async_trait]
+pub trait GrpcStreamResponse {
+    /// Streams the response as gRPC length-prefixed Protobuf messages.
+    ///
+    /// Each message is framed by a 1-byte compressed flag and its 4-byte big-endian length.
+    /// Compressed messages are decompressed according to the `grpc-encoding` response header
+    /// (`gzip` and `deflate` are supported).
+    ///
+    /// The stream will deserialize [`prost::Message`]s as type `T` with a maximum size of
+    /// `max_obj_len` bytes, both before and after decompression. The messages are decoded up to
+    /// their framed length, so bytes after the last message that aren't a complete frame end the
+    /// stream with a [`crate::error::StreamBodyKind::UnexpectedEofError`] error.
+    ///
+    /// A response with a non-OK `grpc-status` header, which gRPC servers send as a Trailers-Only
+    /// response when a call fails before any message, is a
+    /// [`crate::error::StreamBodyKind::HttpStatusError`] error with the status code and the
+    /// `grpc-message` of the response. The `grpc-status` sent in the trailers after the messages
+    /// isn't checked, as the trailers of the response aren't exposed by [`reqwest`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::GrpcStreamResponse as _;
+    ///
+    /// #[derive(Clone, prost::Message)]
+    /// struct MyTestStructure {
+    ///     #[prost(string, tag = "1")]
+    ///     some_test_field: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::Client::new()
+    ///         .post("http://localhost:8080/my.package.MyService/MyMethod")
+    ///         .header("content-type", "application/grpc")
+    ///         .send()
+    ///         .await?
+    ///         .grpc_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn grpc_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b;
+}
+
+#[async_trait]
+impl GrpcStreamResponse for reqwest::Response {
+    fn grpc_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b,
+    {
+        if let Err(err) = check_grpc_status(self.headers()) {
+            return Box::pin(futures::stream::once(async { Err(err) }));
+        }
+
+        let encoding = self
+            .headers()
+            .get("grpc-encoding")
+            .and_then(|value| value.to_str().ok());
+        let compression = match GrpcCompression::from_encoding(encoding) {
+            Ok(compression) => compression,
+            Err(err) => return Box::pin(futures::stream::once(async { Err(err) })),
+        };
+
+        let codec = traced_codec(
+            GrpcCodec::<T>::new_with_max_length(max_obj_len, compression),
+            "grpc",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+}
+
+/// Fails with a [`StreamBodyKind::HttpStatusError`] error if the headers of the response have a
+/// `grpc-status` other than `0` (OK).
+fn check_grpc_status(headers: &HeaderMap) -> Result<(), StreamBodyError> {
+    let status = headers
+        .get("grpc-status")
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim);
+    match status {
+        None | Some("0") => Ok(()),
+        Some(status) => {
+            let message = match headers
+                .get("grpc-message")
+                .and_then(|value| value.to_str().ok())
+            {
+                Some(grpc_message) => format!("gRPC status {}: {}", status, grpc_message),
+                None => format!("gRPC status {}", status),
+            };
+            Err(StreamBodyError::new(
+                StreamBodyKind::HttpStatusError,
+                None,
+                Some(message),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use futures::{stream, TryStreamExt};
+    use std::io::Write;
+
+    #[derive(Clone, prost::Message, PartialEq, Eq)]
+    struct MyTestStructure {
+        #[prost(string, tag = "1")]
+        some_test_field1: String,
+        #[prost(string, tag = "2")]
+        some_test_field2: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    fn encode_frame(test_structure: &MyTestStructure, compressed: bool) -> Vec<u8> {
+        let mut bytes = prost::Message::encode_to_vec(test_structure);
+        if compressed {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&bytes).unwrap();
+            bytes = encoder.finish().unwrap();
+        }
+        let mut frame = vec![u8::from(compressed)];
+        frame.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&bytes);
+        frame
+    }
+
+    #[tokio::test]
+    async fn deserialize_grpc_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        // Every other message is compressed, and each header is split across two chunks
+        let chunks: Vec<Vec<u8>> = test_stream_vec
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, test_structure)| {
+                let frame = encode_frame(test_structure, idx % 2 == 1);
+                vec![frame[..2].to_vec(), frame[2..].to_vec()]
+            })
+            .collect();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                (
+                    [("grpc-encoding", "gzip")],
+                    Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, axum::Error>))),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .grpc_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_grpc_stream_deflate() {
+        let test_stream_vec = generate_test_structures();
+
+        // Every other message is compressed with the zlib format of the deflate encoding
+        let body: Vec<u8> = test_stream_vec
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, test_structure)| {
+                let mut bytes = prost::Message::encode_to_vec(test_structure);
+                let compressed = idx % 2 == 1;
+                if compressed {
+                    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                    encoder.write_all(&bytes).unwrap();
+                    bytes = encoder.finish().unwrap();
+                }
+                let mut frame = vec![u8::from(compressed)];
+                frame.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+                frame.extend_from_slice(&bytes);
+                frame
+            })
+            .collect();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { ([("grpc-encoding", "deflate")], body) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .grpc_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_grpc_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let body: Vec<u8> = test_stream_vec
+            .iter()
+            .flat_map(|test_structure| encode_frame(test_structure, false))
+            .collect();
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .grpc_stream::<MyTestStructure>(10);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert_eq!(err.limit(), Some(10));
+        assert_eq!(err.position(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn deserialize_grpc_stream_trailers_only_status() {
+        let app = Router::new().route(
+            "/",
+            get(|| async { [("grpc-status", "5"), ("grpc-message", "Not found")] }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .grpc_stream::<MyTestStructure>(1024);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("HttpStatusError");
+
+        assert!(err.is_http_status());
+        assert_eq!(err.message(), Some("gRPC status 5: Not found"));
+    }
+
+    #[tokio::test]
+    async fn deserialize_grpc_stream_ok_status() {
+        let test_stream_vec = generate_test_structures();
+
+        let body: Vec<u8> = test_stream_vec
+            .iter()
+            .flat_map(|test_structure| encode_frame(test_structure, false))
+            .collect();
+
+        let app = Router::new().route("/", get(|| async { ([("grpc-status", "0")], body) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .grpc_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+}
@@ -0,0 +1,165 @@
+//! Streaming of the JSON formats from any [`AsyncRead`] source, such as a file or a socket.
+//!
+//! These are the same decoders used by [`crate::JsonStreamResponse`], without the HTTP
+//! response, so the body read from other sources is decoded with the same framing rules and
+//! limits.
+
+use crate::json_array_codec::JsonArrayCodec;
+use crate::json_stream::json_nl_items;
+use crate::response_reader::INITIAL_CAPACITY;
+use crate::stream_tracing::traced_codec;
+use crate::{JsonNlOptions, StreamBodyResult};
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use serde::Deserialize;
+use tokio::io::AsyncRead;
+
+/// Streams the JSON array read from `reader`, where each element is an item of the stream.
+///
+/// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+/// bytes, as [`crate::JsonStreamResponse::json_array_stream`] does.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::json::json_array_reader_stream;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let file = tokio::fs::File::open("items.json").await?;
+///     let stream = json_array_reader_stream::<_, MyTestStructure>(file, MAX_OBJ_LEN);
+///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn json_array_reader_stream<'a, R, T>(
+    reader: R,
+    max_obj_len: usize,
+) -> BoxStream<'a, StreamBodyResult<T>>
+where
+    R: AsyncRead + Send + 'a,
+    T: for<'de> Deserialize<'de> + Send + 'a,
+{
+    let codec = traced_codec(
+        JsonArrayCodec::<T>::new_with_max_length(max_obj_len),
+        "json_array",
+        max_obj_len,
+    );
+    let frames_reader =
+        tokio_util::codec::FramedRead::with_capacity(reader, codec, INITIAL_CAPACITY);
+
+    Box::pin(frames_reader.into_stream())
+}
+
+/// Streams the JSON lines read from `reader`, where each line is an item of the stream.
+///
+/// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+/// bytes, as [`crate::JsonStreamResponse::json_nl_stream`] does.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::json::json_nl_reader_stream;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let file = tokio::fs::File::open("items.jsonl").await?;
+///     let stream = json_nl_reader_stream::<_, MyTestStructure>(file, MAX_OBJ_LEN);
+///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn json_nl_reader_stream<'a, R, T>(
+    reader: R,
+    max_obj_len: usize,
+) -> BoxStream<'a, StreamBodyResult<T>>
+where
+    R: AsyncRead + Send + 'a,
+    T: for<'de> Deserialize<'de> + Send + 'a,
+{
+    json_nl_items(reader, max_obj_len, JsonNlOptions::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use tokio::io::BufReader;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        (0..100)
+            .map(|idx| MyTestStructure {
+                some_test_field: format!("TestValue{}", idx),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_from_reader() {
+        let test_stream_vec = generate_test_structures();
+        let input = serde_json::to_vec(&test_stream_vec).unwrap();
+
+        let reader = BufReader::new(input.as_slice());
+        let items: Vec<MyTestStructure> = json_array_reader_stream(reader, 1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_from_reader() {
+        let test_stream_vec = generate_test_structures();
+        let input: String = test_stream_vec
+            .iter()
+            .map(|item| serde_json::to_string(item).unwrap() + "\n")
+            .collect();
+
+        let reader = BufReader::new(input.as_bytes());
+        let items: Vec<MyTestStructure> = json_nl_reader_stream(reader, 1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_from_reader_check_max_len() {
+        let input = serde_json::to_vec(&generate_test_structures()).unwrap();
+
+        let reader = BufReader::new(input.as_slice());
+        let err = json_array_reader_stream::<_, MyTestStructure>(reader, 10)
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+    }
+}
@@ -1,137 +1,962 @@
 use crate::error::StreamBodyKind;
+use crate::json_stream::DEFAULT_JSON_MAX_DEPTH;
+use crate::utf8_bom::strip_utf8_bom;
 use crate::StreamBodyError;
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use serde::Deserialize;
 use std::marker::PhantomData;
 
 #[derive(Clone, Debug)]
 pub struct JsonArrayCodec<T> {
+    raw_codec: JsonArrayRawCodec,
+    max_depth: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> JsonArrayCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        JsonArrayCodec {
+            raw_codec: JsonArrayRawCodec::new_with_max_length(max_length),
+            max_depth: DEFAULT_JSON_MAX_DEPTH,
+            _ph: PhantomData,
+        }
+    }
+
+    /// Decodes only a body that is a JSON array, see
+    /// [`JsonArrayRawCodec::new_strict_with_max_length`].
+    pub fn new_strict_with_max_length(max_length: usize) -> Self {
+        JsonArrayCodec {
+            raw_codec: JsonArrayRawCodec::new_strict_with_max_length(max_length),
+            max_depth: DEFAULT_JSON_MAX_DEPTH,
+            _ph: PhantomData,
+        }
+    }
+
+    /// Tolerates the trailing and the repeated delimiters, see
+    /// [`JsonArrayRawCodec::new_lenient_with_max_length`].
+    pub fn new_lenient_with_max_length(max_length: usize) -> Self {
+        JsonArrayCodec {
+            raw_codec: JsonArrayRawCodec::new_lenient_with_max_length(max_length),
+            max_depth: DEFAULT_JSON_MAX_DEPTH,
+            _ph: PhantomData,
+        }
+    }
+
+    /// Set the maximum nesting depth of the elements, see [`deserialize_frame_with_max_depth`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set whether a UTF-8 byte order mark before the array is skipped, see
+    /// [`JsonArrayRawCodec::with_strip_bom`].
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.raw_codec = self.raw_codec.with_strip_bom(strip_bom);
+        self
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for JsonArrayCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        self.raw_codec
+            .decode(buf)?
+            .map(|frame| deserialize_frame_with_max_depth(&frame, self.max_depth))
+            .transpose()
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        self.raw_codec
+            .decode_eof(buf)?
+            .map(|frame| deserialize_frame_with_max_depth(&frame, self.max_depth))
+            .transpose()
+    }
+}
+
+/// Deserializes a JSON value framed by the JSON codecs, nested at most
+/// [`DEFAULT_JSON_MAX_DEPTH`] levels deep.
+pub(crate) fn deserialize_frame<T>(frame: &[u8]) -> Result<T, StreamBodyError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    deserialize_frame_with_max_depth(frame, DEFAULT_JSON_MAX_DEPTH)
+}
+
+/// Deserializes a JSON value framed by the JSON codecs, failing with a
+/// [`StreamBodyKind::CodecError`] error if its arrays and objects are nested more than
+/// `max_depth` levels deep.
+///
+/// The depth is checked before deserializing, so deeply nested values can't overflow the stack
+/// of the recursive deserializers. serde_json has a recursion limit of its own, at 128 levels:
+/// with the `json-unbounded-depth` feature, it's disabled when `max_depth` isn't lower.
+///
+/// With the `json-path-errors` feature, the message of the error has the JSON pointer of the
+/// value failing to deserialize, such as `/items/0/name`.
+pub(crate) fn deserialize_frame_with_max_depth<'de, T>(
+    frame: &'de [u8],
+    max_depth: usize,
+) -> Result<T, StreamBodyError>
+where
+    T: Deserialize<'de>,
+{
+    check_max_depth(frame, max_depth)?;
+
+    let mut deserializer = serde_json::Deserializer::from_slice(frame);
+    #[cfg(feature = "json-unbounded-depth")]
+    if max_depth >= SERDE_JSON_RECURSION_LIMIT {
+        deserializer.disable_recursion_limit();
+    }
+
+    #[cfg(feature = "json-path-errors")]
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+        let message = format!("Invalid JSON value at {}", json_pointer(err.path()));
+        StreamBodyError::new(
+            StreamBodyKind::CodecError,
+            Some(Box::new(err.into_inner())),
+            Some(message),
+        )
+    })?;
+
+    #[cfg(not(feature = "json-path-errors"))]
+    let value = T::deserialize(&mut deserializer).map_err(|err| {
+        StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+    })?;
+
+    deserializer.end().map_err(|err| {
+        StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+    })?;
+    Ok(value)
+}
+
+#[cfg(feature = "json-unbounded-depth")]
+const SERDE_JSON_RECURSION_LIMIT: usize = 128;
+
+/// Fails if the arrays and objects of the JSON value in `frame` are nested more than
+/// `max_depth` levels deep, skipping the brackets and braces in strings.
+fn check_max_depth(frame: &[u8], max_depth: usize) -> Result<(), StreamBodyError> {
+    let mut depth = 0usize;
+    let mut quote_opened = false;
+    let mut escaped = false;
+    for byte in frame {
+        if quote_opened {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => quote_opened = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => quote_opened = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(StreamBodyError::new(
+                        StreamBodyKind::CodecError,
+                        None,
+                        Some(format!(
+                            "The JSON value is nested more than {} levels deep",
+                            max_depth
+                        )),
+                    ));
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Formats the path of a value as a JSON pointer, as defined by RFC 6901.
+#[cfg(feature = "json-path-errors")]
+fn json_pointer(path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+
+    let mut pointer = String::new();
+    for segment in path.iter() {
+        pointer.push('/');
+        match segment {
+            Segment::Seq { index } => pointer.push_str(&index.to_string()),
+            Segment::Map { key } | Segment::Enum { variant: key } => {
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"))
+            }
+            Segment::Unknown => pointer.push('?'),
+        }
+    }
+    pointer
+}
+
+/// Frames the elements of a JSON array without deserializing them.
+///
+/// Only the elements of the top-level array are framed: the brackets and braces of nested
+/// arrays and objects are counted together, so they're part of their element. A UTF-8 byte
+/// order mark before the array is skipped, and only whitespace may follow the array.
+#[derive(Clone, Debug)]
+pub struct JsonArrayRawCodec {
     max_length: usize,
     json_cursor: JsonCursor,
-    _ph: PhantomData<T>,
+    consumed_len: usize,
+    bom_checked: bool,
+    strict: bool,
+    lenient_delimiters: bool,
 }
 
 #[derive(Clone, Debug)]
 struct JsonCursor {
     pub current_offset: usize,
     pub array_is_opened: bool,
+    pub array_is_closed: bool,
     pub delimiter_expected: bool,
+    pub delimiter_found: bool,
     pub quote_opened: bool,
     pub escaped: bool,
     pub opened_brackets: usize,
     pub current_obj_pos: usize,
+    pub scalar_opened: bool,
 }
 
-impl<T> JsonArrayCodec<T> {
+impl JsonArrayRawCodec {
     pub fn new_with_max_length(max_length: usize) -> Self {
         let initial_cursor = JsonCursor {
             current_offset: 0,
             array_is_opened: false,
+            array_is_closed: false,
             delimiter_expected: false,
+            delimiter_found: false,
             quote_opened: false,
             escaped: false,
             opened_brackets: 0,
             current_obj_pos: 0,
+            scalar_opened: false,
         };
 
-        JsonArrayCodec {
+        JsonArrayRawCodec {
             max_length,
             json_cursor: initial_cursor,
-            _ph: PhantomData,
+            consumed_len: 0,
+            bom_checked: false,
+            strict: false,
+            lenient_delimiters: false,
+        }
+    }
+
+    /// Decodes only a body that is a JSON array: the first significant byte must be the
+    /// opening bracket, and a body without any array is an error.
+    pub fn new_strict_with_max_length(max_length: usize) -> Self {
+        JsonArrayRawCodec {
+            strict: true,
+            ..Self::new_with_max_length(max_length)
+        }
+    }
+
+    /// Tolerates the trailing delimiter after the last element, as in `[1, 2,]`, and the
+    /// repeated delimiters between the elements, as in `[1,, 2]`, which some producers emit.
+    /// Both are errors by default.
+    pub fn new_lenient_with_max_length(max_length: usize) -> Self {
+        JsonArrayRawCodec {
+            lenient_delimiters: true,
+            ..Self::new_with_max_length(max_length)
+        }
+    }
+
+    /// Set whether a UTF-8 byte order mark before the array is skipped, which is the default.
+    ///
+    /// If not, the mark is a part of the bytes before the array, so it's an error in strict
+    /// mode.
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.bom_checked = !strip_bom;
+        self
+    }
+
+    /// Returns true once the closing bracket of the array was decoded.
+    pub fn is_array_closed(&self) -> bool {
+        self.json_cursor.array_is_closed
+    }
+
+    /// Fails at the end of the stream when the closing bracket of the array is missing. The
+    /// array starts at the byte offset `array_offset` of the stream.
+    pub fn array_not_closed(&self, array_offset: usize, buf: &BytesMut) -> StreamBodyError {
+        StreamBodyError::unexpected_eof(
+            array_offset + self.consumed_len + buf.len(),
+            "The stream ended before the JSON array was closed",
+        )
+    }
+
+    /// Splits the element at `buf[current_obj_pos..obj_end]` off the buffer and advances
+    /// the buffer up to `advance_to`, resetting the cursor for the next element.
+    fn split_element(&mut self, buf: &mut BytesMut, obj_end: usize, advance_to: usize) -> Bytes {
+        buf.advance(self.json_cursor.current_obj_pos);
+        let frame = buf
+            .split_to(obj_end - self.json_cursor.current_obj_pos)
+            .freeze();
+        buf.advance(advance_to - obj_end);
+        self.consumed_len += advance_to;
+        self.json_cursor.delimiter_expected = true;
+        self.json_cursor.scalar_opened = false;
+        self.json_cursor.current_obj_pos = 0;
+        self.json_cursor.current_offset = 0;
+        frame
+    }
+
+    /// Marks the start of a new element at `position`, which must follow a delimiter
+    /// unless it is the first element of the array.
+    fn start_element(&mut self, position: usize) -> Result<(), StreamBodyError> {
+        if self.json_cursor.delimiter_expected {
+            return Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some("Missing delimiter between elements".into()),
+            ));
         }
+        self.json_cursor.current_obj_pos = position;
+        self.json_cursor.delimiter_found = false;
+        Ok(())
+    }
+
+    /// Skips the whitespace following the closed array, failing on any other byte.
+    fn skip_after_array(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, StreamBodyError> {
+        let start = self.json_cursor.current_offset;
+        if let Some(position) = buf[start..]
+            .iter()
+            .position(|ch| !matches!(ch, b' ' | b'\t' | b'\r' | b'\n'))
+        {
+            return Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some(format!(
+                    "Unexpected '{}' after the end of the JSON array at byte offset {}",
+                    char::from(buf[start + position]).escape_default(),
+                    self.consumed_len + start + position
+                )),
+            ));
+        }
+        self.consumed_len += buf.len();
+        buf.clear();
+        self.json_cursor.current_offset = 0;
+        Ok(None)
     }
 }
 
-impl<T> tokio_util::codec::Decoder for JsonArrayCodec<T>
-where
-    T: for<'de> Deserialize<'de>,
-{
-    type Item = T;
+impl tokio_util::codec::Decoder for JsonArrayRawCodec {
+    type Item = Bytes;
     type Error = StreamBodyError;
 
-    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, StreamBodyError> {
+        if !self.bom_checked {
+            match strip_utf8_bom(buf, false) {
+                Some(bom_len) => self.consumed_len += bom_len,
+                None => return Ok(None),
+            }
+            self.bom_checked = true;
+        }
+
         if buf.is_empty() {
             return Ok(None);
         }
 
-        for (position, current_ch) in buf[self.json_cursor.current_offset..buf.len()]
-            .iter()
-            .enumerate()
-        {
-            if self.json_cursor.current_offset + position >= self.max_length {
-                return Err(StreamBodyError::new(
-                    StreamBodyKind::MaxLenReachedError,
-                    None,
-                    Some("Max object length reached".into()),
+        if self.json_cursor.array_is_closed {
+            return self.skip_after_array(buf);
+        }
+
+        for position in self.json_cursor.current_offset..buf.len() {
+            // A position never reaches usize::MAX, so the length isn't limited then
+            if position >= self.max_length {
+                return Err(StreamBodyError::max_len_reached(
+                    self.max_length,
+                    self.consumed_len + position,
                 ));
             }
-            match *current_ch {
-                b'[' if !self.json_cursor.quote_opened && self.json_cursor.opened_brackets == 0 => {
-                    if self.json_cursor.array_is_opened {
-                        return Err(StreamBodyError::new(
-                            StreamBodyKind::CodecError,
-                            None,
-                            Some("Unexpected array begin. It is already opened".into()),
-                        ));
-                    } else {
-                        self.json_cursor.array_is_opened = true;
+            if self.json_cursor.quote_opened {
+                if self.json_cursor.escaped {
+                    // The escaped character is consumed as-is, whatever it is
+                    self.json_cursor.escaped = false;
+                } else if buf[position] == b'\\' {
+                    self.json_cursor.escaped = true;
+                } else if buf[position] == b'"' {
+                    self.json_cursor.quote_opened = false;
+                    if self.json_cursor.array_is_opened && self.json_cursor.opened_brackets == 0 {
+                        return Ok(Some(self.split_element(buf, position + 1, position + 1)));
                     }
                 }
-                b'"' if !self.json_cursor.escaped => {
-                    self.json_cursor.quote_opened = !self.json_cursor.quote_opened;
+                continue;
+            }
+            let element_level =
+                self.json_cursor.array_is_opened && self.json_cursor.opened_brackets == 0;
+            match buf[position] {
+                // Fails early on bodies such as HTML error pages
+                ch if self.strict
+                    && !self.json_cursor.array_is_opened
+                    && !matches!(ch, b'[' | b' ' | b'\t' | b'\r' | b'\n') =>
+                {
+                    return Err(StreamBodyError::new(
+                        StreamBodyKind::CodecError,
+                        None,
+                        Some(format!(
+                            "Expected a JSON array, found '{}' at byte offset {}",
+                            char::from(ch).escape_default(),
+                            self.consumed_len + position
+                        )),
+                    ));
                 }
-                b'\\' if self.json_cursor.quote_opened => {
-                    self.json_cursor.escaped = true;
+                b'[' if self.json_cursor.opened_brackets == 0
+                    && !self.json_cursor.array_is_opened =>
+                {
+                    self.json_cursor.array_is_opened = true;
                 }
-                b'{' if !self.json_cursor.quote_opened => {
+                b'"' => {
+                    self.json_cursor.quote_opened = true;
+                    if element_level {
+                        self.start_element(position)?;
+                    }
+                }
+                b'{' | b'[' => {
                     if self.json_cursor.opened_brackets == 0 {
-                        self.json_cursor.current_obj_pos =
-                            self.json_cursor.current_offset + position;
+                        self.start_element(position)?;
                     }
                     self.json_cursor.opened_brackets += 1;
-                    self.json_cursor.escaped = false;
                 }
-                b'}' if !self.json_cursor.quote_opened => {
-                    self.json_cursor.opened_brackets -= 1;
-                    self.json_cursor.escaped = false;
+                b'}' | b']' => {
                     if self.json_cursor.opened_brackets == 0 {
-                        self.json_cursor.delimiter_expected = true;
-                        let obj_slice = &buf[self.json_cursor.current_obj_pos
-                            ..self.json_cursor.current_offset + position + 1];
-                        let result = serde_json::from_slice(obj_slice).map_err(|err| {
-                            StreamBodyError::new(
+                        if buf[position] == b'}' {
+                            return Err(StreamBodyError::new(
                                 StreamBodyKind::CodecError,
-                                Some(Box::new(err)),
                                 None,
-                            )
-                        });
-                        self.json_cursor.current_obj_pos = 0;
-                        buf.advance(self.json_cursor.current_offset + position + 1);
-                        self.json_cursor.current_offset = 0;
-                        return result;
+                                Some("Unexpected object end".into()),
+                            ));
+                        }
+                        if self.json_cursor.scalar_opened {
+                            return Ok(Some(self.split_element(buf, position, position)));
+                        }
+                        if buf[position] == b']' && self.json_cursor.array_is_opened {
+                            if self.json_cursor.delimiter_found && !self.lenient_delimiters {
+                                return Err(StreamBodyError::new(
+                                    StreamBodyKind::CodecError,
+                                    None,
+                                    Some("Trailing delimiter found".into()),
+                                ));
+                            }
+                            self.json_cursor.array_is_closed = true;
+                            self.json_cursor.current_offset = position + 1;
+                            return Ok(None);
+                        }
+                    } else {
+                        self.json_cursor.opened_brackets -= 1;
+                        if self.json_cursor.opened_brackets == 0 {
+                            return Ok(Some(self.split_element(buf, position + 1, position + 1)));
+                        }
                     }
                 }
-                b',' if !self.json_cursor.quote_opened
-                    && self.json_cursor.opened_brackets == 0
-                    && !self.json_cursor.delimiter_expected =>
+                b',' | b' ' | b'\t' | b'\r' | b'\n'
+                    if element_level && self.json_cursor.scalar_opened =>
                 {
-                    return Err(StreamBodyError::new(
-                        StreamBodyKind::CodecError,
-                        None,
-                        Some("Unexpected delimiter found".into()),
-                    ));
+                    return Ok(Some(self.split_element(buf, position, position)));
                 }
-                _ => {
-                    self.json_cursor.escaped = false;
+                b',' if element_level => {
+                    if !self.json_cursor.delimiter_expected && !self.lenient_delimiters {
+                        return Err(StreamBodyError::new(
+                            StreamBodyKind::CodecError,
+                            None,
+                            Some("Unexpected delimiter found".into()),
+                        ));
+                    }
+                    self.json_cursor.delimiter_expected = false;
+                    self.json_cursor.delimiter_found = true;
                 }
+                // Whitespace is insignificant between the elements
+                b' ' | b'\t' | b'\r' | b'\n' if element_level => {}
+                _ if element_level && !self.json_cursor.scalar_opened => {
+                    self.start_element(position)?;
+                    self.json_cursor.scalar_opened = true;
+                }
+                _ => {}
             }
         }
-        self.json_cursor.current_offset = buf.len();
+
+        if self.json_cursor.opened_brackets == 0
+            && !self.json_cursor.quote_opened
+            && !self.json_cursor.scalar_opened
+        {
+            // No element is in progress, so the whitespace and delimiters scanned so far
+            // aren't kept in the buffer
+            self.consumed_len += buf.len();
+            buf.clear();
+            self.json_cursor.current_offset = 0;
+        } else {
+            // The next call resumes the scan from the new bytes only
+            self.json_cursor.current_offset = buf.len();
+        }
 
         Ok(None)
     }
 
-    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
-        self.decode(buf)
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, StreamBodyError> {
+        match self.decode(buf)? {
+            // The connection may be dropped in the middle of the array
+            None if self.json_cursor.array_is_opened && !self.json_cursor.array_is_closed => {
+                Err(self.array_not_closed(0, buf))
+            }
+            None if self.json_cursor.array_is_closed => self.skip_after_array(buf),
+            None if self.strict && !self.json_cursor.array_is_opened => {
+                Err(StreamBodyError::unexpected_eof(
+                    self.consumed_len + buf.len(),
+                    "The stream ended before a JSON array was found",
+                ))
+            }
+            result => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct PathStructure {
+        p: String,
+    }
+
+    fn decode_byte_by_byte<T>(codec: &mut JsonArrayCodec<T>, input: &[u8]) -> Vec<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for byte in input {
+            buf.extend_from_slice(&[*byte]);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn decode_escaped_backslashes() {
+        let mut codec = JsonArrayCodec::<PathStructure>::new_with_max_length(1024);
+        let items =
+            decode_byte_by_byte(&mut codec, br#"[{"p":"C:\\tmp"},{"p":"\\"},{"p":"\"}\\"}]"#);
+
+        assert_eq!(
+            items,
+            vec![
+                PathStructure {
+                    p: "C:\\tmp".to_string()
+                },
+                PathStructure {
+                    p: "\\".to_string()
+                },
+                PathStructure {
+                    p: "\"}\\".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_pretty_printed_array() {
+        let input =
+            b"  [\n  {\n    \"p\": \"1\"\n  } ,\n\t{ \"p\": \"2\" }\r\n  ,{\"p\":\"3\"}\n]\n";
+        let expected = vec![
+            PathStructure { p: "1".to_string() },
+            PathStructure { p: "2".to_string() },
+            PathStructure { p: "3".to_string() },
+        ];
+
+        let mut codec = JsonArrayCodec::<PathStructure>::new_with_max_length(1024);
+        assert_eq!(decode_byte_by_byte(&mut codec, input), expected);
+
+        let mut codec = JsonArrayCodec::<PathStructure>::new_with_max_length(1024);
+        let mut buf = BytesMut::from(&input[..]);
+        let mut items = Vec::new();
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn decode_pretty_printed_scalars() {
+        let mut codec = JsonArrayCodec::<i64>::new_with_max_length(1024);
+        let items = decode_byte_by_byte(&mut codec, b"[ 1 ,\n  2\n,3 ]\n");
+
+        assert_eq!(items, vec![1, 2, 3]);
+
+        let mut codec = JsonArrayCodec::<i64>::new_with_max_length(1024);
+        let items = decode_byte_by_byte(&mut codec, b" [ \n ] ");
+
+        assert_eq!(items, Vec::<i64>::new());
+    }
+
+    #[test]
+    fn decode_nested_arrays() {
+        let input = b"[[[1,2],[3,4]],[[5,6]], [ ], [[7.5, -8e1]]]";
+
+        let mut codec = JsonArrayCodec::<Vec<[f64; 2]>>::new_with_max_length(1024);
+        let items = decode_byte_by_byte(&mut codec, input);
+
+        assert_eq!(
+            items,
+            vec![
+                vec![[1.0, 2.0], [3.0, 4.0]],
+                vec![[5.0, 6.0]],
+                vec![],
+                vec![[7.5, -80.0]],
+            ]
+        );
+
+        // Objects and arrays nested into each other are a single element
+        let mut codec = JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024);
+        let items = decode_byte_by_byte(&mut codec, br#"[[{"a":[1,{"b":"]"}]}],{"c":[[]]}]"#);
+
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!([{"a": [1, {"b": "]"}]}]),
+                serde_json::json!({"c": [[]]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_empty_input() {
+        for input in [&b""[..], b"[]", b" \n\t ", b"\n[\n]\n"] {
+            let mut codec = JsonArrayCodec::<i64>::new_with_max_length(1024);
+            let items = decode_byte_by_byte(&mut codec, input);
+
+            assert_eq!(items, Vec::<i64>::new(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn decode_unexpected_delimiters() {
+        for input in [
+            &b"[,1]"[..],
+            b"[1,,2]",
+            b"[ 1 , , 2 ]",
+            b"[{\"p\":\"1\"} {\"p\":\"2\"}]",
+        ] {
+            let mut codec = JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024);
+            let mut buf = BytesMut::from(input);
+            let result = (0..4).try_for_each(|_| codec.decode(&mut buf).map(|_| ()));
+
+            assert!(result.is_err(), "{:?}", String::from_utf8_lossy(input));
+        }
+    }
+
+    #[test]
+    fn decode_trailing_delimiter() {
+        for input in [&b"[1, 2,]"[..], b"[{\"p\":\"1\"},\n]", b"[\"a\" , ]"] {
+            let mut codec = JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024);
+            let mut buf = BytesMut::from(input);
+            let result = (0..4).try_for_each(|_| codec.decode_eof(&mut buf).map(|_| ()));
+
+            assert!(result.is_err(), "{:?}", String::from_utf8_lossy(input));
+        }
+    }
+
+    #[test]
+    fn decode_lenient_delimiters() {
+        for input in [&b"[1, 2,]"[..], b"[1,, 2]", b"[,1 , , 2 ,,]", b"[1,\n2,\n]"] {
+            let mut codec = JsonArrayCodec::<i64>::new_lenient_with_max_length(1024);
+            let items = decode_byte_by_byte(&mut codec, input);
+
+            assert_eq!(items, vec![1, 2], "{:?}", String::from_utf8_lossy(input));
+        }
+
+        let mut codec = JsonArrayCodec::<serde_json::Value>::new_lenient_with_max_length(1024);
+        let items = decode_byte_by_byte(&mut codec, b"[{\"p\":\"1\"},,{\"p\":\"2\"},]");
+
+        assert_eq!(
+            items,
+            vec![serde_json::json!({"p": "1"}), serde_json::json!({"p": "2"})]
+        );
+
+        // The elements must still be delimited
+        let mut codec = JsonArrayCodec::<serde_json::Value>::new_lenient_with_max_length(1024);
+        let mut buf = BytesMut::from(&b"[1,, {\"p\":\"1\"} {\"p\":\"2\"}]"[..]);
+        let result = (0..4).try_for_each(|_| codec.decode(&mut buf).map(|_| ()));
+
+        assert!(result.is_err());
+    }
+
+    fn nested_arrays(depth: usize) -> String {
+        format!("{}1{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn decode_pathologically_nested_element() {
+        let input = format!("[{}]", nested_arrays(100_000));
+
+        let mut codec = JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024 * 1024);
+        let mut buf = BytesMut::from(input.as_bytes());
+        let err = codec.decode_eof(&mut buf).expect_err("CodecError");
+
+        assert_eq!(err.kind(), StreamBodyKind::CodecError);
+        assert_eq!(
+            err.message(),
+            Some("The JSON value is nested more than 128 levels deep")
+        );
+    }
+
+    #[test]
+    fn decode_with_max_depth() {
+        let mut codec =
+            JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024).with_max_depth(3);
+        let items = decode_byte_by_byte(&mut codec, br#"[{"a":[[1]]}, {"a":"[[[[["}]"#);
+
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!({"a": [[1]]}),
+                serde_json::json!({"a": "[[[[["})
+            ]
+        );
+
+        let mut codec =
+            JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024).with_max_depth(3);
+        let mut buf = BytesMut::from(&br#"[{"a":[[[1]]]}]"#[..]);
+        let err = codec.decode_eof(&mut buf).expect_err("CodecError");
+
+        assert_eq!(
+            err.message(),
+            Some("The JSON value is nested more than 3 levels deep")
+        );
+    }
+
+    #[cfg(feature = "json-unbounded-depth")]
+    #[test]
+    fn decode_deeper_than_serde_json_recursion_limit() {
+        let input = format!("[{}]", nested_arrays(500));
+
+        let mut codec = JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024 * 1024);
+        let mut buf = BytesMut::from(input.as_bytes());
+        assert!(codec.decode_eof(&mut buf).is_err());
+
+        let mut codec = JsonArrayCodec::<serde_json::Value>::new_with_max_length(1024 * 1024)
+            .with_max_depth(500);
+        let mut buf = BytesMut::from(input.as_bytes());
+        let mut item = codec.decode_eof(&mut buf).unwrap().unwrap();
+
+        let mut depth = 0;
+        while let serde_json::Value::Array(mut values) = item {
+            item = values.pop().unwrap();
+            depth += 1;
+        }
+        assert_eq!(depth, 500);
+    }
+
+    #[test]
+    fn decode_max_len_reached_position() {
+        let mut codec = JsonArrayCodec::<PathStructure>::new_with_max_length(20);
+        let mut buf = BytesMut::from(&br#"[{"p":"1"},{"p":"some long value"}]"#[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(PathStructure { p: "1".to_string() })
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(matches!(err.kind(), StreamBodyKind::MaxLenReachedError));
+        assert_eq!(err.limit(), Some(20));
+        assert_eq!(err.position(), Some(30));
+    }
+
+    #[test]
+    fn decode_large_object_byte_by_byte() {
+        let value = "x".repeat(256 * 1024);
+        let input = serde_json::to_vec(&vec![PathStructure { p: value.clone() }; 2]).unwrap();
+
+        let mut codec = JsonArrayCodec::<PathStructure>::new_with_max_length(512 * 1024);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for byte in &input {
+            buf.extend_from_slice(&[*byte]);
+            match codec.decode(&mut buf).unwrap() {
+                Some(item) => items.push(item),
+                // Every byte is scanned once, the next call starts from the end of the buffer
+                None => assert_eq!(codec.raw_codec.json_cursor.current_offset, buf.len()),
+            }
+        }
+
+        assert_eq!(items, vec![PathStructure { p: value }; 2]);
+    }
+
+    #[test]
+    fn decode_unlimited_max_length() {
+        let value = "x".repeat(4 * 1024 * 1024);
+        let input = serde_json::to_vec(&vec![PathStructure { p: value.clone() }; 2]).unwrap();
+
+        let mut codec = JsonArrayCodec::<PathStructure>::new_with_max_length(usize::MAX);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(4096) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+
+        assert_eq!(items, vec![PathStructure { p: value }; 2]);
+    }
+
+    #[test]
+    fn decode_long_whitespace_between_elements() {
+        let padding = " ".repeat(4096);
+        let input = format!("[{0}1{0},{0}2{0}]", padding);
+
+        let mut codec = JsonArrayCodec::<i64>::new_with_max_length(16);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.as_bytes().chunks(7) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+            assert!(buf.len() <= 16);
+        }
+
+        assert_eq!(items, vec![1, 2]);
+    }
+
+    fn decode_all_eof(input: &[u8]) -> Result<Vec<i64>, StreamBodyError> {
+        let mut codec = JsonArrayCodec::<i64>::new_with_max_length(1024);
+        let mut buf = BytesMut::from(input);
+        let mut items = Vec::new();
+        while let Some(item) = codec.decode_eof(&mut buf)? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    #[test]
+    fn decode_eof_closed_array() {
+        assert_eq!(decode_all_eof(b"[1, 2, 3]\n").unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn decode_trailing_bytes_after_array() {
+        for (input, offset) in [(&b"[] [1]"[..], 3), (b"[1,2],3", 5), (b"[1] {\"b\":2}", 4)] {
+            let err = decode_all_eof(input).expect_err("CodecError");
+
+            assert!(err.is_codec(), "{:?}", String::from_utf8_lossy(input));
+            assert!(
+                err.message()
+                    .unwrap()
+                    .ends_with(&format!("at byte offset {}", offset)),
+                "{:?}",
+                err.message()
+            );
+        }
+
+        let mut codec = JsonArrayCodec::<i64>::new_with_max_length(1024);
+        assert_eq!(
+            decode_byte_by_byte(&mut codec, b"[1, 2] \r\n\t "),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn decode_eof_truncated_array() {
+        for input in [&b"["[..], b"[1, 2", b"[1, 2,", b"[1, [2"] {
+            let err = decode_all_eof(input).expect_err("UnexpectedEofError");
+
+            assert!(
+                err.is_unexpected_eof(),
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+            assert_eq!(err.position(), Some(input.len()));
+        }
+    }
+
+    #[test]
+    fn decode_eof_never_opened_array() {
+        for input in [&b""[..], b" \n\t "] {
+            assert_eq!(decode_all_eof(input).unwrap(), Vec::<i64>::new());
+
+            let mut codec = JsonArrayCodec::<i64>::new_strict_with_max_length(1024);
+            let err = codec
+                .decode_eof(&mut BytesMut::from(input))
+                .expect_err("UnexpectedEofError");
+            assert!(err.is_unexpected_eof());
+        }
+    }
+
+    #[test]
+    fn decode_strict_not_array() {
+        for input in [&b"<!DOCTYPE html>"[..], b" {\"p\":\"1\"}", b"\n1"] {
+            let mut codec = JsonArrayCodec::<serde_json::Value>::new_strict_with_max_length(1024);
+            let err = codec
+                .decode(&mut BytesMut::from(input))
+                .expect_err("CodecError");
+
+            assert!(err.is_codec(), "{:?}", String::from_utf8_lossy(input));
+        }
+
+        let mut codec = JsonArrayCodec::<i64>::new_strict_with_max_length(1024);
+        assert_eq!(decode_byte_by_byte(&mut codec, b" \n[1, 2]"), vec![1, 2]);
+    }
+
+    #[test]
+    fn decode_strict_without_bom_stripping() {
+        let mut codec = JsonArrayCodec::<i64>::new_strict_with_max_length(1024);
+        assert_eq!(
+            decode_byte_by_byte(&mut codec, b"\xEF\xBB\xBF[1, 2]"),
+            vec![1, 2]
+        );
+
+        let mut codec =
+            JsonArrayCodec::<i64>::new_strict_with_max_length(1024).with_strip_bom(false);
+        let err = codec
+            .decode(&mut BytesMut::from(&b"\xEF\xBB\xBF[1, 2]"[..]))
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[cfg(feature = "json-path-errors")]
+    #[test]
+    fn decode_nested_type_mismatch_path() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Inner {
+            test_field: i64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Outer {
+            name: String,
+            test_arr: Vec<Inner>,
+        }
+
+        let mut codec = JsonArrayCodec::<Outer>::new_with_max_length(1024);
+        let mut buf = BytesMut::from(
+            &br#"[{"name": "a", "test_arr": [{"test_field": 1}, {"test_field": "x"}]}]"#[..],
+        );
+        let err = codec.decode(&mut buf).expect_err("CodecError");
+
+        assert!(err.is_codec());
+        assert_eq!(
+            err.message(),
+            Some("Invalid JSON value at /test_arr/1/test_field")
+        );
+        assert!(err
+            .source()
+            .and_then(|source| source.downcast_ref::<serde_json::Error>())
+            .is_some());
     }
 }
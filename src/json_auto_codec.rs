@@ -0,0 +1,187 @@
+use crate::error::StreamBodyKind;
+use crate::json_array_codec::{deserialize_frame, JsonArrayCodec};
+use crate::utf8_bom::UTF8_BOM;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+
+/// Decodes the elements of a JSON array, or a lone JSON object as a single element, depending
+/// on the first significant byte of the body.
+///
+/// The array is decoded by [`JsonArrayCodec`] as it comes. The object is buffered up to the end
+/// of the body, within the maximum length, and deserialized then. A body without any value,
+/// such as an empty one, has no elements.
+#[derive(Clone, Debug)]
+pub struct JsonAutoCodec<T> {
+    max_length: usize,
+    shape: JsonShape,
+    bom_len: usize,
+    array_codec: JsonArrayCodec<T>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JsonShape {
+    Unknown,
+    Array,
+    Object,
+    ObjectDecoded,
+}
+
+impl<T> JsonAutoCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        JsonAutoCodec {
+            max_length,
+            shape: JsonShape::Unknown,
+            bom_len: 0,
+            array_codec: JsonArrayCodec::new_with_max_length(max_length),
+        }
+    }
+
+    /// Detects the shape of the body from its first significant byte, after the UTF-8 BOM and
+    /// the whitespace. The bytes of an array are left to the array codec, while the BOM before
+    /// an object is stripped.
+    fn detect_shape(&mut self, buf: &mut BytesMut, eof: bool) -> Result<(), StreamBodyError> {
+        let bom_len = if buf.starts_with(UTF8_BOM) {
+            UTF8_BOM.len()
+        } else if !eof && UTF8_BOM.starts_with(buf) {
+            return Ok(()); // wait more bytes for the BOM
+        } else {
+            0
+        };
+
+        let significant = buf[bom_len..]
+            .iter()
+            .enumerate()
+            .find(|(_, ch)| !matches!(ch, b' ' | b'\t' | b'\r' | b'\n'));
+        self.shape = match significant {
+            None => JsonShape::Unknown,
+            Some((_, b'[')) => JsonShape::Array,
+            Some((_, b'{')) => {
+                buf.advance(bom_len);
+                self.bom_len = bom_len;
+                JsonShape::Object
+            }
+            Some((position, ch)) => {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some(format!(
+                        "Expected a JSON array or object, found '{}' at byte offset {}",
+                        char::from(*ch).escape_default(),
+                        bom_len + position
+                    )),
+                ))
+            }
+        };
+        Ok(())
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for JsonAutoCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if self.shape == JsonShape::Unknown {
+            self.detect_shape(buf, false)?;
+        }
+
+        match self.shape {
+            JsonShape::Array => self.array_codec.decode(buf),
+            JsonShape::Object => {
+                if buf.len() > self.max_length {
+                    return Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.bom_len.saturating_add(self.max_length),
+                    ));
+                }
+                Ok(None) // wait the end of the body for the object
+            }
+            JsonShape::Unknown | JsonShape::ObjectDecoded => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if self.shape == JsonShape::Unknown {
+            self.detect_shape(buf, true)?;
+        }
+
+        match self.shape {
+            JsonShape::Array => self.array_codec.decode_eof(buf),
+            JsonShape::Object => {
+                self.decode(buf)?;
+                let frame = buf.split();
+                self.shape = JsonShape::ObjectDecoded;
+                deserialize_frame(&frame).map(Some)
+            }
+            JsonShape::Unknown | JsonShape::ObjectDecoded => {
+                buf.clear();
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    fn decode_all(
+        input: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<serde_json::Value>, StreamBodyError> {
+        let mut codec = JsonAutoCodec::<serde_json::Value>::new_with_max_length(1024);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf)? {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf)? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    #[test]
+    fn decode_array_and_object_shapes() {
+        for chunk_size in [1, 2, 64] {
+            assert_eq!(
+                decode_all(b"\xEF\xBB\xBF [{\"a\":1},{\"a\":2}] ", chunk_size).unwrap(),
+                vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+            );
+            assert_eq!(
+                decode_all(b"\xEF\xBB\xBF\n {\"a\":[1,2]}\n", chunk_size).unwrap(),
+                vec![serde_json::json!({"a": [1, 2]})]
+            );
+            assert!(decode_all(b" \r\n", chunk_size).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn decode_unexpected_shape() {
+        let err = decode_all(b" <html>", 64).expect_err("CodecError");
+
+        assert!(err.is_codec());
+        assert_eq!(
+            err.message(),
+            Some("Expected a JSON array or object, found '<' at byte offset 1")
+        );
+    }
+
+    #[test]
+    fn decode_object_max_len() {
+        let input = format!("{{\"a\":\"{}\"}}", "x".repeat(2048));
+
+        let err = decode_all(input.as_bytes(), 64).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+        assert_eq!(err.limit(), Some(1024));
+    }
+}
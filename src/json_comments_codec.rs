@@ -0,0 +1,185 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use bytes::BytesMut;
+
+/// Strips the `//` line comments and the `/* */` block comments of JSON texts before decoding
+/// them with the inner codec.
+///
+/// The strings of the texts are tracked, so the comment sequences inside them are kept. A line
+/// comment is replaced by the end of its line and a block comment by a space, so the texts are
+/// still delimited by whitespace as they were. The byte offsets of the errors of the inner codec
+/// are offsets into the stripped texts.
+#[derive(Clone, Debug)]
+pub struct JsonCommentsCodec<D> {
+    codec: D,
+    stripped: BytesMut,
+    state: CommentsState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CommentsState {
+    Text,
+    String,
+    StringEscape,
+    Slash,
+    LineComment,
+    BlockComment,
+    BlockCommentStar,
+}
+
+impl<D> JsonCommentsCodec<D> {
+    pub fn new(codec: D) -> Self {
+        JsonCommentsCodec {
+            codec,
+            stripped: BytesMut::new(),
+            state: CommentsState::Text,
+        }
+    }
+
+    /// Moves the bytes of `buf` into the stripped texts, dropping the comments.
+    fn strip_comments(&mut self, buf: &mut BytesMut) {
+        self.stripped.reserve(buf.len());
+        for &ch in buf.iter() {
+            self.state = match (self.state, ch) {
+                (CommentsState::Text, b'"') => {
+                    self.stripped.extend_from_slice(&[ch]);
+                    CommentsState::String
+                }
+                (CommentsState::Text, b'/') => CommentsState::Slash,
+                (CommentsState::Text, _) => {
+                    self.stripped.extend_from_slice(&[ch]);
+                    CommentsState::Text
+                }
+                (CommentsState::String, _) => {
+                    self.stripped.extend_from_slice(&[ch]);
+                    match ch {
+                        b'\\' => CommentsState::StringEscape,
+                        b'"' => CommentsState::Text,
+                        _ => CommentsState::String,
+                    }
+                }
+                (CommentsState::StringEscape, _) => {
+                    self.stripped.extend_from_slice(&[ch]);
+                    CommentsState::String
+                }
+                (CommentsState::Slash, b'/') => CommentsState::LineComment,
+                (CommentsState::Slash, b'*') => CommentsState::BlockComment,
+                (CommentsState::Slash, _) => {
+                    // Not a comment, the slash is left for the inner codec to reject
+                    self.stripped.extend_from_slice(&[b'/', ch]);
+                    if ch == b'"' {
+                        CommentsState::String
+                    } else {
+                        CommentsState::Text
+                    }
+                }
+                (CommentsState::LineComment, b'\n') => {
+                    self.stripped.extend_from_slice(&[ch]);
+                    CommentsState::Text
+                }
+                (CommentsState::LineComment, _) => CommentsState::LineComment,
+                (CommentsState::BlockComment | CommentsState::BlockCommentStar, b'*') => {
+                    CommentsState::BlockCommentStar
+                }
+                (CommentsState::BlockCommentStar, b'/') => {
+                    self.stripped.extend_from_slice(b" ");
+                    CommentsState::Text
+                }
+                (CommentsState::BlockComment | CommentsState::BlockCommentStar, _) => {
+                    CommentsState::BlockComment
+                }
+            };
+        }
+        buf.clear();
+    }
+}
+
+impl<D> tokio_util::codec::Decoder for JsonCommentsCodec<D>
+where
+    D: tokio_util::codec::Decoder<Error = StreamBodyError>,
+{
+    type Item = D::Item;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<D::Item>, StreamBodyError> {
+        self.strip_comments(buf);
+        self.codec.decode(&mut self.stripped)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<D::Item>, StreamBodyError> {
+        self.strip_comments(buf);
+        match self.state {
+            CommentsState::Slash => {
+                self.stripped.extend_from_slice(b"/");
+                self.state = CommentsState::Text;
+            }
+            CommentsState::BlockComment | CommentsState::BlockCommentStar => {
+                return Err(StreamBodyError::new(
+                    StreamBodyKind::CodecError,
+                    None,
+                    Some("Unterminated JSON comment at the end of the stream".into()),
+                ));
+            }
+            _ => {}
+        }
+        self.codec.decode_eof(&mut self.stripped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_array_codec::JsonArrayCodec;
+    use tokio_util::codec::Decoder;
+
+    fn decode_in_chunks(
+        input: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<serde_json::Value>, StreamBodyError> {
+        let mut codec = JsonCommentsCodec::new(JsonArrayCodec::new_with_max_length(1024));
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf)? {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf)? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    #[test]
+    fn decode_array_with_comments() {
+        let input = br#"// The first line
+            [
+                // The first element
+                {"a": 1, "url": "http://localhost/a//b"}, // After the first element
+                /* Between the elements */ {"a": /* inside */ 2, "s": "/* \"kept\" */"},
+                3 // The last element
+            ]
+            // The last line"#;
+
+        for chunk_size in [1, 2, 5, input.len()] {
+            let items = decode_in_chunks(input, chunk_size).unwrap();
+
+            assert_eq!(
+                items,
+                vec![
+                    serde_json::json!({"a": 1, "url": "http://localhost/a//b"}),
+                    serde_json::json!({"a": 2, "s": "/* \"kept\" */"}),
+                    serde_json::json!(3),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_unterminated_comment() {
+        let err = decode_in_chunks(b"[1, 2] /* unterminated", 4).expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+}
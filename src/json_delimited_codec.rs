@@ -0,0 +1,147 @@
+use crate::json_array_codec::deserialize_frame;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Decodes JSON values terminated by a single delimiter byte.
+#[derive(Clone, Debug)]
+pub struct JsonDelimitedCodec<T> {
+    max_length: usize,
+    delimiter: u8,
+    next_index: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> JsonDelimitedCodec<T> {
+    pub fn new_with_max_length(max_length: usize, delimiter: u8) -> Self {
+        JsonDelimitedCodec {
+            max_length,
+            delimiter,
+            next_index: 0,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+
+    fn max_len_reached(&self) -> StreamBodyError {
+        StreamBodyError::max_len_reached(
+            self.max_length,
+            self.consumed_len.saturating_add(self.max_length),
+        )
+    }
+}
+
+impl<T> JsonDelimitedCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    /// Deserializes the frame, returning `None` for the blank ones.
+    fn decode_frame(&mut self, frame: BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if frame.len() > self.max_length {
+            return Err(self.max_len_reached());
+        }
+        self.consumed_len += frame.len();
+
+        if frame.iter().all(|ch| ch.is_ascii_whitespace()) {
+            return Ok(None);
+        }
+        deserialize_frame(&frame).map(Some)
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for JsonDelimitedCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        loop {
+            match buf[self.next_index..]
+                .iter()
+                .position(|ch| *ch == self.delimiter)
+            {
+                Some(offset) => {
+                    let frame = buf.split_to(self.next_index + offset);
+                    buf.advance(1);
+                    self.next_index = 0;
+                    let result = self.decode_frame(frame);
+                    self.consumed_len += 1;
+                    if let Some(item) = result? {
+                        return Ok(Some(item));
+                    }
+                }
+                None if buf.len() > self.max_length => return Err(self.max_len_reached()),
+                None => {
+                    self.next_index = buf.len();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            Some(item) => Ok(Some(item)),
+            None if buf.is_empty() => Ok(None),
+            None => {
+                let frame = buf.split();
+                self.next_index = 0;
+                self.decode_frame(frame)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        a: i32,
+    }
+
+    #[test]
+    fn decode_unit_separated_values() {
+        let mut codec = JsonDelimitedCodec::<MyTestStructure>::new_with_max_length(1024, 0x1F);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for byte in b"{\"a\":1}\x1f{\"a\":\n2}\x1f\x1f{\"a\":3}" {
+            buf.extend_from_slice(&[*byte]);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+
+        assert_eq!(
+            items,
+            vec![
+                MyTestStructure { a: 1 },
+                MyTestStructure { a: 2 },
+                MyTestStructure { a: 3 }
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_max_len_reached() {
+        let mut codec = JsonDelimitedCodec::<MyTestStructure>::new_with_max_length(10, 0x1F);
+        let mut buf = BytesMut::from(&b"{\"a\":1}\x1f{\"a\":    2}"[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(MyTestStructure { a: 1 })
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+    }
+}
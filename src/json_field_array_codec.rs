@@ -0,0 +1,295 @@
+use crate::error::StreamBodyKind;
+use crate::json_array_codec::{deserialize_frame, JsonArrayRawCodec};
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Decodes the elements of the JSON array found in a field of the top-level JSON object,
+/// such as the `items` of `{"total": 2, "items": [...]}`.
+///
+/// The values of the other fields are skipped while they are read, so they are never buffered.
+/// Everything after the array is ignored.
+#[derive(Clone, Debug)]
+pub struct JsonFieldArrayCodec<T> {
+    field: String,
+    max_length: usize,
+    state: FieldScanState,
+    key: Vec<u8>,
+    value_depth: usize,
+    quote_opened: bool,
+    escaped: bool,
+    consumed_len: usize,
+    array_codec: JsonArrayRawCodec,
+    _ph: PhantomData<T>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FieldScanState {
+    ObjectStart,
+    BeforeKey,
+    Key,
+    BeforeColon,
+    BeforeValue,
+    Value,
+    AfterValue,
+    Array,
+    Done,
+}
+
+impl<T> JsonFieldArrayCodec<T> {
+    pub fn new_with_max_length(max_length: usize, field: &str) -> Self {
+        JsonFieldArrayCodec {
+            field: field.to_string(),
+            max_length,
+            state: FieldScanState::ObjectStart,
+            key: Vec::new(),
+            value_depth: 0,
+            quote_opened: false,
+            escaped: false,
+            consumed_len: 0,
+            array_codec: JsonArrayRawCodec::new_with_max_length(max_length),
+            _ph: PhantomData,
+        }
+    }
+
+    fn codec_error(&self, message: String) -> StreamBodyError {
+        StreamBodyError::new(StreamBodyKind::CodecError, None, Some(message))
+    }
+
+    fn field_not_found(&self) -> StreamBodyError {
+        self.codec_error(format!("Field {} not found in the JSON object", self.field))
+    }
+
+    fn unexpected_char(&self, position: usize) -> StreamBodyError {
+        self.codec_error(format!(
+            "Unexpected character in the JSON object at {}",
+            self.consumed_len + position
+        ))
+    }
+
+    /// Returns true if the key just read is the field of the array.
+    fn is_field_key(&self) -> bool {
+        serde_json::from_slice::<String>(&self.key)
+            .map(|key| key == self.field)
+            .unwrap_or(false)
+    }
+
+    /// Reads the object up to the opening bracket of the array, discarding the bytes read.
+    fn scan_to_array(&mut self, buf: &mut BytesMut) -> Result<(), StreamBodyError> {
+        let mut position = 0;
+        while position < buf.len() && self.state != FieldScanState::Array {
+            let ch = buf[position];
+            match self.state {
+                _ if ch.is_ascii_whitespace()
+                    && self.state != FieldScanState::Key
+                    && self.state != FieldScanState::Value => {}
+                FieldScanState::ObjectStart if ch == b'{' => {
+                    self.state = FieldScanState::BeforeKey;
+                }
+                FieldScanState::BeforeKey if ch == b'"' => {
+                    self.key.clear();
+                    self.key.push(ch);
+                    self.state = FieldScanState::Key;
+                }
+                FieldScanState::BeforeKey | FieldScanState::AfterValue if ch == b'}' => {
+                    return Err(self.field_not_found());
+                }
+                FieldScanState::Key => {
+                    if self.key.len() >= self.max_length {
+                        return Err(StreamBodyError::max_len_reached(
+                            self.max_length,
+                            self.consumed_len + position,
+                        ));
+                    }
+                    self.key.push(ch);
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if ch == b'\\' {
+                        self.escaped = true;
+                    } else if ch == b'"' {
+                        self.state = FieldScanState::BeforeColon;
+                    }
+                }
+                FieldScanState::BeforeColon if ch == b':' => {
+                    self.state = FieldScanState::BeforeValue;
+                }
+                FieldScanState::BeforeValue if self.is_field_key() => {
+                    if ch != b'[' {
+                        return Err(
+                            self.codec_error(format!("Field {} isn't a JSON array", self.field))
+                        );
+                    }
+                    // The opening bracket is left for the array codec
+                    self.state = FieldScanState::Array;
+                    break;
+                }
+                FieldScanState::BeforeValue | FieldScanState::Value => {
+                    self.state = FieldScanState::Value;
+                    self.skip_value_char(ch);
+                    if self.state == FieldScanState::BeforeKey && ch == b'}' {
+                        return Err(self.field_not_found());
+                    }
+                }
+                FieldScanState::AfterValue if ch == b',' => {
+                    self.state = FieldScanState::BeforeKey;
+                }
+                _ => return Err(self.unexpected_char(position)),
+            }
+            position += 1;
+        }
+        buf.advance(position);
+        self.consumed_len += position;
+
+        Ok(())
+    }
+
+    /// Skips a character of the value of another field.
+    fn skip_value_char(&mut self, ch: u8) {
+        if self.quote_opened {
+            if self.escaped {
+                self.escaped = false;
+            } else if ch == b'\\' {
+                self.escaped = true;
+            } else if ch == b'"' {
+                self.quote_opened = false;
+                if self.value_depth == 0 {
+                    self.state = FieldScanState::AfterValue;
+                }
+            }
+            return;
+        }
+
+        match ch {
+            b'"' => self.quote_opened = true,
+            b'{' | b'[' => self.value_depth += 1,
+            b'}' | b']' if self.value_depth == 0 => {
+                // A scalar value terminated by the end of the object
+                self.state = FieldScanState::BeforeKey;
+            }
+            b'}' | b']' => {
+                self.value_depth -= 1;
+                if self.value_depth == 0 {
+                    self.state = FieldScanState::AfterValue;
+                }
+            }
+            b',' if self.value_depth == 0 => self.state = FieldScanState::BeforeKey,
+            _ => {}
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for JsonFieldArrayCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if self.state != FieldScanState::Array && self.state != FieldScanState::Done {
+            self.scan_to_array(buf)?;
+        }
+
+        match self.state {
+            FieldScanState::Array => match self.array_codec.decode(buf)? {
+                Some(frame) => deserialize_frame(&frame).map(Some),
+                None if self.array_codec.is_array_closed() => {
+                    self.state = FieldScanState::Done;
+                    buf.clear();
+                    Ok(None)
+                }
+                None => Ok(None),
+            },
+            FieldScanState::Done => {
+                buf.clear();
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            Some(item) => Ok(Some(item)),
+            None => match self.state {
+                FieldScanState::Array => {
+                    Err(self.array_codec.array_not_closed(self.consumed_len, buf))
+                }
+                FieldScanState::Done => Ok(None),
+                FieldScanState::ObjectStart if buf.is_empty() => Ok(None),
+                _ => Err(self.field_not_found()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    fn decode_in_chunks(
+        codec: &mut JsonFieldArrayCodec<serde_json::Value>,
+        input: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<serde_json::Value>, StreamBodyError> {
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf)? {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf)? {
+            items.push(item);
+        }
+        Ok(items)
+    }
+
+    #[test]
+    fn decode_field_array_between_other_fields() {
+        let input = br#" {"total": 2, "meta": {"items": [1, "]"], "s": "\"items\""},
+            "items" : [{"a": 1}, {"a": "}"}],
+            "next": null, "tail": [1, 2, {"b": []}]}"#;
+
+        for chunk_size in [1, 3, input.len()] {
+            let mut codec = JsonFieldArrayCodec::new_with_max_length(1024, "items");
+            let items = decode_in_chunks(&mut codec, input, chunk_size).unwrap();
+
+            assert_eq!(
+                items,
+                vec![serde_json::json!({"a": 1}), serde_json::json!({"a": "}"})]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_missing_field() {
+        let mut codec = JsonFieldArrayCodec::new_with_max_length(1024, "items");
+        let err = decode_in_chunks(&mut codec, br#"{"total": 0, "other": []}"#, 4)
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[test]
+    fn decode_truncated_field_array() {
+        let input = br#"{"total": 2, "items": [{"a": 1}, {"a""#;
+        let mut codec = JsonFieldArrayCodec::new_with_max_length(1024, "items");
+        let err = decode_in_chunks(&mut codec, input, 4).expect_err("UnexpectedEofError");
+
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.position(), Some(input.len()));
+    }
+
+    #[test]
+    fn decode_field_not_array() {
+        let mut codec = JsonFieldArrayCodec::new_with_max_length(1024, "items");
+        let err =
+            decode_in_chunks(&mut codec, br#"{"items": {"a": 1}}"#, 4).expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+}
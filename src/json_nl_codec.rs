@@ -0,0 +1,313 @@
+use crate::error::StreamBodyKind;
+use crate::json_stream::JsonLineEnding;
+use crate::utf8_bom::strip_utf8_bom;
+use crate::StreamBodyError;
+use bytes::{Bytes, BytesMut};
+
+/// Decodes the lines framed by [`JsonNlBytesCodec`] as strings, validating their UTF-8.
+#[derive(Clone, Debug)]
+pub struct JsonNlCodec {
+    bytes_codec: JsonNlBytesCodec,
+}
+
+impl JsonNlCodec {
+    pub fn new_with_max_length(
+        max_length: usize,
+        line_ending: JsonLineEnding,
+        strip_bom: bool,
+    ) -> Self {
+        JsonNlCodec {
+            bytes_codec: JsonNlBytesCodec::new_with_line_ending(max_length, line_ending, strip_bom),
+        }
+    }
+
+    fn decode_string(line: Option<(BytesMut, usize)>) -> Result<Option<String>, StreamBodyError> {
+        match line {
+            Some((line, line_pos)) => String::from_utf8(Vec::from(line)).map(Some).map_err(|err| {
+                let utf8_err = err.utf8_error();
+                StreamBodyError::invalid_utf8(utf8_err, line_pos + utf8_err.valid_up_to())
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for JsonNlCodec {
+    type Item = String;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<String>, StreamBodyError> {
+        Self::decode_string(self.bytes_codec.decode_frame(buf)?)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<String>, StreamBodyError> {
+        Self::decode_string(self.bytes_codec.decode_frame_eof(buf)?)
+    }
+}
+
+/// Decodes the lines as [`JsonNlCodec`] does, along with the byte offset of the end of each line
+/// in the stream, so a stream can be resumed after the last decoded line.
+#[derive(Clone, Debug)]
+pub struct JsonNlOffsetCodec {
+    codec: JsonNlCodec,
+}
+
+impl JsonNlOffsetCodec {
+    pub fn new(codec: JsonNlCodec) -> Self {
+        JsonNlOffsetCodec { codec }
+    }
+}
+
+impl tokio_util::codec::Decoder for JsonNlOffsetCodec {
+    type Item = (String, usize);
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<(String, usize)>, StreamBodyError> {
+        let line = self.codec.decode(buf)?;
+        Ok(line.map(|line| (line, self.codec.bytes_codec.consumed_len)))
+    }
+
+    fn decode_eof(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<(String, usize)>, StreamBodyError> {
+        let line = self.codec.decode_eof(buf)?;
+        Ok(line.map(|line| (line, self.codec.bytes_codec.consumed_len)))
+    }
+}
+
+/// Frames the lines of a JSON lines stream, yielding their raw bytes.
+///
+/// The lines are found with [`memchr::memchr`] and split from the decoding buffer without
+/// copying them, and their UTF-8 isn't validated, which is left to the JSON deserializer.
+/// The blank lines are skipped, and a UTF-8 byte order mark at the start of the stream is
+/// stripped.
+#[derive(Clone, Debug)]
+pub struct JsonNlBytesCodec {
+    max_length: usize,
+    line_ending: JsonLineEnding,
+    next_index: usize,
+    consumed_len: usize,
+    bom_checked: bool,
+}
+
+impl JsonNlBytesCodec {
+    /// Frames the lines terminated by either `\n` or `\r\n`.
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        Self::new_with_line_ending(max_length, JsonLineEnding::Either, true)
+    }
+
+    pub fn new_with_line_ending(
+        max_length: usize,
+        line_ending: JsonLineEnding,
+        strip_bom: bool,
+    ) -> Self {
+        JsonNlBytesCodec {
+            max_length,
+            line_ending,
+            next_index: 0,
+            consumed_len: 0,
+            bom_checked: !strip_bom,
+        }
+    }
+
+    fn decode_line(
+        &mut self,
+        mut line: BytesMut,
+        with_lf: bool,
+    ) -> Result<BytesMut, StreamBodyError> {
+        let line_pos = self.consumed_len;
+        self.consumed_len += line.len();
+        if with_lf {
+            line.truncate(line.len() - 1);
+            match self.line_ending {
+                JsonLineEnding::Lf => {}
+                JsonLineEnding::CrLf => {
+                    if line.last() != Some(&b'\r') {
+                        return Err(StreamBodyError::new(
+                            StreamBodyKind::CodecError,
+                            None,
+                            Some("Expected CRLF line ending".into()),
+                        ));
+                    }
+                    line.truncate(line.len() - 1);
+                }
+                JsonLineEnding::Either => {
+                    if line.last() == Some(&b'\r') {
+                        line.truncate(line.len() - 1);
+                    }
+                }
+            }
+        }
+
+        if line.len() > self.max_length {
+            return Err(StreamBodyError::max_len_reached(
+                self.max_length,
+                line_pos.saturating_add(self.max_length),
+            ));
+        }
+        Ok(line)
+    }
+
+    /// Decodes the next line that isn't blank, along with its byte offset in the stream.
+    fn decode_frame(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<(BytesMut, usize)>, StreamBodyError> {
+        if !self.bom_checked {
+            match strip_utf8_bom(buf, false) {
+                Some(bom_len) => self.consumed_len += bom_len,
+                None => return Ok(None),
+            }
+            self.bom_checked = true;
+        }
+
+        loop {
+            match memchr::memchr(b'\n', &buf[self.next_index..]) {
+                Some(offset) => {
+                    let line_pos = self.consumed_len;
+                    let line = buf.split_to(self.next_index + offset + 1);
+                    self.next_index = 0;
+                    let line = self.decode_line(line, true)?;
+                    if !is_blank(&line) {
+                        return Ok(Some((line, line_pos)));
+                    }
+                }
+                None if buf.len() > self.max_length.saturating_add(2) => {
+                    return Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.consumed_len.saturating_add(self.max_length),
+                    ))
+                }
+                None => {
+                    self.next_index = buf.len();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_frame_eof(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<(BytesMut, usize)>, StreamBodyError> {
+        match self.decode_frame(buf)? {
+            Some(line) => Ok(Some(line)),
+            None if buf.is_empty() => Ok(None),
+            None => {
+                let line_pos = self.consumed_len;
+                let line = buf.split();
+                self.next_index = 0;
+                let line = self.decode_line(line, false)?;
+                Ok(Some((line, line_pos)).filter(|(line, _)| !is_blank(line)))
+            }
+        }
+    }
+}
+
+impl tokio_util::codec::Decoder for JsonNlBytesCodec {
+    type Item = Bytes;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, StreamBodyError> {
+        let line = self.decode_frame(buf)?;
+        Ok(line.map(|(line, _)| line.freeze()))
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, StreamBodyError> {
+        let line = self.decode_frame_eof(buf)?;
+        Ok(line.map(|(line, _)| line.freeze()))
+    }
+}
+
+// The blank lines, such as the trailing ones, don't hold any value
+fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    fn decode_all(input: &[u8]) -> Vec<String> {
+        let mut codec = JsonNlCodec::new_with_max_length(1024, JsonLineEnding::Either, true);
+        let mut buf = BytesMut::from(input);
+        let mut lines = Vec::new();
+        while let Some(line) = codec.decode_eof(&mut buf).unwrap() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    #[test]
+    fn decode_empty_and_blank_input() {
+        for input in [&b""[..], b"\n", b"\r\n\r\n", b"  \t ", b" \n \n  "] {
+            assert_eq!(decode_all(input), Vec::<String>::new(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn decode_skipping_blank_lines() {
+        let lines = decode_all(b"\n{\"a\":1}\n  \n\r\n{\"a\":2}\n\n");
+
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"a\":2}"]);
+    }
+
+    #[test]
+    fn decode_invalid_utf8_between_lines() {
+        let mut codec = JsonNlCodec::new_with_max_length(1024, JsonLineEnding::Either, true);
+        let mut buf = BytesMut::from(&b"{\"a\":1}\n{\"a\":\"\xff\xfe\"}\n{\"a\":2}\n"[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some("{\"a\":1}".to_string())
+        );
+        let err = codec.decode(&mut buf).expect_err("EncodingError");
+
+        assert!(err.is_encoding());
+        assert!(!err.is_codec());
+        assert_eq!(err.position(), Some(14));
+    }
+
+    #[test]
+    fn decode_bytes_as_lines() {
+        let input = b"\xEF\xBB\xBF{\"a\":1}\r\n\n  \n{\"a\":\"\xff\"}\n{\"a\":2}";
+        for chunk_size in [1, 3, input.len()] {
+            let mut codec = JsonNlBytesCodec::new_with_max_length(1024);
+            let mut buf = BytesMut::new();
+            let mut lines = Vec::new();
+            for chunk in input.chunks(chunk_size) {
+                buf.extend_from_slice(chunk);
+                while let Some(line) = codec.decode(&mut buf).unwrap() {
+                    lines.push(line);
+                }
+            }
+            while let Some(line) = codec.decode_eof(&mut buf).unwrap() {
+                lines.push(line);
+            }
+
+            assert_eq!(
+                lines,
+                vec![&b"{\"a\":1}"[..], b"{\"a\":\"\xff\"}", b"{\"a\":2}"],
+                "{}",
+                chunk_size
+            );
+        }
+    }
+
+    #[test]
+    fn decode_bytes_max_len() {
+        let mut codec = JsonNlBytesCodec::new_with_max_length(8);
+        let mut buf = BytesMut::from(&b"{\"a\":1}\n{\"a\":\"long\"}\n"[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Bytes::from("{\"a\":1}"))
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+        assert_eq!(err.position(), Some(16));
+    }
+}
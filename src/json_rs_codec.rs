@@ -0,0 +1,170 @@
+use crate::json_array_codec::deserialize_frame;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+// The record separator (RS) preceding every JSON text of a sequence
+const RECORD_SEPARATOR: u8 = 0x1E;
+
+/// Decodes JSON text sequences ([RFC 7464]), where every JSON text is preceded by the record
+/// separator and usually followed by a newline.
+///
+/// [RFC 7464]: https://www.rfc-editor.org/rfc/rfc7464
+#[derive(Clone, Debug)]
+pub struct JsonRsCodec<T> {
+    max_length: usize,
+    next_index: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> JsonRsCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        JsonRsCodec {
+            max_length,
+            next_index: 0,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+
+    fn max_len_reached(&self) -> StreamBodyError {
+        StreamBodyError::max_len_reached(
+            self.max_length,
+            self.consumed_len.saturating_add(self.max_length),
+        )
+    }
+}
+
+impl<T> JsonRsCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    /// Deserializes the record, returning `None` for the empty ones.
+    fn decode_record(&mut self, record: BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if record.len() > self.max_length {
+            return Err(self.max_len_reached());
+        }
+        self.consumed_len += record.len();
+
+        if record.iter().all(|ch| ch.is_ascii_whitespace()) {
+            return Ok(None);
+        }
+        deserialize_frame(&record).map(Some)
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for JsonRsCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        loop {
+            // The first record may come without the leading separator
+            if buf.first() == Some(&RECORD_SEPARATOR) {
+                buf.advance(1);
+                self.consumed_len += 1;
+                self.next_index = 0;
+            }
+
+            match buf[self.next_index..]
+                .iter()
+                .position(|ch| *ch == RECORD_SEPARATOR)
+            {
+                Some(offset) => {
+                    let record = buf.split_to(self.next_index + offset);
+                    self.next_index = 0;
+                    if let Some(item) = self.decode_record(record)? {
+                        return Ok(Some(item));
+                    }
+                }
+                None if buf.len() > self.max_length => return Err(self.max_len_reached()),
+                None => {
+                    self.next_index = buf.len();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            Some(item) => Ok(Some(item)),
+            None if buf.is_empty() => Ok(None),
+            None => {
+                let record = buf.split();
+                self.next_index = 0;
+                self.decode_record(record)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        a: i32,
+    }
+
+    fn decode_all(input: &[u8]) -> Vec<MyTestStructure> {
+        let mut codec = JsonRsCodec::<MyTestStructure>::new_with_max_length(1024);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for byte in input {
+            buf.extend_from_slice(&[*byte]);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn decode_with_leading_separator() {
+        let items = decode_all(b"\x1e{\"a\":1}\n\x1e{\"a\":2}\n\x1e\x1e{\"a\":3}\n");
+
+        assert_eq!(
+            items,
+            vec![
+                MyTestStructure { a: 1 },
+                MyTestStructure { a: 2 },
+                MyTestStructure { a: 3 }
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_without_leading_separator() {
+        let items = decode_all(b"{\"a\":1}\n\x1e{\"a\":2}");
+
+        assert_eq!(
+            items,
+            vec![MyTestStructure { a: 1 }, MyTestStructure { a: 2 }]
+        );
+    }
+
+    #[test]
+    fn decode_max_len_reached() {
+        let mut codec = JsonRsCodec::<MyTestStructure>::new_with_max_length(10);
+        let mut buf = BytesMut::from(&b"\x1e{\"a\":1}\n\x1e{\"a\":   2}\n"[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(MyTestStructure { a: 1 })
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+    }
+}
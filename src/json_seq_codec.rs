@@ -0,0 +1,188 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Decodes concatenated JSON values, separated by optional whitespace.
+#[derive(Clone, Debug)]
+pub struct JsonSeqCodec<T> {
+    max_length: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> JsonSeqCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        JsonSeqCodec {
+            max_length,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+
+    fn max_len_reached(&self) -> StreamBodyError {
+        StreamBodyError::max_len_reached(
+            self.max_length,
+            self.consumed_len.saturating_add(self.max_length),
+        )
+    }
+}
+
+impl<T> JsonSeqCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    fn decode_value(
+        &mut self,
+        buf: &mut BytesMut,
+        eof: bool,
+    ) -> Result<Option<T>, StreamBodyError> {
+        let value_pos = match buf.iter().position(|ch| !ch.is_ascii_whitespace()) {
+            Some(value_pos) => value_pos,
+            None => {
+                self.consumed_len += buf.len();
+                buf.clear();
+                return Ok(None);
+            }
+        };
+
+        let mut values = serde_json::Deserializer::from_slice(buf.as_ref()).into_iter::<T>();
+        match values.next() {
+            // A number or a literal at the end of the buffer may continue in the next bytes
+            Some(Ok(_))
+                if !eof
+                    && values.byte_offset() == buf.len()
+                    && !matches!(buf[value_pos], b'{' | b'[' | b'"') =>
+            {
+                if buf.len() > self.max_length {
+                    return Err(self.max_len_reached());
+                }
+                Ok(None)
+            }
+            Some(Ok(value)) => {
+                let obj_len = values.byte_offset();
+                if obj_len - value_pos > self.max_length {
+                    return Err(self.max_len_reached());
+                }
+                buf.advance(obj_len);
+                self.consumed_len += obj_len;
+                Ok(Some(value))
+            }
+            Some(Err(err)) if err.is_eof() => {
+                if buf.len() - value_pos > self.max_length {
+                    return Err(self.max_len_reached());
+                }
+                if eof {
+                    return Err(StreamBodyError::new(
+                        StreamBodyKind::CodecError,
+                        Some(Box::new(err)),
+                        Some("Incomplete JSON value at the end of the stream".into()),
+                    ));
+                }
+                Ok(None) // wait more bytes for the value
+            }
+            Some(Err(err)) => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                Some(Box::new(err)),
+                None,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for JsonSeqCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        self.decode_value(buf, false)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        self.decode_value(buf, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    fn decode_in_chunks<T>(codec: &mut JsonSeqCodec<T>, input: &[u8], chunk_size: usize) -> Vec<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn decode_concatenated_values() {
+        let input = br#" {"a":1}{"a":2}
+            ["b", "c"] "d"{"a":3} 123 4.5 true null"#;
+
+        for chunk_size in [1, 2, 7, input.len()] {
+            let mut codec = JsonSeqCodec::<serde_json::Value>::new_with_max_length(1024);
+            let items = decode_in_chunks(&mut codec, input, chunk_size);
+
+            assert_eq!(
+                items,
+                vec![
+                    serde_json::json!({"a": 1}),
+                    serde_json::json!({"a": 2}),
+                    serde_json::json!(["b", "c"]),
+                    serde_json::json!("d"),
+                    serde_json::json!({"a": 3}),
+                    serde_json::json!(123),
+                    serde_json::json!(4.5),
+                    serde_json::json!(true),
+                    serde_json::Value::Null,
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_incomplete_value_at_eof() {
+        let mut codec = JsonSeqCodec::<serde_json::Value>::new_with_max_length(1024);
+        let mut buf = BytesMut::from(&br#"{"a":1}{"a":"#[..]);
+
+        assert_eq!(
+            codec.decode_eof(&mut buf).unwrap(),
+            Some(serde_json::json!({"a": 1}))
+        );
+        let err = codec.decode_eof(&mut buf).expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[test]
+    fn decode_max_len_reached() {
+        let mut codec = JsonSeqCodec::<serde_json::Value>::new_with_max_length(10);
+        let mut buf = BytesMut::from(&br#"{"a":1} {"a":"some long value""#[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(serde_json::json!({"a": 1}))
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+        assert_eq!(err.position(), Some(17));
+    }
+}
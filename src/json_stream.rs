@@ -1,11 +1,49 @@
-use crate::error::StreamBodyKind;
-use crate::json_array_codec::JsonArrayCodec;
-use crate::{StreamBodyError, StreamBodyResult};
+use crate::buffer_shrink::ShrinkingCodec;
+use crate::codec_stream::reader_stream_with_codec_and_capacity;
+use crate::error::{OnError, StreamBodyKind};
+use crate::json_array_codec::{
+    deserialize_frame, deserialize_frame_with_max_depth, JsonArrayCodec, JsonArrayRawCodec,
+};
+use crate::json_auto_codec::JsonAutoCodec;
+#[cfg(feature = "json5")]
+use crate::json_comments_codec::JsonCommentsCodec;
+use crate::json_delimited_codec::JsonDelimitedCodec;
+use crate::json_field_array_codec::JsonFieldArrayCodec;
+use crate::json_nl_codec::{JsonNlBytesCodec, JsonNlCodec};
+use crate::json_rs_codec::JsonRsCodec;
+use crate::json_seq_codec::JsonSeqCodec;
+use crate::response_reader::{
+    check_content_type, check_status, response_reader, response_reader_with_metrics,
+    response_reader_with_read_timeout, INITIAL_CAPACITY,
+};
+use crate::stream_tracing::traced_codec;
+use crate::util::stream_with_headers;
+use crate::CodecStreamResponse;
+use crate::{FusedItemStream, Progress, StreamBodyError, StreamBodyResult, StreamMetrics};
 use async_trait::*;
+use bytes::Bytes;
 use futures::stream::BoxStream;
 use futures::{StreamExt, TryStreamExt};
+use reqwest::header::HeaderMap;
 use serde::Deserialize;
-use tokio_util::io::StreamReader;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncRead;
+
+pub(crate) const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// The default maximum nesting depth of the arrays and objects in the items of the JSON
+/// streams.
+///
+/// Deeper items fail with a [`crate::error::StreamBodyKind::CodecError`] error instead of
+/// exhausting the stack while they're deserialized.
+pub const DEFAULT_JSON_MAX_DEPTH: usize = 128;
+
+/// A fused and [`Unpin`] stream of JSON items, as returned by
+/// [`crate::StreamBodyExt::into_fused`] for the JSON streams.
+pub type JsonItemStream<'a, T> = FusedItemStream<'a, T>;
+pub(crate) const JSON_NL_CONTENT_TYPE: &str = "application/x-ndjson";
 
 /// Extension trait for [`reqwest::Response`] that provides streaming support for the JSON array
 /// and JSON Lines (NL/NewLines) formats.
@@ -14,8 +52,13 @@ pub trait JsonStreamResponse {
     /// Streams the response as a JSON array.
     ///
     /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
-    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a newline (`\n`)
-    /// character is reached.
+    /// bytes. The elements can be any JSON values, so an array of arrays such as
+    /// `[[1,"a"],[2,"b"]]` is streamed as tuples with `T = (i64, String)`. If `max_obj_len` is
+    /// [`usize::MAX`], the size of the elements isn't limited, so every element is buffered
+    /// until its end. An empty array, as well as an empty or
+    /// whitespace-only body, is an empty stream. A body ending before the closing bracket of
+    /// the array, such as a dropped connection, ends the stream with a
+    /// [`crate::error::StreamBodyKind::UnexpectedEofError`] error.
     ///
     /// # Example
     ///
@@ -47,8 +90,7 @@ pub trait JsonStreamResponse {
     /// Streams the response as a JSON array.
     ///
     /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
-    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a newline (`\n`)
-    /// character is reached.
+    /// bytes. If `max_obj_len` is [`usize::MAX`], the size of the elements isn't limited.
     ///
     /// `buf_capacity` is the initial capacity of the stream's decoding buffer.
     ///
@@ -84,16 +126,17 @@ pub trait JsonStreamResponse {
     where
         T: for<'de> Deserialize<'de> + Send + 'b;
 
-    /// Streams the response as JSON lines (NL/NewLines), where each line contains a JSON object.
+    /// Streams the response as a JSON array, checking the status of the response first.
     ///
-    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
-    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a newline (`\n`)
-    /// character is reached.
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. If the response doesn't
+    /// have a success (2xx) status, the stream yields a single
+    /// [`crate::error::StreamBodyKind::HttpStatusError`] error carrying the status instead of
+    /// decoding the body, which is usually an error page.
     ///
     /// # Example
     ///
     /// ```rust,no_run
-    /// use futures::stream::BoxStream as _;
+    /// use futures::{prelude::*, stream::BoxStream as _};
     /// use reqwest_streams::JsonStreamResponse as _;
     /// use serde::{Deserialize, Serialize};
     ///
@@ -106,27 +149,36 @@ pub trait JsonStreamResponse {
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     const MAX_OBJ_LEN: usize = 64 * 1024;
     ///
-    ///     let _stream = reqwest::get("http://localhost:8080/json-nl")
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
     ///         .await?
-    ///         .json_nl_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///         .json_array_stream_checked::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     match stream.try_collect::<Vec<MyTestStructure>>().await {
+    ///         Ok(_items) => {}
+    ///         Err(err) if err.is_http_status() => println!("Failed with {:?}", err.status()),
+    ///         Err(err) => return Err(err.into()),
+    ///     }
     ///
     ///     Ok(())
     /// }
     /// ```
-    fn json_nl_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    fn json_array_stream_checked<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
     where
         T: for<'de> Deserialize<'de> + Send + 'b;
 
-    /// Streams the response as JSON lines (NL/NewLines), where each line contains a JSON object.
+    /// Streams the response as a JSON array, checking the content type of the response first.
     ///
-    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
-    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a `\n` character
-    /// is reached.
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. If the media type of the
+    /// response isn't `content_type` (`application/json` if `None`), the stream yields a single
+    /// [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error instead of decoding the
+    /// body.
     ///
     /// # Example
     ///
     /// ```rust,no_run
-    /// use futures::stream::BoxStream as _;
+    /// use futures::{prelude::*, stream::BoxStream as _};
     /// use reqwest_streams::JsonStreamResponse as _;
     /// use serde::{Deserialize, Serialize};
     ///
@@ -138,140 +190,3025 @@ pub trait JsonStreamResponse {
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
     ///     const MAX_OBJ_LEN: usize = 64 * 1024;
-    ///     const INITIAL_BUF_CAPACITY: usize = 16 * 1024;
     ///
-    ///     let _stream = reqwest::get("http://localhost:8080/json-nl")
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
     ///         .await?
-    ///         .json_nl_stream_with_capacity::<MyTestStructure>(MAX_OBJ_LEN, INITIAL_BUF_CAPACITY);
+    ///         .json_array_stream_strict::<MyTestStructure>(MAX_OBJ_LEN, None);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
     ///
     ///     Ok(())
     /// }
     /// ```
-    fn json_nl_stream_with_capacity<'a, 'b, T>(
+    fn json_array_stream_strict<'a, 'b, T>(
         self,
         max_obj_len: usize,
-        buf_capacity: usize,
+        content_type: Option<&str>,
     ) -> BoxStream<'b, StreamBodyResult<T>>
     where
         T: for<'de> Deserialize<'de> + Send + 'b;
-}
 
-// This is the default capacity of the buffer used by StreamReader
-const INITIAL_CAPACITY: usize = 8 * 1024;
-
-#[async_trait]
-impl JsonStreamResponse for reqwest::Response {
-    fn json_nl_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    /// Streams the response as a JSON array, validating that the whole body is the array.
+    ///
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. Unlike it, the first
+    /// significant byte of the body must be the opening bracket of the array, so a body such
+    /// as an HTML error page fails with a [`crate::error::StreamBodyKind::CodecError`] error
+    /// as soon as its first byte is read. An empty or whitespace-only body fails with a
+    /// [`crate::error::StreamBodyKind::UnexpectedEofError`] error, as an array that isn't
+    /// closed does.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_validated::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_validated<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
     where
-        T: for<'de> Deserialize<'de> + Send + 'b,
-    {
-        self.json_nl_stream_with_capacity(max_obj_len, INITIAL_CAPACITY)
-    }
+        T: for<'de> Deserialize<'de> + Send + 'b;
 
-    fn json_nl_stream_with_capacity<'a, 'b, T>(
+    /// Streams the response as a JSON array, tolerating the extra delimiters between the
+    /// elements.
+    ///
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. Unlike it, a trailing
+    /// comma after the last element, as in `[{...}, {...},]`, and repeated commas between the
+    /// elements, as in `[{...},, {...}]`, are skipped instead of failing with a
+    /// [`crate::error::StreamBodyKind::CodecError`] error. Such arrays aren't valid JSON, but
+    /// some producers emit them. The elements still have to be separated by a comma.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_lenient::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_lenient<'a, 'b, T>(
         self,
         max_obj_len: usize,
-        buf_capacity: usize,
     ) -> BoxStream<'b, StreamBodyResult<T>>
     where
-        T: for<'de> Deserialize<'de> + Send + 'b,
-    {
-        let reader = StreamReader::new(
-            self.bytes_stream()
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)),
-        );
-
-        let codec = tokio_util::codec::LinesCodec::new_with_max_length(max_obj_len);
-        let frames_reader =
-            tokio_util::codec::FramedRead::with_capacity(reader, codec, buf_capacity);
-
-        Box::pin(
-            frames_reader
-                .into_stream()
-                .map(|frame_res| match frame_res {
-                    Ok(frame_str) => serde_json::from_str(frame_str.as_str()).map_err(|err| {
-                        StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
-                    }),
-                    Err(err) => Err(StreamBodyError::new(
-                        StreamBodyKind::CodecError,
-                        Some(Box::new(err)),
-                        None,
-                    )),
-                }),
-        )
-    }
+        T: for<'de> Deserialize<'de> + Send + 'b;
 
-    fn json_array_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    /// Streams the response as a JSON array, with a maximum nesting depth of the elements.
+    ///
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. The elements whose
+    /// arrays and objects are nested more than `max_depth` levels deep fail with a
+    /// [`crate::error::StreamBodyKind::CodecError`] error, before they're deserialized, so
+    /// a malicious response can't overflow the stack. The other JSON streams use
+    /// [`DEFAULT_JSON_MAX_DEPTH`].
+    ///
+    /// serde_json has a recursion limit of its own, at 128 levels. A higher `max_depth` needs
+    /// the `json-unbounded-depth` feature, which disables the limit of serde_json: the
+    /// deserialization of `T` must then fit in the stack with `max_depth` levels.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const MAX_DEPTH: usize = 16;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_with_max_depth::<MyTestStructure>(MAX_OBJ_LEN, MAX_DEPTH);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_with_max_depth<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        max_depth: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
     where
-        T: for<'de> Deserialize<'de> + Send + 'b,
-    {
-        self.json_array_stream_with_capacity(max_obj_len, INITIAL_CAPACITY)
-    }
+        T: for<'de> Deserialize<'de> + Send + 'b;
 
-    fn json_array_stream_with_capacity<'a, 'b, T>(
+    /// Streams the response as a JSON array, failing if the server stops sending bytes.
+    ///
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. If no bytes of the body
+    /// are received within `read_timeout` while the stream is waiting for them, the stream
+    /// fails with a [`crate::error::StreamBodyKind::TimeoutError`] error. Unlike the timeout of
+    /// the request, which covers the whole response, and
+    /// [`crate::StreamBodyExt::with_item_timeout`], which covers every item, every received
+    /// chunk resets the timeout, so slow but steady responses are read to the end.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_with_read_timeout::<MyTestStructure>(
+    ///             MAX_OBJ_LEN,
+    ///             Duration::from_secs(30),
+    ///         );
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_with_read_timeout<'a, 'b, T>(
         self,
         max_obj_len: usize,
-        buf_capacity: usize,
+        read_timeout: Duration,
     ) -> BoxStream<'b, StreamBodyResult<T>>
     where
-        T: for<'de> Deserialize<'de> + Send + 'b,
-    {
-        let reader = StreamReader::new(
-            self.bytes_stream()
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)),
-        );
+        T: for<'de> Deserialize<'de> + Send + 'b;
 
-        //serde_json::from_reader(read);
-        let codec = JsonArrayCodec::<T>::new_with_max_length(max_obj_len);
-        let frames_reader =
-            tokio_util::codec::FramedRead::with_capacity(reader, codec, buf_capacity);
+    /// Streams the response as a JSON array with comments, as some JSON5 feeds have.
+    ///
+    /// The `//` line comments and the `/* */` block comments are stripped before the elements
+    /// are framed, both between and inside the elements, except in the strings. The elements
+    /// are then decoded as [`JsonStreamResponse::json_array_stream`] does, with a maximum size
+    /// of `max_obj_len` bytes without the comments. Other JSON5 extensions, such as trailing
+    /// commas or unquoted keys, aren't supported.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array-with-comments")
+    ///         .await?
+    ///         .json5_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "json5")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json5")))]
+    fn json5_array_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
 
-        Box::pin(frames_reader.into_stream())
-    }
-}
+    /// Streams the response as a JSON array, reporting the metrics of the stream.
+    ///
+    /// See [`JsonStreamResponse::json_array_stream`] for the details. The bytes read from the
+    /// response body, the decoded items and the errors are reported to `metrics`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamMetrics};
+    /// use serde::{Deserialize, Serialize};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct ItemsCounter(AtomicUsize);
+    ///
+    /// impl StreamMetrics for ItemsCounter {
+    ///     fn on_item(&self) {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_with_metrics::<MyTestStructure>(
+    ///             MAX_OBJ_LEN,
+    ///             Arc::new(ItemsCounter::default()),
+    ///         );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_with_metrics<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        metrics: Arc<dyn StreamMetrics>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::test_client::*;
-    use axum::{routing::*, Router};
-    use axum_streams::*;
-    use futures::stream;
-    use serde::Serialize;
+    /// Streams the response as a JSON array, deserializing the objects on the blocking
+    /// threads of the Tokio runtime.
+    ///
+    /// The objects are framed while the response body is read, and up to `concurrency` of them
+    /// are deserialized at the same time, which speeds up the streams of types that are
+    /// expensive to deserialize. The objects are yielded in the order of the array.
+    /// See [`JsonStreamResponse::json_array_stream`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_buffered::<MyTestStructure>(MAX_OBJ_LEN, 4);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_buffered<'a, T>(
+        self,
+        max_obj_len: usize,
+        concurrency: usize,
+    ) -> BoxStream<'a, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'static;
 
-    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
-    struct MyTestStructure {
-        some_test_field: String,
+    /// Streams the response as a JSON array, returning the headers of the response along with
+    /// the stream.
+    ///
+    /// See [`JsonStreamResponse::json_array_stream`] for the details, and
+    /// [`crate::util::stream_with_headers`] for the other formats.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let (headers, _stream) = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream_with_headers::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     println!("Total count: {:?}", headers.get("X-Total-Count"));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_stream_with_headers<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> (HeaderMap, BoxStream<'b, StreamBodyResult<T>>)
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines (NL/NewLines), where each line contains a JSON object.
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a newline (`\n`)
+    /// character is reached. Blank lines are skipped, so an empty or whitespace-only body is an
+    /// empty stream.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines (NL/NewLines), where each line contains a JSON object.
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes. If `max_obj_len` is [`usize::MAX`], lines will be read until a `\n` character
+    /// is reached.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const INITIAL_BUF_CAPACITY: usize = 16 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_stream_with_capacity::<MyTestStructure>(MAX_OBJ_LEN, INITIAL_BUF_CAPACITY);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines (NL/NewLines), where each line contains a JSON object.
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes. The line ending mode and the initial buffer capacity are configured
+    /// with [`JsonNlOptions`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::{JsonLineEnding, JsonNlOptions, JsonStreamResponse as _};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_stream_with_options::<MyTestStructure>(
+    ///             MAX_OBJ_LEN,
+    ///             JsonNlOptions::new().with_line_ending(JsonLineEnding::CrLf),
+    ///         );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_stream_with_options<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        options: JsonNlOptions,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines (NL/NewLines), deserializing the lines from the raw
+    /// bytes of the body.
+    ///
+    /// This is a faster variant of [`JsonStreamResponse::json_nl_stream`] for high-throughput
+    /// streams: the lines are found with `memchr` and deserialized in place, without copying
+    /// them into strings or validating their UTF-8 beforehand. serde_json still validates the
+    /// strings of the values, so invalid UTF-8 is a [`crate::error::StreamBodyKind::CodecError`]
+    /// error rather than an [`crate::error::StreamBodyKind::EncodingError`] one. Otherwise the
+    /// lines are decoded as with the default [`JsonNlOptions`]: ended by `\n` or `\r\n`, with
+    /// the blank lines and a leading UTF-8 BOM skipped, and a maximum size of `max_obj_len`
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_fast_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_fast_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines (NL/NewLines), checking the content type of the
+    /// response first.
+    ///
+    /// See [`JsonStreamResponse::json_nl_stream`] for the details. If the media type of the
+    /// response isn't `content_type` (`application/x-ndjson` if `None`), the stream yields a
+    /// single [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error instead of
+    /// decoding the body.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_stream_strict::<MyTestStructure>(MAX_OBJ_LEN, Some("application/jsonl"));
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines, yielding every item along with the progress of the
+    /// download.
+    ///
+    /// Every [`Progress`] has the number of bytes read from the response body when the item was
+    /// decoded, and the `Content-Length` of the response if it was advertised, which is handy to
+    /// render progress bars. See [`JsonStreamResponse::json_nl_stream`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let mut stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_stream_with_progress::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     while let Some(progress) = stream.try_next().await? {
+    ///         if let Some(content_length) = progress.content_length {
+    ///             println!("Downloaded {}/{} bytes", progress.bytes_read, content_length);
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_stream_with_progress<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<Progress<T>>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON lines compressed with gzip as a whole.
+    ///
+    /// This is for bodies that are gzip files themselves, such as `.jsonl.gz` exports, rather
+    /// than bodies with a `Content-Encoding`, which are already decompressed. The decompressed
+    /// lines are decoded as [`JsonStreamResponse::json_nl_stream`] does, with a maximum size of
+    /// `max_obj_len` bytes. Concatenated gzip members are decompressed one after another.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-nl.gz")
+    ///         .await?
+    ///         .json_nl_stream_gzip::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    fn json_nl_stream_gzip<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as a JSON array of untyped [`serde_json::Value`]s.
+    ///
+    /// This is useful when the schema of the elements isn't known ahead of time. Elements may be
+    /// objects, arrays or scalars. Numbers that don't fit into `i64`/`u64`/`f64` follow the
+    /// behaviour of the `arbitrary_precision` feature of `serde_json`, if enabled by your crate.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_value_stream(MAX_OBJ_LEN);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_value_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<serde_json::Value>>;
+
+    /// Streams the response as JSON lines (NL/NewLines) of untyped [`serde_json::Value`]s.
+    ///
+    /// See [`JsonStreamResponse::json_array_value_stream`] for the details about the values.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_value_stream(MAX_OBJ_LEN);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_nl_value_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<serde_json::Value>>;
+
+    /// Streams the response as a JSON array, yielding the raw bytes of each element without
+    /// deserializing it.
+    ///
+    /// Each [`Bytes`] item contains exactly one JSON array element with a maximum size of
+    /// `max_obj_len` bytes, which is useful to validate, log or route elements before
+    /// deserializing them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_raw_stream(MAX_OBJ_LEN);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_raw_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<Bytes>>;
+
+    /// Streams the response as a JSON array, yielding the raw bytes of each element along with
+    /// the element deserialized from them.
+    ///
+    /// The raw bytes are exactly the ones the item was deserialized from, as yielded by
+    /// [`JsonStreamResponse::json_array_raw_stream`], which is useful to audit the input of the
+    /// items. The elements have a maximum size of `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let mut stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_audited_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     while let Some((raw, item)) = stream.try_next().await? {
+    ///         println!("{:?} decoded from {:?}", item, raw);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_audited_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<(Bytes, T)>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as a JSON array, yielding the zero-based index of each element
+    /// along with the element.
+    ///
+    /// The index counts the elements framed in the array, so it's the position of the element
+    /// in the array. An element failing to deserialize still has its index: its error, with the
+    /// index as [`StreamBodyError::item_index`], is yielded instead of the element, and the
+    /// stream goes on with the next elements. Unlike the deserialization errors, the errors of
+    /// the framing or of the response body, such as exceeding `max_obj_len`, end the stream and
+    /// don't have an index, but a byte offset as [`StreamBodyError::position`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let mut stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_enumerated_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     while let Some(result) = stream.next().await {
+    ///         match result {
+    ///             Ok((index, item)) => println!("Element {}: {:?}", index, item),
+    ///             Err(err) => match err.item_index() {
+    ///                 Some(index) => eprintln!("Invalid element {}: {}", index, err),
+    ///                 None => return Err(err.into()),
+    ///             },
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_array_enumerated_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<(usize, T)>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as a JSON array, or as a single item if the body is a lone JSON
+    /// object.
+    ///
+    /// Some endpoints return a single object instead of an array when there's one result. The
+    /// shape is detected from the first significant byte of the body, after a UTF-8 BOM and the
+    /// whitespace: with `[`, the elements are streamed as by
+    /// [`JsonStreamResponse::json_array_stream`], and with `{`, the whole object is buffered
+    /// and yielded as the only item once the body ended. Any other value is a
+    /// [`crate::error::StreamBodyKind::CodecError`] error, and an empty or whitespace-only
+    /// body is an empty stream. The elements and the object have a maximum size of
+    /// `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array-or-object")
+    ///         .await?
+    ///         .json_auto_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_auto_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Reads the response as a JSON array, calling `f` with each element borrowed from the
+    /// decoding buffer.
+    ///
+    /// Unlike the streams, which yield owned items, the [`JsonFrame`] passed to `f` is valid
+    /// only within the call, so it can be deserialized into types borrowing from it, such as
+    /// `&str` fields, with [`JsonFrame::deserialize`]. The strings are then used without
+    /// copying them, which avoids allocating the fields of each item in huge responses. A
+    /// `&str` can't borrow a string with escape sequences: use a `Cow<str>` field with
+    /// `#[serde(borrow)]` for the strings that may have them. The `json-path-errors` feature
+    /// allocates the path of the values while deserializing them.
+    ///
+    /// Returns once the array ended, or with the first error of the response or of `f`. The
+    /// elements have a maximum size of `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct MyBorrowedStructure<'a> {
+    ///     some_test_field: &'a str
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let mut total_len = 0;
+    ///     reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_for_each_borrowed(MAX_OBJ_LEN, |frame| {
+    ///             let item: MyBorrowedStructure = frame.deserialize()?;
+    ///             total_len += item.some_test_field.len();
+    ///             Ok(())
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn json_array_for_each_borrowed<F>(
+        self,
+        max_obj_len: usize,
+        f: F,
+    ) -> StreamBodyResult<()>
+    where
+        F: FnMut(JsonFrame<'_>) -> StreamBodyResult<()> + Send;
+
+    /// Reads the response as JSON lines (NL/NewLines), calling `f` with each line borrowed
+    /// from the decoding buffer.
+    ///
+    /// See [`JsonStreamResponse::json_array_for_each_borrowed`] for the details. The lines have
+    /// a maximum size of `max_obj_len` bytes and the empty lines are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct MyBorrowedStructure<'a> {
+    ///     some_test_field: &'a str
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     reqwest::get("http://localhost:8080/json-nl")
+    ///         .await?
+    ///         .json_nl_for_each_borrowed(MAX_OBJ_LEN, |frame| {
+    ///             let item: MyBorrowedStructure = frame.deserialize()?;
+    ///             println!("{}", item.some_test_field);
+    ///             Ok(())
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn json_nl_for_each_borrowed<F>(self, max_obj_len: usize, f: F) -> StreamBodyResult<()>
+    where
+        F: FnMut(JsonFrame<'_>) -> StreamBodyResult<()> + Send;
+
+    /// Streams the response as a sequence of concatenated JSON values.
+    ///
+    /// The values may be separated by any whitespace or by nothing at all, without the
+    /// surrounding brackets of a JSON array and without a value per line. The stream will
+    /// [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-seq")
+    ///         .await?
+    ///         .json_seq_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_seq_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as [JSON text sequences] (`application/json-seq`), where each JSON
+    /// text is preceded by the record separator (`0x1E`).
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes. The newline following each JSON text is ignored, as well as a missing separator
+    /// before the first one.
+    ///
+    /// [JSON text sequences]: https://www.rfc-editor.org/rfc/rfc7464
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-seq")
+    ///         .await?
+    ///         .json_rs_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_rs_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as JSON values terminated by the `delimiter` byte.
+    ///
+    /// This generalizes [`JsonStreamResponse::json_nl_stream`] to feeds delimited by other
+    /// bytes, such as the ASCII unit separator (`0x1F`). The stream will [`Deserialize`] entries
+    /// as type `T` with a maximum size of `max_obj_len` bytes. Blank entries are skipped, and the
+    /// last entry doesn't need the delimiter.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-us")
+    ///         .await?
+    ///         .json_delimited_stream::<MyTestStructure>(MAX_OBJ_LEN, 0x1F);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_delimited_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        delimiter: u8,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the elements of the JSON array in the `field` of the top-level JSON object of
+    /// the response, such as the `items` of `{"total": 2, "items": [...]}`.
+    ///
+    /// The other fields are skipped without buffering their values, and everything after
+    /// the array is ignored. The stream fails with a codec error if the object doesn't have
+    /// the field or its value isn't an array.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::stream::BoxStream as _;
+    /// use reqwest_streams::JsonStreamResponse as _;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _stream = reqwest::get("http://localhost:8080/json-page")
+    ///         .await?
+    ///         .json_object_field_array_stream::<MyTestStructure>(MAX_OBJ_LEN, "items");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn json_object_field_array_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        field: &str,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+/// A JSON item of a response, borrowed from the decoding buffer by
+/// [`JsonStreamResponse::json_array_for_each_borrowed`] and
+/// [`JsonStreamResponse::json_nl_for_each_borrowed`].
+#[derive(Clone, Copy, Debug)]
+pub struct JsonFrame<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> JsonFrame<'a> {
+    /// The raw bytes of the item.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Deserializes the item into a `T` that may borrow from the frame.
+    ///
+    /// The values nested more than [`DEFAULT_JSON_MAX_DEPTH`] levels deep fail with a
+    /// [`crate::error::StreamBodyKind::CodecError`] error, like the other errors of
+    /// deserialization.
+    pub fn deserialize<T>(&self) -> StreamBodyResult<T>
+    where
+        T: Deserialize<'a>,
+    {
+        deserialize_frame_with_max_depth(self.bytes, DEFAULT_JSON_MAX_DEPTH)
+    }
+}
+
+/// The line ending expected between entries of a JSON lines stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonLineEnding {
+    /// Lines are terminated by `\n`. A preceding `\r` is left as part of the line.
+    Lf,
+
+    /// Lines are terminated by `\r\n`. A line terminated by a bare `\n` is a codec error.
+    CrLf,
+
+    /// Lines are terminated by either `\n` or `\r\n`.
+    Either,
+}
+
+/// Options for streaming the JSON lines (NL/NewLines) format.
+#[derive(Clone, Debug)]
+pub struct JsonNlOptions {
+    line_ending: JsonLineEnding,
+    strip_bom: bool,
+    buf_capacity: usize,
+    buf_shrink_threshold: Option<usize>,
+    max_depth: usize,
+    on_error: OnError,
+}
+
+impl JsonNlOptions {
+    /// Create the default options: [`JsonLineEnding::Either`], a leading UTF-8 BOM stripped,
+    /// the default buffer capacity and [`OnError::Fail`].
+    pub fn new() -> Self {
+        Self {
+            line_ending: JsonLineEnding::Either,
+            strip_bom: true,
+            buf_capacity: INITIAL_CAPACITY,
+            buf_shrink_threshold: None,
+            max_depth: DEFAULT_JSON_MAX_DEPTH,
+            on_error: OnError::Fail,
+        }
+    }
+
+    /// Set the line ending mode.
+    pub fn with_line_ending(mut self, line_ending: JsonLineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Set whether a UTF-8 byte order mark (`EF BB BF`) at the start of the body is stripped.
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.strip_bom = strip_bom;
+        self
+    }
+
+    /// Set the initial capacity of the stream's decoding buffer.
+    pub fn with_buf_capacity(mut self, buf_capacity: usize) -> Self {
+        self.buf_capacity = buf_capacity;
+        self
+    }
+
+    /// Shrink the decoding buffer back to its initial capacity after a line, once it has grown
+    /// over `buf_shrink_threshold` bytes.
+    ///
+    /// By default, the buffer grows to fit the longest line and keeps that capacity until the
+    /// end of the stream.
+    pub fn with_buf_shrink_threshold(mut self, buf_shrink_threshold: usize) -> Self {
+        self.buf_shrink_threshold = Some(buf_shrink_threshold);
+        self
+    }
+
+    /// Set the maximum nesting depth of the arrays and objects in a line, which is
+    /// [`DEFAULT_JSON_MAX_DEPTH`] by default.
+    ///
+    /// Deeper lines fail with a [`crate::error::StreamBodyKind::CodecError`] error. serde_json
+    /// also limits the depth to 128 levels, unless the `json-unbounded-depth` feature is
+    /// enabled.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set what to do with lines that fail to deserialize.
+    pub fn with_on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+}
+
+impl Default for JsonNlOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl JsonStreamResponse for reqwest::Response {
+    fn json_nl_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        self.json_nl_stream_with_capacity(max_obj_len, INITIAL_CAPACITY)
+    }
+
+    fn json_nl_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        self.json_nl_stream_with_options(
+            max_obj_len,
+            JsonNlOptions::new().with_buf_capacity(buf_capacity),
+        )
+    }
+
+    fn json_nl_stream_with_options<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        options: JsonNlOptions,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader(self);
+
+        json_nl_items(reader, max_obj_len, options)
+    }
+
+    fn json_nl_fast_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonNlBytesCodec::new_with_max_length(max_obj_len),
+            "json_nl",
+            max_obj_len,
+        );
+        let frames_reader = tokio_util::codec::FramedRead::new(response_reader(self), codec);
+
+        Box::pin(
+            frames_reader
+                .into_stream()
+                .and_then(|frame| futures::future::ready(deserialize_frame::<T>(&frame))),
+        )
+    }
+
+    fn json_nl_stream_with_progress<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<Progress<T>>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let content_length = self.content_length();
+        let bytes_counter = Arc::new(BytesCounter::default());
+        let reader = response_reader_with_metrics(self, Some(bytes_counter.clone()));
+
+        Box::pin(
+            json_nl_items(reader, max_obj_len, JsonNlOptions::new()).map_ok(move |item| Progress {
+                item,
+                bytes_read: bytes_counter.bytes_read(),
+                content_length,
+            }),
+        )
+    }
+
+    #[cfg(feature = "compression")]
+    fn json_nl_stream_gzip<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let mut reader = async_compression::tokio::bufread::GzipDecoder::new(
+            tokio::io::BufReader::new(response_reader(self)),
+        );
+        reader.multiple_members(true);
+
+        json_nl_items(reader, max_obj_len, JsonNlOptions::new())
+    }
+
+    fn json_array_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        self.json_array_stream_with_capacity(max_obj_len, INITIAL_CAPACITY)
+    }
+
+    fn json_array_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader(self);
+
+        //serde_json::from_reader(read);
+        let codec = traced_codec(
+            JsonArrayCodec::<T>::new_with_max_length(max_obj_len),
+            "json_array",
+            max_obj_len,
+        );
+        let frames_reader =
+            tokio_util::codec::FramedRead::with_capacity(reader, codec, buf_capacity);
+
+        Box::pin(frames_reader.into_stream())
+    }
+
+    fn json_array_stream_checked<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        match check_status(&self) {
+            Ok(()) => self.json_array_stream(max_obj_len),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    fn json_array_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        match check_content_type(&self, content_type.unwrap_or(JSON_CONTENT_TYPE)) {
+            Ok(()) => self.json_array_stream(max_obj_len),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    fn json_array_stream_validated<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonArrayCodec::<T>::new_strict_with_max_length(max_obj_len),
+            "json_array",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_array_stream_lenient<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonArrayCodec::<T>::new_lenient_with_max_length(max_obj_len),
+            "json_array",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_array_stream_with_max_depth<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        max_depth: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonArrayCodec::<T>::new_with_max_length(max_obj_len).with_max_depth(max_depth),
+            "json_array",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_array_stream_with_read_timeout<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        read_timeout: Duration,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader_with_read_timeout(self, read_timeout);
+
+        let codec = traced_codec(
+            JsonArrayCodec::<T>::new_with_max_length(max_obj_len),
+            "json_array",
+            max_obj_len,
+        );
+
+        reader_stream_with_codec_and_capacity(reader, codec, INITIAL_CAPACITY)
+    }
+
+    #[cfg(feature = "json5")]
+    fn json5_array_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader(self);
+
+        let codec = traced_codec(
+            JsonCommentsCodec::new(JsonArrayCodec::<T>::new_with_max_length(max_obj_len)),
+            "json5_array",
+            max_obj_len,
+        );
+        let frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+
+        Box::pin(frames_reader.into_stream())
+    }
+
+    fn json_nl_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        match check_content_type(&self, content_type.unwrap_or(JSON_NL_CONTENT_TYPE)) {
+            Ok(()) => self.json_nl_stream(max_obj_len),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    fn json_array_stream_with_metrics<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        metrics: Arc<dyn StreamMetrics>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader_with_metrics(self, Some(metrics.clone()));
+
+        let codec = traced_codec(
+            JsonArrayCodec::<T>::new_with_max_length(max_obj_len),
+            "json_array",
+            max_obj_len,
+        );
+        let frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+
+        Box::pin(
+            frames_reader
+                .into_stream()
+                .inspect(move |result| match result {
+                    Ok(_) => metrics.on_item(),
+                    Err(err) => metrics.on_error(err),
+                }),
+        )
+    }
+
+    fn json_array_stream_buffered<'a, T>(
+        self,
+        max_obj_len: usize,
+        concurrency: usize,
+    ) -> BoxStream<'a, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'static,
+    {
+        let items = self
+            .json_array_raw_stream(max_obj_len)
+            .map_ok(|frame| async move {
+                tokio::task::spawn_blocking(move || deserialize_frame::<T>(&frame))
+                    .await
+                    .map_err(|err| {
+                        StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+                    })?
+            })
+            .try_buffered(concurrency.max(1));
+
+        Box::pin(items)
+    }
+
+    fn json_array_stream_with_headers<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> (HeaderMap, BoxStream<'b, StreamBodyResult<T>>)
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        stream_with_headers(self, |response| response.json_array_stream(max_obj_len))
+    }
+
+    fn json_array_value_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<serde_json::Value>> {
+        self.json_array_stream::<serde_json::Value>(max_obj_len)
+    }
+
+    fn json_nl_value_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<serde_json::Value>> {
+        self.json_nl_stream::<serde_json::Value>(max_obj_len)
+    }
+
+    fn json_array_raw_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<Bytes>> {
+        let codec = traced_codec(
+            JsonArrayRawCodec::new_with_max_length(max_obj_len),
+            "json_array",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_array_audited_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<(Bytes, T)>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        Box::pin(self.json_array_raw_stream(max_obj_len).and_then(|frame| {
+            futures::future::ready(deserialize_frame::<T>(&frame).map(|item| (frame, item)))
+        }))
+    }
+
+    fn json_array_enumerated_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<(usize, T)>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let mut next_index = 0;
+        Box::pin(self.json_array_raw_stream(max_obj_len).map(move |frame| {
+            let frame = frame?;
+            let index = next_index;
+            next_index += 1;
+            deserialize_frame::<T>(&frame)
+                .map(|item| (index, item))
+                .map_err(|err| err.with_item_index(index))
+        }))
+    }
+
+    fn json_auto_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let reader = response_reader(self);
+
+        let codec = traced_codec(
+            JsonAutoCodec::<T>::new_with_max_length(max_obj_len),
+            "json_auto",
+            max_obj_len,
+        );
+        let frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+
+        Box::pin(frames_reader.into_stream())
+    }
+
+    async fn json_array_for_each_borrowed<F>(
+        self,
+        max_obj_len: usize,
+        mut f: F,
+    ) -> StreamBodyResult<()>
+    where
+        F: FnMut(JsonFrame<'_>) -> StreamBodyResult<()> + Send,
+    {
+        let mut frames = self.json_array_raw_stream(max_obj_len);
+        while let Some(frame) = frames.try_next().await? {
+            f(JsonFrame { bytes: &frame })?;
+        }
+        Ok(())
+    }
+
+    async fn json_nl_for_each_borrowed<F>(
+        self,
+        max_obj_len: usize,
+        mut f: F,
+    ) -> StreamBodyResult<()>
+    where
+        F: FnMut(JsonFrame<'_>) -> StreamBodyResult<()> + Send,
+    {
+        // The lines are split from the decoding buffer without copying them
+        let codec = traced_codec(
+            JsonNlBytesCodec::new_with_max_length(max_obj_len),
+            "json_nl",
+            max_obj_len,
+        );
+        let mut lines = self.stream_with_codec(codec);
+        while let Some(line) = lines.try_next().await? {
+            f(JsonFrame { bytes: &line })?;
+        }
+        Ok(())
+    }
+
+    fn json_seq_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonSeqCodec::<T>::new_with_max_length(max_obj_len),
+            "json_seq",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_rs_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonRsCodec::<T>::new_with_max_length(max_obj_len),
+            "json_rs",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_delimited_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        delimiter: u8,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonDelimitedCodec::<T>::new_with_max_length(max_obj_len, delimiter),
+            "json_delimited",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn json_object_field_array_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        field: &str,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            JsonFieldArrayCodec::<T>::new_with_max_length(max_obj_len, field),
+            "json_field_array",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+}
+
+/// Counts the bytes read from the response body.
+#[derive(Debug, Default)]
+struct BytesCounter(AtomicU64);
+
+impl BytesCounter {
+    fn bytes_read(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl StreamMetrics for BytesCounter {
+    fn on_bytes(&self, len: usize) {
+        self.0.fetch_add(len as u64, Ordering::Relaxed);
+    }
+}
+
+/// Decodes the JSON lines read from `reader`.
+pub(crate) fn json_nl_items<'b, R, T>(
+    reader: R,
+    max_obj_len: usize,
+    options: JsonNlOptions,
+) -> BoxStream<'b, StreamBodyResult<T>>
+where
+    R: AsyncRead + Send + 'b,
+    T: for<'de> Deserialize<'de> + Send + 'b,
+{
+    let codec = traced_codec(
+        ShrinkingCodec::new(
+            JsonNlCodec::new_with_max_length(max_obj_len, options.line_ending, options.strip_bom),
+            options.buf_capacity,
+            options.buf_shrink_threshold,
+        ),
+        "json_nl",
+        max_obj_len,
+    );
+    let frames_reader =
+        tokio_util::codec::FramedRead::with_capacity(reader, codec, options.buf_capacity);
+
+    let max_depth = options.max_depth;
+    let on_error = options.on_error;
+
+    Box::pin(frames_reader.into_stream().filter_map(move |frame_res| {
+        futures::future::ready(match frame_res {
+            Ok(frame_str) => {
+                match deserialize_frame_with_max_depth(frame_str.as_bytes(), max_depth) {
+                    Ok(item) => Some(Ok(item)),
+                    Err(_) if on_error == OnError::Skip => None,
+                    Err(err) => Some(Err(err)),
+                }
+            }
+            Err(err) => Some(Err(err)),
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::{routing::*, Router};
+    use axum_streams::*;
+    use futures::stream;
+    use serde::Serialize;
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field: String,
         test_arr: Vec<MyChildTest>,
     }
 
-    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
-    struct MyChildTest {
-        test_field: String,
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyChildTest {
+        test_field: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field: "TestValue".to_string(),
+                test_arr: vec![
+                    MyChildTest {
+                        test_field: "TestValue1".to_string()
+                    },
+                    MyChildTest {
+                        test_field: "TestValue2".to_string()
+                    }
+                ]
+            };
+            100
+        ]
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_with_metrics() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct TestMetrics {
+            bytes: AtomicUsize,
+            items: AtomicUsize,
+            errors: AtomicUsize,
+        }
+
+        impl StreamMetrics for TestMetrics {
+            fn on_bytes(&self, len: usize) {
+                self.bytes.fetch_add(len, Ordering::Relaxed);
+            }
+
+            fn on_item(&self) {
+                self.items.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn on_error(&self, _err: &StreamBodyError) {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let test_stream_vec = generate_test_structures();
+        let body = serde_json::to_string(&test_stream_vec).unwrap();
+        let body_len = body.len();
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let metrics = Arc::new(TestMetrics::default());
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_with_metrics::<MyTestStructure>(1024, metrics.clone());
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+        assert_eq!(metrics.bytes.load(Ordering::Relaxed), body_len);
+        assert_eq!(metrics.items.load(Ordering::Relaxed), test_stream_vec.len());
+        assert_eq!(metrics.errors.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_seq_stream() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        struct SeqTestStructure {
+            a: i32,
+        }
+
+        let app = Router::new().route("/", get(|| async { "{\"a\":1}{\"a\":2}\n{\"a\":3}" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_seq_stream::<SeqTestStructure>(1024);
+        let items: Vec<SeqTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                SeqTestStructure { a: 1 },
+                SeqTestStructure { a: 2 },
+                SeqTestStructure { a: 3 }
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_rs_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let body: String = test_stream_vec
+            .iter()
+            .map(|item| format!("\u{1e}{}\n", serde_json::to_string(item).unwrap()))
+            .collect();
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_rs_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_checked() {
+        let test_stream_vec = generate_test_structures();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::json_array(stream::iter(test_stream_vec)) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_checked::<MyTestStructure>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, generate_test_structures());
+
+        let results: Vec<StreamBodyResult<MyTestStructure>> = client
+            .get("/not-found")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_checked::<MyTestStructure>(1024)
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 1);
+        let err = results
+            .into_iter()
+            .next()
+            .unwrap()
+            .expect_err("HttpStatusError");
+        assert!(err.is_http_status());
+        assert_eq!(err.status(), Some(reqwest::StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_stream_strict() {
+        let test_stream_vec = generate_test_structures();
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    StreamBodyAs::json_array(stream::iter(generate_test_structures()))
+                }),
+            )
+            .route(
+                "/nl",
+                get(|| async { StreamBodyAs::json_nl(stream::iter(generate_test_structures())) }),
+            )
+            .route(
+                "/html",
+                get(|| async { axum::response::Html("<html>Bad gateway</html>") }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_strict::<MyTestStructure>(1024, None)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        // The server uses a content type of its own for JSON lines
+        let items: Vec<MyTestStructure> = client
+            .get("/nl")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_strict::<MyTestStructure>(1024, Some("application/jsonstream"))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let err = client
+            .get("/nl")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_strict::<MyTestStructure>(1024, None)
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+
+        let results: Vec<StreamBodyResult<MyTestStructure>> = client
+            .get("/html")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_strict::<MyTestStructure>(1024, None)
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 1);
+        let err = results
+            .into_iter()
+            .next()
+            .unwrap()
+            .expect_err("ContentTypeMismatchError");
+        assert!(err.is_content_type_mismatch());
+        assert_eq!(
+            err.message(),
+            Some("Expected content type application/json, but the response has text/html; charset=utf-8")
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_delimited_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let body: String = test_stream_vec
+            .iter()
+            .map(|item| format!("{}\u{1f}", serde_json::to_string(item).unwrap()))
+            .collect();
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_delimited_stream::<MyTestStructure>(1024, 0x1F);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_delimited_stream_as_nl() {
+        let test_stream_vec = generate_test_structures();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { StreamBodyAs::json_nl(stream::iter(test_stream_vec)) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let nl_items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<MyTestStructure>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+        let delimited_items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_delimited_stream::<MyTestStructure>(1024, b'\n')
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(delimited_items, nl_items);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_buffered() {
+        let test_stream_vec: Vec<MyTestStructure> = (0..100)
+            .map(|idx| MyTestStructure {
+                some_test_field: format!("TestValue{}", idx),
+                test_arr: vec![],
+            })
+            .collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        for concurrency in [0, 1, 4] {
+            let res = client
+                .get("/")
+                .send()
+                .await
+                .unwrap()
+                .json_array_stream_buffered::<MyTestStructure>(1024, concurrency);
+            let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+            assert_eq!(items, test_stream_vec);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deserialize_json_array_stream_buffered_large_feed() {
+        let test_stream_vec: Vec<MyTestStructure> = generate_test_structures()
+            .into_iter()
+            .cycle()
+            .take(20_000)
+            .collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let sequential: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        // The timings of both ways are compared by the json-stream bench
+        let buffered: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_buffered::<MyTestStructure>(1024, 4)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(buffered, sequential);
+        assert_eq!(buffered.len(), 20_000);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_buffered_codec_error() {
+        let app = Router::new().route("/", get(|| async { r#"[{"some_test_field": 1}]"# }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_buffered::<MyTestStructure>(1024, 2);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_with_progress() {
+        let test_stream_vec = generate_test_structures();
+
+        let body: String = test_stream_vec
+            .iter()
+            .map(|item| format!("{}\n", serde_json::to_string(item).unwrap()))
+            .collect();
+        let body_len = body.len() as u64;
+
+        // The chunks are read one by one, so the progress moves along the items
+        let chunks: Vec<String> = body.lines().map(|line| format!("{}\n", line)).collect();
+
+        let app = Router::new()
+            .route("/", get(move || async move { body }))
+            .route(
+                "/chunked",
+                get(|| async {
+                    axum::body::Body::from_stream(stream::iter(
+                        chunks.into_iter().map(Ok::<_, axum::Error>),
+                    ))
+                }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_progress::<MyTestStructure>(1024);
+        let items: Vec<Progress<MyTestStructure>> = res.try_collect().await.unwrap();
+
+        assert_eq!(items.len(), test_stream_vec.len());
+        assert!(items
+            .iter()
+            .all(|progress| progress.content_length == Some(body_len)));
+
+        let res = client
+            .get("/chunked")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_progress::<MyTestStructure>(1024);
+        let items: Vec<Progress<MyTestStructure>> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items
+                .iter()
+                .map(|progress| progress.item.clone())
+                .collect::<Vec<_>>(),
+            test_stream_vec
+        );
+        assert!(items
+            .windows(2)
+            .all(|progress| progress[0].bytes_read <= progress[1].bytes_read));
+        assert!(items[0].bytes_read < body_len);
+        assert_eq!(items.last().unwrap().bytes_read, body_len);
+    }
+
+    #[tokio::test]
+    async fn deserialize_empty_bodies() {
+        for body in ["", "[]", " \n\t "] {
+            let app = Router::new().route("/", get(move || async move { body }));
+
+            let client = TestClient::new(app).await;
+
+            let res = client
+                .get("/")
+                .send()
+                .await
+                .unwrap()
+                .json_array_stream::<MyTestStructure>(1024);
+            let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+            assert!(items.is_empty(), "{:?}", body);
+        }
+
+        for body in ["", "\n", " \n\t "] {
+            let app = Router::new().route("/", get(move || async move { body }));
+
+            let client = TestClient::new(app).await;
+
+            let res = client
+                .get("/")
+                .send()
+                .await
+                .unwrap()
+                .json_nl_stream::<MyTestStructure>(1024);
+            let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+            assert!(items.is_empty(), "{:?}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_validated() {
+        // An error page that never ends, so only an early error ends the stream
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = std::iter::once("<html><body>".to_string())
+                    .chain(std::iter::repeat("Service Unavailable".to_string()))
+                    .map(Ok::<_, axum::Error>);
+                axum::body::Body::from_stream(stream::iter(chunks))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_validated::<MyTestStructure>(1024);
+        let err = tokio::time::timeout(
+            Duration::from_secs(5),
+            res.try_collect::<Vec<MyTestStructure>>(),
+        )
+        .await
+        .expect("Early error")
+        .expect_err("CodecError");
+
+        assert!(err.is_codec());
+        assert_eq!(
+            err.message(),
+            Some("Expected a JSON array, found '<' at byte offset 0")
+        );
+
+        let test_stream_vec = generate_test_structures();
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_validated::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { "" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_validated::<MyTestStructure>(1024);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("UnexpectedEofError");
+
+        assert!(err.is_unexpected_eof());
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_lenient() {
+        let app = Router::new()
+            .route("/trailing", get(|| async { "[{\"a\":1},{\"a\":2},]" }))
+            .route("/double", get(|| async { "[{\"a\":1},, {\"a\":2}]" }));
+
+        let client = TestClient::new(app).await;
+
+        for path in ["/trailing", "/double"] {
+            let res = client
+                .get(path)
+                .send()
+                .await
+                .unwrap()
+                .json_array_stream_lenient::<serde_json::Value>(1024);
+            let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+            assert_eq!(
+                items,
+                vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+            );
+
+            let res = client
+                .get(path)
+                .send()
+                .await
+                .unwrap()
+                .json_array_stream::<serde_json::Value>(1024);
+            let err = res
+                .try_collect::<Vec<serde_json::Value>>()
+                .await
+                .expect_err("CodecError");
+
+            assert!(err.is_codec());
+        }
+    }
+
+    #[tokio::test]
+    async fn deserialize_deeply_nested_json_streams() {
+        let nested = format!("{}1{}", "[".repeat(100_000), "]".repeat(100_000));
+        let array_body = format!("[{{\"a\":[[1]]}},{}]", nested);
+        let nl_body = format!("{{\"a\":[[1]]}}\n{}\n", nested);
+
+        let app = Router::new()
+            .route("/array", get(|| async move { array_body }))
+            .route("/nl", get(|| async move { nl_body }));
+
+        let client = TestClient::new(app).await;
+
+        let results: Vec<StreamBodyResult<serde_json::Value>> = client
+            .get("/array")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<serde_json::Value>(1024 * 1024)
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &serde_json::json!({"a": [[1]]})
+        );
+        let err = results[1].as_ref().expect_err("CodecError");
+        assert!(err.is_codec());
+        assert_eq!(
+            err.message(),
+            Some("The JSON value is nested more than 128 levels deep")
+        );
+
+        let err = client
+            .get("/array")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_with_max_depth::<serde_json::Value>(1024 * 1024, 2)
+            .try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("CodecError");
+
+        assert_eq!(
+            err.message(),
+            Some("The JSON value is nested more than 2 levels deep")
+        );
+
+        let items: Vec<serde_json::Value> = client
+            .get("/nl")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_options::<serde_json::Value>(
+                1024 * 1024,
+                JsonNlOptions::new()
+                    .with_max_depth(3)
+                    .with_on_error(OnError::Skip),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, vec![serde_json::json!({"a": [[1]]})]);
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct MyBorrowedStructure<'a> {
+        some_test_field: &'a str,
+    }
+
+    #[tokio::test]
+    async fn json_array_for_each_borrowed_without_item_allocation() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let mut items = Vec::new();
+        client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_for_each_borrowed(1024, |frame| {
+                let item: MyBorrowedStructure = frame.deserialize()?;
+                // The field points into the frame, so it wasn't allocated for the item
+                assert!(frame
+                    .as_bytes()
+                    .as_ptr_range()
+                    .contains(&item.some_test_field.as_ptr()));
+                items.push(item.some_test_field.to_string());
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            items,
+            test_stream_vec
+                .iter()
+                .map(|item| item.some_test_field.clone())
+                .collect::<Vec<String>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn json_nl_for_each_borrowed() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "{\"some_test_field\":\"A\"}\n\n{\"some_test_field\":\"B\"}\n{\"some_test_field\":1}\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let mut items = Vec::new();
+        let err = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_for_each_borrowed(1024, |frame| {
+                let item: MyBorrowedStructure = frame.deserialize()?;
+                items.push(item.some_test_field.to_string());
+                Ok(())
+            })
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+        assert_eq!(items, vec!["A", "B"]);
+
+        // The errors of the callback stop the response
+        let mut calls = 0;
+        let err = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_for_each_borrowed(1024, |_| {
+                calls += 1;
+                Err(StreamBodyError::new(
+                    StreamBodyKind::ValidationError,
+                    None,
+                    None,
+                ))
+            })
+            .await
+            .expect_err("ValidationError");
+
+        assert_eq!(err.kind(), StreamBodyKind::ValidationError);
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn deserialize_bom_prefixed_bodies() {
+        let app = Router::new().route("/", get(|| async { "\u{FEFF}[{\"a\":1},{\"a\":2}]" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<serde_json::Value>(1024);
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+        );
+
+        let app = Router::new().route("/", get(|| async { "\u{FEFF}{\"a\":1}\n{\"a\":2}\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<serde_json::Value>(1024);
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+        );
+
+        // Without stripping, the BOM is a part of the first line
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_options::<serde_json::Value>(
+                1024,
+                JsonNlOptions::new().with_strip_bom(false),
+            );
+        let err = res
+            .try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_with_headers() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                (
+                    [("X-Total-Count", "100")],
+                    StreamBodyAs::json_array(test_stream),
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let (headers, res) = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_with_headers::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(headers.get("X-Total-Count").unwrap(), "100");
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_object_field_array_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        // The siblings are larger than the max object length, as they're never buffered
+        let padding = "x".repeat(4096);
+        let bodies = vec![
+            serde_json::json!({ "items": test_stream_vec, "padding": padding, "total": 100 }),
+            serde_json::json!({ "total": 100, "padding": padding, "items": test_stream_vec }),
+            serde_json::json!({
+                "meta": { "items": [padding], "next": null },
+                "items": test_stream_vec,
+                "padding": [padding],
+            }),
+        ];
+
+        for body in bodies {
+            let body = serde_json::to_string(&body).unwrap();
+            let app = Router::new().route("/", get(|| async { body }));
+
+            let client = TestClient::new(app).await;
+
+            let res = client
+                .get("/")
+                .send()
+                .await
+                .unwrap()
+                .json_object_field_array_stream::<MyTestStructure>(1024, "items");
+            let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+            assert_eq!(items, test_stream_vec);
+        }
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_object_field_array_stream_missing_field() {
+        let app = Router::new().route("/", get(|| async { r#"{"total": 0}"# }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_object_field_array_stream::<MyTestStructure>(1024, "items");
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_timeout() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = vec![
+                    (0, "[{\"some_test_field\":\"TestValue\",\"test_arr\":[]},"),
+                    (2000, "{\"some_test_field\":\"TestValue\",\"test_arr\":[]}]"),
+                ];
+                axum::body::Body::from_stream(stream::iter(chunks).then(
+                    |(delay_ms, chunk)| async move {
+                        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                        Ok::<_, axum::Error>(chunk)
+                    },
+                ))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let err = client
+            .get("/")
+            .timeout(Duration::from_millis(500))
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024)
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("TimeoutError");
+
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_with_read_timeout() {
+        // The server pauses between every few bytes, and stalls before the second element
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    let body = "[{\"some_test_field\":\"TestValue\",\"test_arr\":[]}]";
+                    let chunks: Vec<String> = body
+                        .as_bytes()
+                        .chunks(4)
+                        .map(|chunk| String::from_utf8(chunk.to_vec()).unwrap())
+                        .collect();
+                    axum::body::Body::from_stream(stream::iter(chunks).then(|chunk| async move {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Ok::<_, axum::Error>(chunk)
+                    }))
+                }),
+            )
+            .route(
+                "/stalled",
+                get(|| async {
+                    let chunks = vec![
+                        (0, "[{\"some_test_field\":\"TestValue\",\"test_arr\":[]},"),
+                        (2000, "{\"some_test_field\":\"TestValue\",\"test_arr\":[]}]"),
+                    ];
+                    axum::body::Body::from_stream(stream::iter(chunks).then(
+                        |(delay_ms, chunk)| async move {
+                            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                            Ok::<_, axum::Error>(chunk)
+                        },
+                    ))
+                }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        // The whole body takes longer than the read timeout
+        let items = client
+            .get("/slow")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_with_read_timeout::<MyTestStructure>(
+                1024,
+                Duration::from_millis(300),
+            )
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .unwrap();
+
+        assert_eq!(items.len(), 1);
+
+        let results: Vec<StreamBodyResult<MyTestStructure>> = client
+            .get("/stalled")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_with_read_timeout::<MyTestStructure>(
+                1024,
+                Duration::from_millis(300),
+            )
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].as_ref().expect_err("TimeoutError").is_timeout());
+    }
+
+    #[cfg(feature = "json5")]
+    #[tokio::test]
+    async fn deserialize_json5_array_stream() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "[\n\
+                 // The first element\n\
+                 {\"some_test_field\":\"http://localhost//a\",\"test_arr\":[]},\n\
+                 // The second element\n\
+                 {\"some_test_field\":\"TestValue\",\"test_arr\":[/* empty */]}\n\
+                 ]\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json5_array_stream::<MyTestStructure>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                MyTestStructure {
+                    some_test_field: "http://localhost//a".to_string(),
+                    test_arr: vec![],
+                },
+                MyTestStructure {
+                    some_test_field: "TestValue".to_string(),
+                    test_arr: vec![],
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(10);
+        res.try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_check_len_capacity() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream_with_capacity::<MyTestStructure>(1024, 50);
+
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_of_numbers() {
+        let test_stream_vec: Vec<i64> = (0..100).collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<i64>(1024);
+        let items: Vec<i64> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_of_strings() {
+        let test_stream_vec: Vec<String> = (0..100)
+            .map(|idx| format!("Test \"value\" [{}], {{}}", idx))
+            .collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<String>(1024);
+        let items: Vec<String> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_of_arrays() {
+        let test_stream_vec: Vec<Vec<i64>> = (0..100).map(|idx| vec![idx, idx * 2]).collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<Vec<i64>>(1024);
+        let items: Vec<Vec<i64>> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_stream_of_tuples() {
+        let test_stream_vec: Vec<(i64, String)> = (0..100)
+            .map(|idx| (idx, format!("Test [{}], \"{{}}\"", idx)))
+            .collect();
+
+        // The chunks split the elements at arbitrary positions, including inside the strings
+        let body = serde_json::to_vec(&test_stream_vec).unwrap();
+        let chunks: Vec<Vec<u8>> = body.chunks(5).map(|chunk| chunk.to_vec()).collect();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                axum::body::Body::from_stream(stream::iter(
+                    chunks.into_iter().map(Ok::<_, axum::Error>),
+                ))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<(i64, String)>(1024);
+        let items: Vec<(i64, String)> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_value_stream() {
+        let test_stream_vec = vec![
+            serde_json::json!({ "some_test_field": "TestValue", "test_arr": [1, 2] }),
+            serde_json::json!(42),
+            serde_json::json!("TestValue"),
+            serde_json::json!(null),
+            serde_json::json!([true, false]),
+            serde_json::json!(1.5),
+        ];
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_value_stream(1024);
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_value_stream() {
+        let test_stream_vec = vec![
+            serde_json::json!({ "some_test_field": "TestValue" }),
+            serde_json::json!(42),
+            serde_json::json!(["TestValue"]),
+        ];
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_nl(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_value_stream(1024);
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
     }
 
-    fn generate_test_structures() -> Vec<MyTestStructure> {
-        vec![
-            MyTestStructure {
-                some_test_field: "TestValue".to_string(),
-                test_arr: vec![
-                    MyChildTest {
-                        test_field: "TestValue1".to_string()
-                    },
-                    MyChildTest {
-                        test_field: "TestValue2".to_string()
-                    }
-                ]
-                .iter()
-                .cloned()
-                .collect()
-            };
-            100
-        ]
+    #[tokio::test]
+    async fn deserialize_json_array_raw_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_raw_stream(1024);
+        let items: Vec<Bytes> = res.try_collect().await.unwrap();
+
+        let expected_items: Vec<Bytes> = test_stream_vec
+            .iter()
+            .map(|item| Bytes::from(serde_json::to_vec(item).unwrap()))
+            .collect();
+
+        assert_eq!(items, expected_items);
     }
 
     #[tokio::test]
-    async fn deserialize_json_array_stream() {
+    async fn deserialize_json_array_audited_stream() {
         let test_stream_vec = generate_test_structures();
 
         let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
@@ -285,14 +3222,19 @@ mod tests {
             .send()
             .await
             .unwrap()
-            .json_array_stream::<MyTestStructure>(1024);
-        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+            .json_array_audited_stream::<MyTestStructure>(1024);
+        let items: Vec<(Bytes, MyTestStructure)> = res.try_collect().await.unwrap();
 
-        assert_eq!(items, test_stream_vec);
+        assert_eq!(items.len(), test_stream_vec.len());
+        for ((raw, item), expected_item) in items.iter().zip(test_stream_vec.iter()) {
+            assert_eq!(item, expected_item);
+            let raw_item: MyTestStructure = serde_json::from_slice(raw).unwrap();
+            assert_eq!(&raw_item, item);
+        }
     }
 
     #[tokio::test]
-    async fn deserialize_json_array_stream_check_max_len() {
+    async fn deserialize_json_array_enumerated_stream() {
         let test_stream_vec = generate_test_structures();
 
         let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
@@ -306,14 +3248,44 @@ mod tests {
             .send()
             .await
             .unwrap()
-            .json_array_stream::<MyTestStructure>(10);
-        res.try_collect::<Vec<MyTestStructure>>()
+            .json_array_enumerated_stream::<MyTestStructure>(1024);
+        let items: Vec<(usize, MyTestStructure)> = res.try_collect().await.unwrap();
+
+        let expected_items: Vec<(usize, MyTestStructure)> =
+            test_stream_vec.into_iter().enumerate().collect();
+        assert_eq!(items, expected_items);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_array_enumerated_stream_with_invalid_element() {
+        let app = Router::new().route("/", get(|| async { "[1,\"two\",3,{\"four\":4},5," }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
             .await
-            .expect_err("MaxLenReachedError");
+            .unwrap()
+            .json_array_enumerated_stream::<u32>(1024);
+        let results: Vec<StreamBodyResult<(usize, u32)>> = res.collect().await;
+
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[0].as_ref().unwrap(), &(0, 1));
+        let err = results[1].as_ref().expect_err("CodecError");
+        assert!(err.is_codec());
+        assert_eq!(err.item_index(), Some(1));
+        assert_eq!(results[2].as_ref().unwrap(), &(2, 3));
+        assert_eq!(results[3].as_ref().unwrap_err().item_index(), Some(3));
+        assert_eq!(results[4].as_ref().unwrap(), &(4, 5));
+        // The body ends before the closing bracket of the array
+        let err = results[5].as_ref().expect_err("UnexpectedEofError");
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.item_index(), None);
     }
 
     #[tokio::test]
-    async fn deserialize_json_array_stream_check_len_capacity() {
+    async fn deserialize_json_auto_stream_array() {
         let test_stream_vec = generate_test_structures();
 
         let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
@@ -327,13 +3299,49 @@ mod tests {
             .send()
             .await
             .unwrap()
-            .json_array_stream_with_capacity::<MyTestStructure>(1024, 50);
-
+            .json_auto_stream::<MyTestStructure>(1024);
         let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
 
         assert_eq!(items, test_stream_vec);
     }
 
+    #[tokio::test]
+    async fn deserialize_json_auto_stream_object() {
+        let test_structure = generate_test_structures().remove(0);
+
+        let body = format!("\n{}\n", serde_json::to_string(&test_structure).unwrap());
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_auto_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, vec![test_structure]);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_auto_stream_empty_body() {
+        let app = Router::new().route("/", get(|| async { "" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_auto_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert!(items.is_empty());
+    }
+
     #[tokio::test]
     async fn deserialize_json_nl_stream() {
         let test_stream_vec = generate_test_structures();
@@ -355,6 +3363,343 @@ mod tests {
         assert_eq!(items, test_stream_vec);
     }
 
+    #[tokio::test]
+    async fn deserialize_json_nl_fast_stream_as_json_nl_stream() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        struct SimpleStructure {
+            a: i64,
+        }
+
+        // A BOM, both line endings, blank lines and an invalid item, sent in small chunks
+        let body = "\u{feff}{\"a\":1}\r\n\n{\"a\":\"x\"}\n  \r\n{\"a\":2}\n{\"a\":3}";
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks: Vec<Vec<u8>> = body.as_bytes().chunks(3).map(Vec::from).collect();
+                axum::body::Body::from_stream(stream::iter(
+                    chunks.into_iter().map(Ok::<_, axum::Error>),
+                ))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let fast_results: Vec<Result<SimpleStructure, StreamBodyKind>> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_fast_stream::<SimpleStructure>(1024)
+            .map_err(|err| err.kind())
+            .collect()
+            .await;
+        let results: Vec<Result<SimpleStructure, StreamBodyKind>> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<SimpleStructure>(1024)
+            .map_err(|err| err.kind())
+            .collect()
+            .await;
+
+        assert_eq!(fast_results, results);
+        assert_eq!(
+            fast_results,
+            vec![
+                Ok(SimpleStructure { a: 1 }),
+                Err(StreamBodyKind::CodecError),
+                Ok(SimpleStructure { a: 2 }),
+                Ok(SimpleStructure { a: 3 }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_fast_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_nl(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_fast_stream::<MyTestStructure>(10);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+        assert_eq!(err.limit(), Some(10));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn deserialize_json_nl_fast_stream_large_feed() {
+        let test_stream_vec: Vec<MyTestStructure> = generate_test_structures()
+            .into_iter()
+            .cycle()
+            .take(20_000)
+            .collect();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_nl(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<MyTestStructure>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        // The timings of both paths are compared by the json-stream bench
+        let fast_items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_fast_stream::<MyTestStructure>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(fast_items, items);
+        assert_eq!(fast_items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_crlf() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        struct SimpleStructure {
+            a: i64,
+        }
+
+        let app = Router::new().route("/", get(|| async { "{\"a\":1}\r\n{\"a\":2}\r\n" }));
+
+        let client = TestClient::new(app).await;
+
+        for line_ending in [JsonLineEnding::CrLf, JsonLineEnding::Either] {
+            let res = client
+                .get("/")
+                .send()
+                .await
+                .unwrap()
+                .json_nl_stream_with_options::<SimpleStructure>(
+                    1024,
+                    JsonNlOptions::new().with_line_ending(line_ending),
+                );
+            let items: Vec<SimpleStructure> = res.try_collect().await.unwrap();
+
+            assert_eq!(
+                items,
+                vec![SimpleStructure { a: 1 }, SimpleStructure { a: 2 }]
+            );
+        }
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<SimpleStructure>(1024);
+        let items: Vec<SimpleStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![SimpleStructure { a: 1 }, SimpleStructure { a: 2 }]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_shrinking_buffer() {
+        let mut body = format!("\"{}\"\n", "a".repeat(64 * 1024));
+        for idx in 0..100 {
+            body.push_str(&format!("{}\n", idx));
+        }
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_options::<serde_json::Value>(
+                1024 * 1024,
+                JsonNlOptions::new()
+                    .with_buf_capacity(64)
+                    .with_buf_shrink_threshold(1024),
+            );
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(items.len(), 101);
+        assert_eq!(items[0], serde_json::json!("a".repeat(64 * 1024)));
+        assert_eq!(items[100], serde_json::json!(99));
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_crlf_expected() {
+        let app = Router::new().route("/", get(|| async { "{\"a\":1}\n{\"a\":2}\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_options::<serde_json::Value>(
+                1024,
+                JsonNlOptions::new().with_line_ending(JsonLineEnding::CrLf),
+            );
+        res.try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("CodecError");
+    }
+
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_skip_errors() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        struct SimpleStructure {
+            a: i64,
+        }
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { "{\"a\":1}\n{\"a\":\n{\"b\":2}\n{\"a\":3}\n" }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_with_options::<SimpleStructure>(
+                1024,
+                JsonNlOptions::new().with_on_error(OnError::Skip),
+            );
+        let items: Vec<SimpleStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![SimpleStructure { a: 1 }, SimpleStructure { a: 3 }]
+        );
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<SimpleStructure>(1024);
+        let results: Vec<StreamBodyResult<SimpleStructure>> = res.collect().await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results.iter().filter(|res| res.is_err()).count(), 2);
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_gzip_encoded() {
+        use async_compression::tokio::bufread::GzipEncoder;
+        use tokio::io::AsyncReadExt;
+
+        let test_stream_vec = generate_test_structures();
+
+        let mut body = Vec::new();
+        for item in test_stream_vec.iter() {
+            body.extend(serde_json::to_vec(item).unwrap());
+            body.push(b'\n');
+        }
+        let mut compressed_body = Vec::new();
+        GzipEncoder::new(body.as_slice())
+            .read_to_end(&mut compressed_body)
+            .await
+            .unwrap();
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                (
+                    [(axum::http::header::CONTENT_ENCODING, "gzip")],
+                    compressed_body,
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn deserialize_json_nl_stream_gzip() {
+        use async_compression::tokio::bufread::GzipEncoder;
+        use tokio::io::AsyncReadExt;
+
+        let test_stream_vec = generate_test_structures();
+
+        let mut body = Vec::new();
+        for item in test_stream_vec.iter() {
+            body.extend(serde_json::to_vec(item).unwrap());
+            body.push(b'\n');
+        }
+        let mut compressed_body = Vec::new();
+        GzipEncoder::new(body.as_slice())
+            .read_to_end(&mut compressed_body)
+            .await
+            .unwrap();
+
+        // The gzip file is served as is, without a Content-Encoding
+        let chunks: Vec<Vec<u8>> = compressed_body
+            .chunks(16)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                axum::body::Body::from_stream(stream::iter(
+                    chunks.into_iter().map(Ok::<_, axum::Error>),
+                ))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream_gzip::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
     #[tokio::test]
     async fn deserialize_json_nl_stream_check_max_len() {
         let test_stream_vec = generate_test_structures();
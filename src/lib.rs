@@ -5,9 +5,19 @@
 //! Streaming responses support for reqwest for different formats:
 //! - JSON array stream format
 //! - JSON Lines (NL/NewLines) format
+//! - Concatenated JSON values format
+//! - [JSON text sequences] (RFC 7464) format
 //! - CSV stream format
 //! - [Protobuf] len-prefixed stream format
 //! - [Apache Arrow IPC] stream format
+//! - [MessagePack] len-prefixed and concatenated stream formats
+//! - [CBOR] sequence stream format
+//! - [BSON] document sequence stream format
+//! - [YAML] multi-document stream format
+//! - [Apache Avro] single-object encoding stream format
+//! - [Server-Sent Events] (`text/event-stream`) format
+//! - [gRPC] length-prefixed message format
+//! - [Apache Parquet] files, decoded by row groups
 //!
 //! This type of responses are useful when you are reading huge stream of objects from some source (such as database, file, etc)
 //! and want to avoid huge memory allocations to store on the server side.
@@ -16,10 +26,29 @@
 //!
 //! **Note:** The `default` features do not include any formats.
 //!
-//! - `json`: JSON array and JSON Lines (JSONL) stream formats
+//! - `json`: JSON array, JSON Lines (JSONL), delimited JSON, concatenated JSON and JSON text
+//!   sequences stream formats
+//! - `json-path-errors`: the JSON pointer of the value failing to deserialize in the errors of
+//!   the JSON streams
+//! - `json-unbounded-depth`: JSON values nested deeper than the recursion limit of serde_json,
+//!   up to the maximum depth of the stream
+//! - `json5`: JSON arrays with `//` and `/* */` comments
 //! - `csv`: CSV stream format
 //! - `protobuf`: [Protobuf] len-prefixed stream format
-//! - `arrow`: [Apache Arrow IPC] stream format
+//! - `arrow`: [Apache Arrow IPC] stream and file formats
+//! - `arrow-compression`: LZ4 and ZSTD compressed Arrow IPC record batches
+//! - `arrow-serde`: deserialization of Arrow IPC stream rows with serde
+//! - `messagepack`: [MessagePack] len-prefixed and concatenated stream formats
+//! - `cbor`: [CBOR] sequence stream format
+//! - `bson`: [BSON] document sequence stream format
+//! - `yaml`: [YAML] multi-document stream format
+//! - `avro`: [Apache Avro] single-object encoding stream format
+//! - `sse`: [Server-Sent Events] format
+//! - `grpc`: [gRPC] server-streaming responses of Protobuf messages
+//! - `parquet`: [Apache Parquet] files
+//! - `compression`: transparent decompression of response bodies according to `Content-Encoding`,
+//!   and JSON lines in gzip files
+//! - `tracing`: [tracing] spans and events of the decoded frames and the errors of the streams
 //!
 //! # Example
 //!
@@ -51,27 +80,52 @@
 //! - [axum-streams](https://github.com/abdolence/axum-streams-rs).
 //!
 //!
+//! [JSON text sequences]: https://www.rfc-editor.org/rfc/rfc7464
 //! [Apache Arrow IPC]: https://arrow.apache.org/docs/format/Columnar.html#serialization-and-interprocess-communication-ipc
 //! [Protobuf]: https://protobuf.dev/programming-guides/encoding/
+//! [MessagePack]: https://msgpack.org/
+//! [CBOR]: https://www.rfc-editor.org/rfc/rfc8742.html
+//! [BSON]: https://bsonspec.org/spec.html
+//! [YAML]: https://yaml.org/spec/1.2.2/#streams
+//! [Apache Avro]: https://avro.apache.org/docs/1.11.1/specification/#single-object-encoding
+//! [Server-Sent Events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+//! [gRPC]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md
+//! [Apache Parquet]: https://parquet.apache.org/docs/file-format/
+//! [tracing]: https://docs.rs/tracing
 
 #[macro_use]
 mod macros;
 
 cfg_json! {
-    pub use json_stream::JsonStreamResponse;
+    pub use json_stream::{
+        JsonFrame, JsonItemStream, JsonLineEnding, JsonNlOptions, JsonStreamResponse,
+        DEFAULT_JSON_MAX_DEPTH,
+    };
     mod json_stream;
+    pub mod json;
     mod json_array_codec;
+    mod json_auto_codec;
+    mod json_nl_codec;
+    mod json_seq_codec;
+    mod json_rs_codec;
+    mod json_delimited_codec;
+    mod json_field_array_codec;
+}
+
+cfg_json5! {
+    mod json_comments_codec;
 }
 
 cfg_csv! {
-    pub use csv_stream::CsvStreamResponse;
+    pub use csv_stream::{CsvOptions, CsvStreamResponse};
     mod csv_stream;
+    mod csv_codec;
 }
 
 use crate::error::StreamBodyError;
 
 cfg_protobuf! {
-    pub use protobuf_stream::ProtobufStreamResponse;
+    pub use protobuf_stream::{LengthPrefix, ProtobufStreamResponse};
     mod protobuf_stream;
     mod protobuf_len_codec;
 }
@@ -82,8 +136,110 @@ cfg_arrow! {
     mod arrow_ipc_len_codec;
 }
 
+cfg_messagepack! {
+    pub use msgpack_stream::MessagePackStreamResponse;
+    mod msgpack_stream;
+    mod msgpack_len_codec;
+    mod msgpack_seq_codec;
+}
+
+cfg_cbor! {
+    pub use cbor_stream::CborStreamResponse;
+    mod cbor_stream;
+    mod cbor_seq_codec;
+}
+
+cfg_bson! {
+    pub use bson_stream::BsonStreamResponse;
+    mod bson_stream;
+    mod bson_codec;
+}
+
+cfg_yaml! {
+    pub use yaml_stream::YamlStreamResponse;
+    mod yaml_stream;
+    mod yaml_codec;
+}
+
+cfg_avro! {
+    pub use avro_stream::AvroStreamResponse;
+    mod avro_stream;
+    mod avro_codec;
+}
+
+cfg_sse! {
+    pub use sse_stream::{SseEvent, SseStreamResponse};
+    mod sse_stream;
+    mod sse_codec;
+}
+
+cfg_grpc! {
+    pub use grpc_stream::GrpcStreamResponse;
+    mod grpc_stream;
+    mod grpc_codec;
+}
+
+cfg_parquet! {
+    pub use parquet_stream::ParquetStreamResponse;
+    mod parquet_stream;
+}
+
 pub mod error;
 
+pub mod util;
+
+pub use codec_stream::CodecStreamResponse;
+mod codec_stream;
+
+#[cfg(any(
+    feature = "json",
+    feature = "csv",
+    feature = "protobuf",
+    feature = "arrow"
+))]
+pub use stream_builder::{StreamBuilder, StreamBuilderResponse, DEFAULT_MAX_OBJ_LEN};
+#[cfg(any(
+    feature = "json",
+    feature = "csv",
+    feature = "protobuf",
+    feature = "arrow"
+))]
+mod stream_builder;
+
+pub use stream_ext::{FusedItemStream, StreamBodyExt};
+mod stream_ext;
+
+pub use metrics::{Progress, StreamMetrics};
+mod metrics;
+
+pub use response_limits::StreamLimitsResponse;
+mod response_limits;
+
+mod response_reader;
+
+#[cfg(any(feature = "protobuf", feature = "messagepack"))]
+mod varint;
+
+#[cfg(any(feature = "json", feature = "csv", feature = "sse"))]
+mod utf8_bom;
+
+#[cfg(any(
+    feature = "json",
+    feature = "csv",
+    feature = "protobuf",
+    feature = "arrow",
+    feature = "messagepack",
+    feature = "cbor",
+    feature = "bson",
+    feature = "yaml",
+    feature = "sse",
+    feature = "grpc"
+))]
+mod stream_tracing;
+
+#[cfg(any(feature = "json", feature = "csv"))]
+mod buffer_shrink;
+
 /// Alias for the [`Result`] type returned by streaming responses.
 pub type StreamBodyResult<T> = std::result::Result<T, StreamBodyError>;
 
@@ -37,3 +37,103 @@ macro_rules! cfg_protobuf {
         )*
     }
 }
+
+macro_rules! cfg_compression {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "compression")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_messagepack {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "messagepack")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "messagepack")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_cbor {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "cbor")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_bson {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "bson")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "bson")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_yaml {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "yaml")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_avro {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "avro")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "avro")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_sse {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "sse")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "sse")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_grpc {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "grpc")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "grpc")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_parquet {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "parquet")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "parquet")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_json5 {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "json5")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "json5")))]
+            $item
+        )*
+    }
+}
@@ -0,0 +1,30 @@
+use crate::StreamBodyError;
+
+/// Receives the metrics of a stream, such as the number of bytes read and items decoded.
+///
+/// All the methods do nothing by default, so implementations may only observe what they need.
+/// They are called on the task polling the stream, or on the task reading the body ahead with
+/// [`crate::StreamLimitsResponse::with_read_buffer`], so they should be cheap.
+pub trait StreamMetrics: Send + Sync {
+    /// Called for every chunk of bytes read from the response body, before any decompression.
+    fn on_bytes(&self, _len: usize) {}
+
+    /// Called for every successfully decoded item.
+    fn on_item(&self) {}
+
+    /// Called for every error yielded by the stream.
+    fn on_error(&self, _err: &StreamBodyError) {}
+}
+
+/// An item of a stream along with the progress of the download of the response body.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress<T> {
+    /// The decoded item.
+    pub item: T,
+
+    /// The number of bytes read from the response body so far, before any decompression.
+    pub bytes_read: u64,
+
+    /// The `Content-Length` of the response, if it was advertised.
+    pub content_length: Option<u64>,
+}
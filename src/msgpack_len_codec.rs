@@ -0,0 +1,157 @@
+use crate::error::StreamBodyKind;
+use crate::varint::decode_varint_slice;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct MessagePackLenPrefixCodec<T> {
+    max_length: usize,
+    cursor: MessagePackCursor,
+    _ph: PhantomData<T>,
+}
+
+#[derive(Clone, Debug)]
+struct MessagePackCursor {
+    current_obj_len: usize,
+    consumed_len: usize,
+}
+
+impl<T> MessagePackLenPrefixCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        let initial_cursor = MessagePackCursor {
+            current_obj_len: 0,
+            consumed_len: 0,
+        };
+
+        MessagePackLenPrefixCodec {
+            max_length,
+            cursor: initial_cursor,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for MessagePackLenPrefixCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        let buf_len = buf.len();
+        if buf_len == 0 {
+            return Ok(None);
+        }
+
+        if self.cursor.current_obj_len == 0 {
+            let bytes = buf.chunk();
+            let byte = bytes[0];
+            if byte < 0x80 {
+                buf.advance(1);
+                self.cursor.consumed_len += 1;
+                self.cursor.current_obj_len = u64::from(byte) as usize;
+                self.decode(buf)
+            } else if buf_len > 10 || bytes[buf_len - 1] < 0x80 {
+                let (value, advance) = decode_varint_slice(bytes)?;
+                buf.advance(advance);
+                self.cursor.consumed_len += advance;
+                self.cursor.current_obj_len = value as usize;
+                self.decode(buf)
+            } else {
+                Ok(None) // wait more bytes for len
+            }
+        } else if self.cursor.current_obj_len > self.max_length {
+            Err(StreamBodyError::max_len_reached(
+                self.max_length,
+                self.cursor.consumed_len,
+            ))
+        } else if buf_len >= self.cursor.current_obj_len {
+            let obj_bytes = buf.split_to(self.cursor.current_obj_len);
+            self.cursor.consumed_len += self.cursor.current_obj_len;
+            let result: Result<Option<T>, StreamBodyError> = rmp_serde::from_slice::<T>(&obj_bytes)
+                .map(Some)
+                .map_err(|err| {
+                    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+                });
+            self.cursor.current_obj_len = 0;
+            result
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            None if self.cursor.current_obj_len > 0 => Err(StreamBodyError::unexpected_eof(
+                self.cursor.consumed_len + buf.len(),
+                format!(
+                    "The stream ended inside a MessagePack value of {} bytes, after {} bytes",
+                    self.cursor.current_obj_len,
+                    buf.len()
+                ),
+            )),
+            None if !buf.is_empty() => Err(StreamBodyError::unexpected_eof(
+                self.cursor.consumed_len + buf.len(),
+                "The stream ended inside the length prefix of a MessagePack value",
+            )),
+            result => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+    struct MyTestStructure {
+        some_test_field: String,
+    }
+
+    fn encode_frame(test_structure: &MyTestStructure) -> Vec<u8> {
+        let bytes = rmp_serde::to_vec_named(test_structure).unwrap();
+        // The test values are shorter than 128 bytes, so the length is a single varint byte
+        let mut frame = vec![bytes.len() as u8];
+        frame.extend_from_slice(&bytes);
+        frame
+    }
+
+    #[test]
+    fn decode_eof_truncated_frame() {
+        let test_structure = MyTestStructure {
+            some_test_field: "TestValue1".to_string(),
+        };
+        let frame = encode_frame(&test_structure);
+
+        for truncated_len in [1, frame.len() - 1] {
+            let mut buf = BytesMut::from(&frame[..]);
+            buf.extend_from_slice(&frame[..truncated_len]);
+            let mut codec = MessagePackLenPrefixCodec::<MyTestStructure>::new_with_max_length(1024);
+
+            assert_eq!(
+                codec.decode_eof(&mut buf).unwrap(),
+                Some(test_structure.clone())
+            );
+            let err = codec.decode_eof(&mut buf).expect_err("UnexpectedEofError");
+            assert!(err.is_unexpected_eof(), "{}", truncated_len);
+            assert_eq!(err.position(), Some(frame.len() + truncated_len));
+        }
+    }
+
+    #[test]
+    fn decode_eof_truncated_length_prefix() {
+        let mut codec = MessagePackLenPrefixCodec::<MyTestStructure>::new_with_max_length(1024);
+
+        let err = codec
+            .decode_eof(&mut BytesMut::from(&[0x80u8][..]))
+            .expect_err("UnexpectedEofError");
+
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.position(), Some(1));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct MessagePackSeqCodec<T> {
+    max_length: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> MessagePackSeqCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        MessagePackSeqCodec {
+            max_length,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for MessagePackSeqCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        // MessagePack values are self-delimiting, so the number of bytes consumed by the
+        // deserializer is the length of the value
+        let mut remaining: &[u8] = buf.as_ref();
+        let mut deserializer = rmp_serde::Deserializer::new(&mut remaining);
+        match T::deserialize(&mut deserializer) {
+            Ok(item) => {
+                let obj_len = buf.len() - remaining.len();
+                if obj_len > self.max_length {
+                    return Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.consumed_len.saturating_add(self.max_length),
+                    ));
+                }
+                buf.advance(obj_len);
+                self.consumed_len += obj_len;
+                Ok(Some(item))
+            }
+            Err(
+                rmp_serde::decode::Error::InvalidMarkerRead(err)
+                | rmp_serde::decode::Error::InvalidDataRead(err),
+            ) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                if buf.len() > self.max_length {
+                    Err(StreamBodyError::max_len_reached(
+                        self.max_length,
+                        self.consumed_len.saturating_add(self.max_length),
+                    ))
+                } else {
+                    Ok(None) // wait more bytes for the value
+                }
+            }
+            Err(err) => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                Some(Box::new(err)),
+                None,
+            )),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            None if !buf.is_empty() => Err(StreamBodyError::unexpected_eof(
+                self.consumed_len + buf.len(),
+                "Incomplete MessagePack value at the end of the stream",
+            )),
+            result => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+    struct MyTestStructure {
+        some_test_field: String,
+        some_test_num: f64,
+        some_test_vec: Vec<u32>,
+    }
+
+    fn generate_test_sequence() -> (Vec<MyTestStructure>, Vec<u8>) {
+        let items: Vec<MyTestStructure> = (0..10)
+            .map(|idx| MyTestStructure {
+                some_test_field: format!("TestValue{}", idx).repeat(idx + 1),
+                some_test_num: idx as f64 * 1.5,
+                some_test_vec: (0..idx as u32 * 100).collect(),
+            })
+            .collect();
+        let mut sequence = Vec::new();
+        for item in items.iter() {
+            sequence.extend(rmp_serde::to_vec_named(item).unwrap());
+        }
+        (items, sequence)
+    }
+
+    #[test]
+    fn decode_msgpack_sequence_across_chunks() {
+        let (expected_items, sequence) = generate_test_sequence();
+
+        for chunk_size in [1, 3, 7, 64, sequence.len()] {
+            let mut codec = MessagePackSeqCodec::<MyTestStructure>::new_with_max_length(4096);
+            let mut buf = BytesMut::new();
+            let mut items = Vec::new();
+            for chunk in sequence.chunks(chunk_size) {
+                buf.extend_from_slice(chunk);
+                while let Some(item) = codec.decode(&mut buf).unwrap() {
+                    items.push(item);
+                }
+            }
+            while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+                items.push(item);
+            }
+
+            assert_eq!(items, expected_items);
+        }
+    }
+
+    #[test]
+    fn decode_msgpack_sequence_truncated() {
+        let (_, sequence) = generate_test_sequence();
+
+        let mut codec = MessagePackSeqCodec::<MyTestStructure>::new_with_max_length(4096);
+        let mut buf = BytesMut::from(&sequence[..sequence.len() - 1]);
+        let mut result = Ok(None);
+        for _ in 0..10 {
+            result = codec.decode_eof(&mut buf);
+        }
+
+        let err = result.expect_err("UnexpectedEofError");
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.position(), Some(sequence.len() - 1));
+    }
+
+    #[test]
+    fn decode_msgpack_sequence_max_len() {
+        let (_, sequence) = generate_test_sequence();
+
+        let mut codec = MessagePackSeqCodec::<MyTestStructure>::new_with_max_length(64);
+        let mut buf = BytesMut::new();
+        let mut result = Ok(None);
+        for chunk in sequence.chunks(16) {
+            buf.extend_from_slice(chunk);
+            result = codec.decode(&mut buf);
+            while let Ok(Some(_)) = result {
+                result = codec.decode(&mut buf);
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+
+        assert!(result.expect_err("MaxLenReachedError").is_max_len());
+    }
+}
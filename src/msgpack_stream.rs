@@ -0,0 +1,231 @@
+use crate::msgpack_len_codec::MessagePackLenPrefixCodec;
+use crate::msgpack_seq_codec::MessagePackSeqCodec;
+use crate::stream_tracing::traced_codec;
+use crate::CodecStreamResponse;
+use crate::StreamBodyResult;
+use async_trait::*;
+use futures::stream::BoxStream;
+use serde::Deserialize;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for the
+/// len-prefixed and the concatenated [MessagePack format].
+///
+/// [MessagePack format]: https://msgpack.org/
+#[async_trait]
+pub trait MessagePackStreamResponse {
+    /// Streams the response as MessagePack objects, each prefixed with its length encoded as a
+    /// varint (the same framing as the Protobuf stream format).
+    ///
+    /// The stream will [`Deserialize`] entries as type `T` with a maximum size of `max_obj_len`
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::MessagePackStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/msgpack")
+    ///         .await?
+    ///         .msgpack_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn msgpack_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+
+    /// Streams the response as a sequence of concatenated MessagePack values, without any
+    /// length prefix.
+    ///
+    /// MessagePack values are self-delimiting, so each value is decoded as soon as all of its
+    /// bytes are received. The stream will [`Deserialize`] entries as type `T` with a maximum
+    /// size of `max_obj_len` bytes. A body ending with an incomplete value is a
+    /// [`crate::error::StreamBodyKind::UnexpectedEofError`] error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::MessagePackStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/msgpack-seq")
+    ///         .await?
+    ///         .msgpack_seq_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn msgpack_seq_stream<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+#[async_trait]
+impl MessagePackStreamResponse for reqwest::Response {
+    fn msgpack_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            MessagePackLenPrefixCodec::<T>::new_with_max_length(max_obj_len),
+            "messagepack",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn msgpack_seq_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            MessagePackSeqCodec::<T>::new_with_max_length(max_obj_len),
+            "messagepack_seq",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::{routing::*, Router};
+    use futures::TryStreamExt;
+    use serde::Serialize;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field1: String,
+        some_test_field2: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    fn encode_len_prefixed(items: &[MyTestStructure]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for item in items {
+            let obj_bytes = rmp_serde::to_vec_named(item).unwrap();
+            let mut len = obj_bytes.len();
+            while len >= 0x80 {
+                body.push((len as u8 & 0x7F) | 0x80);
+                len >>= 7;
+            }
+            body.push(len as u8);
+            body.extend(obj_bytes);
+        }
+        body
+    }
+
+    #[tokio::test]
+    async fn deserialize_msgpack_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let body = encode_len_prefixed(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .msgpack_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_msgpack_seq_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let body: Vec<u8> = test_stream_vec
+            .iter()
+            .flat_map(|item| rmp_serde::to_vec_named(item).unwrap())
+            .collect();
+
+        // Values split across the chunks of the body
+        let app = Router::new().route(
+            "/",
+            get(|| async move {
+                let chunks: Vec<Result<Vec<u8>, std::io::Error>> =
+                    body.chunks(7).map(|chunk| Ok(chunk.to_vec())).collect();
+                axum::body::Body::from_stream(futures::stream::iter(chunks))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .msgpack_seq_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_msgpack_stream_check_max_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let body = encode_len_prefixed(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .msgpack_stream::<MyTestStructure>(10);
+        res.try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+    }
+}
@@ -0,0 +1,206 @@
+use crate::error::StreamBodyKind;
+use crate::response_reader::response_reader;
+use crate::{StreamBodyError, StreamBodyResult};
+use arrow::array::RecordBatch;
+use async_trait::*;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ParquetRecordBatchReaderBuilder};
+use tokio::io::AsyncReadExt;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for [Apache
+/// Parquet] files.
+///
+/// [Apache Parquet]: https://parquet.apache.org/docs/file-format/
+#[async_trait]
+pub trait ParquetStreamResponse {
+    /// Streams the response as the row groups of a Parquet file, one [`RecordBatch`] per row
+    /// group.
+    ///
+    /// The metadata of a Parquet file is in the footer at the end of the file, so the file can't
+    /// be decoded incrementally. The whole response is buffered first, up to `max_obj_len` bytes,
+    /// and the row groups are decoded with [`parquet::arrow::arrow_reader`] afterwards, one at a
+    /// time. Uncompressed, Snappy and ZSTD compressed pages are supported.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use arrow::array::RecordBatch;
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ParquetStreamResponse as _;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_FILE_LEN: usize = 16 * 1024 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/parquet")
+    ///         .await?
+    ///         .parquet_stream(MAX_FILE_LEN);
+    ///     let _items: Vec<RecordBatch> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn parquet_stream<'a>(self, max_obj_len: usize)
+        -> BoxStream<'a, StreamBodyResult<RecordBatch>>;
+}
+
+#[async_trait]
+impl ParquetStreamResponse for reqwest::Response {
+    fn parquet_stream<'a>(
+        self,
+        max_obj_len: usize,
+    ) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
+        let reader = response_reader(self);
+
+        let batches = async move {
+            let mut buf = Vec::new();
+            reader
+                .take(max_obj_len as u64 + 1)
+                .read_to_end(&mut buf)
+                .await?;
+            if buf.len() > max_obj_len {
+                return Err(StreamBodyError::max_len_reached(max_obj_len, buf.len()));
+            }
+
+            let file = Bytes::from(buf);
+            let metadata =
+                ArrowReaderMetadata::load(&file, Default::default()).map_err(codec_error)?;
+            let row_groups_len = metadata.metadata().num_row_groups();
+
+            Ok(futures::stream::iter(
+                (0..row_groups_len).map(move |row_group| {
+                    read_row_group(file.clone(), metadata.clone(), row_group)
+                }),
+            )
+            .try_filter_map(|batch| futures::future::ready(Ok(batch))))
+        };
+
+        Box::pin(futures::stream::once(batches).try_flatten())
+    }
+}
+
+/// Reads all the rows of a row group as a single batch, or `None` if the row group is empty.
+fn read_row_group(
+    file: Bytes,
+    metadata: ArrowReaderMetadata,
+    row_group: usize,
+) -> StreamBodyResult<Option<RecordBatch>> {
+    let rows_len = metadata.metadata().row_group(row_group).num_rows() as usize;
+    ParquetRecordBatchReaderBuilder::new_with_metadata(file, metadata)
+        .with_row_groups(vec![row_group])
+        .with_batch_size(rows_len.max(1))
+        .build()
+        .map_err(codec_error)?
+        .next()
+        .transpose()
+        .map_err(codec_error)
+}
+
+fn codec_error<E>(err: E) -> StreamBodyError
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use arrow::array::{Float64Array, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use axum::{routing::*, Router};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    fn generate_test_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("city", DataType::Utf8, false),
+            Field::new("lat", DataType::Float64, false),
+        ]))
+    }
+
+    fn generate_test_batches() -> Vec<RecordBatch> {
+        (0i64..10i64)
+            .map(move |idx| {
+                RecordBatch::try_new(
+                    generate_test_schema(),
+                    vec![
+                        Arc::new(Int64Array::from(vec![idx, idx * 2, idx * 3])),
+                        Arc::new(StringArray::from(vec!["New York", "London", "Gothenburg"])),
+                        Arc::new(Float64Array::from(vec![40.7128, 51.5074, 57.7089])),
+                    ],
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn generate_test_file(batches: &[RecordBatch]) -> Vec<u8> {
+        // A row group for every batch
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(3)
+            .build();
+        let mut file = Vec::new();
+        let mut writer =
+            ArrowWriter::try_new(&mut file, generate_test_schema(), Some(props)).unwrap();
+        for batch in batches {
+            writer.write(batch).unwrap();
+        }
+        writer.close().unwrap();
+        file
+    }
+
+    #[tokio::test]
+    async fn deserialize_parquet_stream() {
+        let test_stream_vec = generate_test_batches();
+        let test_file = generate_test_file(&test_stream_vec);
+
+        let app = Router::new().route("/", get(|| async { test_file }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .parquet_stream(1024 * 1024);
+        let items: Vec<RecordBatch> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let res = client.get("/").send().await.unwrap().parquet_stream(64);
+        let err = res
+            .try_collect::<Vec<RecordBatch>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+        assert_eq!(err.position(), Some(65));
+    }
+
+    #[tokio::test]
+    async fn deserialize_parquet_stream_not_parquet() {
+        let app = Router::new().route("/", get(|| async { "id,city\n1,London\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .parquet_stream(1024 * 1024);
+        let err = res
+            .try_collect::<Vec<RecordBatch>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::error::StreamBodyKind;
+use crate::protobuf_stream::LengthPrefix;
+use crate::varint::decode_varint_slice;
 use crate::StreamBodyError;
 use bytes::{Buf, BytesMut};
 use std::marker::PhantomData;
@@ -6,25 +8,62 @@ use std::marker::PhantomData;
 #[derive(Clone, Debug)]
 pub struct ProtobufLenPrefixCodec<T> {
     max_length: usize,
+    length_prefix: LengthPrefix,
     cursor: ProtobufCursor,
     _ph: PhantomData<T>,
 }
 
 #[derive(Clone, Debug)]
 struct ProtobufCursor {
-    current_obj_len: usize,
+    current_obj_len: Option<usize>,
+    consumed_len: usize,
 }
 
 impl<T> ProtobufLenPrefixCodec<T> {
-    pub fn new_with_max_length(max_length: usize) -> Self {
-        let initial_cursor = ProtobufCursor { current_obj_len: 0 };
+    pub fn new_with_max_length(max_length: usize, length_prefix: LengthPrefix) -> Self {
+        let initial_cursor = ProtobufCursor {
+            current_obj_len: None,
+            consumed_len: 0,
+        };
 
         ProtobufLenPrefixCodec {
             max_length,
+            length_prefix,
             cursor: initial_cursor,
             _ph: PhantomData,
         }
     }
+
+    fn decode_len(&mut self, buf: &mut BytesMut) -> Result<Option<usize>, StreamBodyError> {
+        let buf_len = buf.len();
+        let (value, advance) = match self.length_prefix {
+            LengthPrefix::Varint => {
+                if buf_len == 0 {
+                    return Ok(None);
+                }
+                let bytes = buf.chunk();
+                if bytes[0] < 0x80 {
+                    (u64::from(bytes[0]), 1)
+                } else if buf_len > 10 || bytes[buf_len - 1] < 0x80 {
+                    decode_varint_slice(bytes)?
+                } else {
+                    return Ok(None); // wait more bytes for len
+                }
+            }
+            LengthPrefix::U32Be if buf_len >= 4 => (
+                u64::from(u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]])),
+                4,
+            ),
+            LengthPrefix::U32Le if buf_len >= 4 => (
+                u64::from(u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])),
+                4,
+            ),
+            LengthPrefix::U32Be | LengthPrefix::U32Le => return Ok(None),
+        };
+        buf.advance(advance);
+        self.cursor.consumed_len += advance;
+        Ok(Some(value as usize))
+    }
 }
 
 impl<T> tokio_util::codec::Decoder for ProtobufLenPrefixCodec<T>
@@ -35,145 +74,310 @@ where
     type Error = StreamBodyError;
 
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
-        let buf_len = buf.len();
-        if buf_len == 0 {
-            return Ok(None);
-        }
+        let obj_len = match self.cursor.current_obj_len {
+            Some(obj_len) => obj_len,
+            None => match self.decode_len(buf)? {
+                Some(obj_len) => {
+                    self.cursor.current_obj_len = Some(obj_len);
+                    obj_len
+                }
+                None => return Ok(None),
+            },
+        };
 
-        if self.cursor.current_obj_len == 0 {
-            let bytes = buf.chunk();
-            let byte = bytes[0];
-            if byte < 0x80 {
-                buf.advance(1);
-                self.cursor.current_obj_len = u64::from(byte) as usize;
-                Ok(None)
-            } else if buf_len > 10 || bytes[buf_len - 1] < 0x80 {
-                let (value, advance) = decode_varint_slice(bytes)?;
-                buf.advance(advance);
-                self.cursor.current_obj_len = value as usize;
-                Ok(None)
-            } else {
-                Ok(None) // wait more bytes for len
-            }
-        } else if self.cursor.current_obj_len > self.max_length {
-            Err(StreamBodyError::new(
-                StreamBodyKind::MaxLenReachedError,
-                None,
-                Some("Max object length reached".into()),
+        if obj_len > self.max_length {
+            Err(StreamBodyError::max_len_reached(
+                self.max_length,
+                self.cursor.consumed_len,
             ))
-        } else if buf_len >= self.cursor.current_obj_len {
-            let obj_bytes = buf.copy_to_bytes(self.cursor.current_obj_len);
-            let result: Result<Option<T>, StreamBodyError> = prost::Message::decode(obj_bytes)
-                .map(|res| Some(res))
-                .map_err(|err| {
-                    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
-                });
-            self.cursor.current_obj_len = 0;
-            result
+        } else if buf.len() >= obj_len {
+            let obj_bytes = buf.copy_to_bytes(obj_len);
+            self.cursor.consumed_len += obj_len;
+            self.cursor.current_obj_len = None;
+            prost::Message::decode(obj_bytes).map(Some).map_err(|err| {
+                StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+            })
         } else {
             Ok(None)
         }
     }
 
     fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
-        self.decode(buf)
+        match self.decode(buf)? {
+            None => match self.cursor.current_obj_len {
+                Some(obj_len) => Err(StreamBodyError::unexpected_eof(
+                    self.cursor.consumed_len + buf.len(),
+                    format!(
+                        "The stream ended inside a Protobuf message of {} bytes, after {} bytes",
+                        obj_len,
+                        buf.len()
+                    ),
+                )),
+                None if !buf.is_empty() => Err(StreamBodyError::unexpected_eof(
+                    self.cursor.consumed_len + buf.len(),
+                    "The stream ended inside the length prefix of a Protobuf message",
+                )),
+                None => Ok(None),
+            },
+            result => Ok(result),
+        }
     }
 }
 
-/// This function is copied from Prost, since it is not available as public API yet optimized for performance.
-///
-/// Decodes a LEB128-encoded variable length integer from the slice, returning the value and the
-/// number of bytes read.
-///
-/// Based loosely on [`ReadVarint64FromArray`][1] with a varint overflow check from
-/// [`ConsumeVarint`][2].
-///
-/// ## Safety
-///
-/// The caller must ensure that `bytes` is non-empty and either `bytes.len() >= 10` or the last
-/// element in bytes is < `0x80`.
-///
-/// [1]: https://github.com/google/protobuf/blob/3.3.x/src/google/protobuf/io/coded_stream.cc#L365-L406
-/// [2]: https://github.com/protocolbuffers/protobuf-go/blob/v1.27.1/encoding/protowire/wire.go#L358
-#[inline]
-fn decode_varint_slice(bytes: &[u8]) -> Result<(u64, usize), StreamBodyError> {
-    // Fully unrolled varint decoding loop. Splitting into 32-bit pieces gives better performance.
-
-    // Use assertions to ensure memory safety, but it should always be optimized after inline.
-    assert!(!bytes.is_empty());
-    assert!(bytes.len() > 10 || bytes[bytes.len() - 1] < 0x80);
-
-    let mut b: u8 = bytes[0];
-    let mut part0: u32 = u32::from(b);
-    if b < 0x80 {
-        return Ok((u64::from(part0), 1));
-    };
-    part0 -= 0x80;
-    b = bytes[1];
-    part0 += u32::from(b) << 7;
-    if b < 0x80 {
-        return Ok((u64::from(part0), 2));
-    };
-    part0 -= 0x80 << 7;
-    b = bytes[2];
-    part0 += u32::from(b) << 14;
-    if b < 0x80 {
-        return Ok((u64::from(part0), 3));
-    };
-    part0 -= 0x80 << 14;
-    b = bytes[3];
-    part0 += u32::from(b) << 21;
-    if b < 0x80 {
-        return Ok((u64::from(part0), 4));
-    };
-    part0 -= 0x80 << 21;
-    let value = u64::from(part0);
-
-    b = bytes[4];
-    let mut part1: u32 = u32::from(b);
-    if b < 0x80 {
-        return Ok((value + (u64::from(part1) << 28), 5));
-    };
-    part1 -= 0x80;
-    b = bytes[5];
-    part1 += u32::from(b) << 7;
-    if b < 0x80 {
-        return Ok((value + (u64::from(part1) << 28), 6));
-    };
-    part1 -= 0x80 << 7;
-    b = bytes[6];
-    part1 += u32::from(b) << 14;
-    if b < 0x80 {
-        return Ok((value + (u64::from(part1) << 28), 7));
-    };
-    part1 -= 0x80 << 14;
-    b = bytes[7];
-    part1 += u32::from(b) << 21;
-    if b < 0x80 {
-        return Ok((value + (u64::from(part1) << 28), 8));
-    };
-    part1 -= 0x80 << 21;
-    let value = value + ((u64::from(part1)) << 28);
-
-    b = bytes[8];
-    let mut part2: u32 = u32::from(b);
-    if b < 0x80 {
-        return Ok((value + (u64::from(part2) << 56), 9));
-    };
-    part2 -= 0x80;
-    b = bytes[9];
-    part2 += u32::from(b) << 7;
-    // Check for u64::MAX overflow. See [`ConsumeVarint`][1] for details.
-    // [1]: https://github.com/protocolbuffers/protobuf-go/blob/v1.27.1/encoding/protowire/wire.go#L358
-    if b < 0x02 {
-        return Ok((value + (u64::from(part2) << 56), 10));
-    };
-
-    // We have overrun the maximum size of a varint (10 bytes) or the final byte caused an overflow.
-    // Assume the data is corrupt.
-    Err(StreamBodyError::new(
-        StreamBodyKind::CodecError,
-        None,
-        Some("invalid varint".into()),
-    ))
+/// Decodes concatenated Protobuf messages of the same `message_size`, without any prefix.
+#[derive(Clone, Debug)]
+pub struct ProtobufFixedSizeCodec<T> {
+    message_size: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> ProtobufFixedSizeCodec<T> {
+    pub fn new(message_size: usize) -> Self {
+        ProtobufFixedSizeCodec {
+            message_size,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for ProtobufFixedSizeCodec<T>
+where
+    T: prost::Message + Default,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        if buf.is_empty() || buf.len() < self.message_size {
+            return Ok(None);
+        }
+        if self.message_size == 0 {
+            // Empty frames would never consume the buffer
+            return Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some("The size of the messages must be positive".into()),
+            ));
+        }
+
+        let obj_bytes = buf.copy_to_bytes(self.message_size);
+        self.consumed_len += self.message_size;
+        prost::Message::decode(obj_bytes).map(Some).map_err(|err| {
+            StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+        })
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            None if !buf.is_empty() => Err(StreamBodyError::new(
+                StreamBodyKind::CodecError,
+                None,
+                Some(format!(
+                    "Incomplete message of {} bytes at byte offset {}, expected {} bytes",
+                    buf.len(),
+                    self.consumed_len,
+                    self.message_size
+                )),
+            )),
+            result => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Clone, prost::Message, PartialEq, Eq)]
+    struct MyTestStructure {
+        #[prost(string, tag = "1")]
+        some_test_field: String,
+    }
+
+    fn decode_in_chunks(
+        codec: &mut ProtobufLenPrefixCodec<MyTestStructure>,
+        input: &[u8],
+        chunk_size: usize,
+    ) -> Vec<MyTestStructure> {
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+        items
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field: "TestValue1".to_string(),
+            },
+            MyTestStructure {
+                some_test_field: "".to_string(),
+            },
+            MyTestStructure {
+                some_test_field: "TestValue2".repeat(20),
+            },
+        ]
+    }
+
+    fn encode_test_structures(
+        test_structures: &[MyTestStructure],
+        encode_len: fn(u32) -> [u8; 4],
+    ) -> Vec<u8> {
+        let mut input = Vec::new();
+        for test_structure in test_structures {
+            let bytes = prost::Message::encode_to_vec(test_structure);
+            input.extend_from_slice(&encode_len(bytes.len() as u32));
+            input.extend_from_slice(&bytes);
+        }
+        input
+    }
+
+    #[test]
+    fn decode_varint_prefix() {
+        let test_structures = generate_test_structures();
+        let mut input = Vec::new();
+        for test_structure in test_structures.iter() {
+            prost::Message::encode_length_delimited(test_structure, &mut input).unwrap();
+        }
+
+        for chunk_size in [1, 3, input.len()] {
+            let mut codec = ProtobufLenPrefixCodec::new_with_max_length(1024, LengthPrefix::Varint);
+            assert_eq!(
+                decode_in_chunks(&mut codec, &input, chunk_size),
+                test_structures
+            );
+        }
+    }
+
+    #[test]
+    fn decode_fixed_size_frames() {
+        #[derive(Clone, prost::Message, PartialEq)]
+        struct MyFixedSizeStructure {
+            #[prost(fixed64, tag = "1")]
+            id: u64,
+            #[prost(double, tag = "2")]
+            value: f64,
+        }
+
+        let test_structures: Vec<MyFixedSizeStructure> = (1..=10)
+            .map(|idx| MyFixedSizeStructure {
+                id: idx,
+                value: idx as f64 * 0.5,
+            })
+            .collect();
+        let input: Vec<u8> = test_structures
+            .iter()
+            .flat_map(prost::Message::encode_to_vec)
+            .collect();
+        let message_size = input.len() / test_structures.len();
+
+        for chunk_size in [1, 7, input.len()] {
+            let mut codec = ProtobufFixedSizeCodec::<MyFixedSizeStructure>::new(message_size);
+            let mut buf = BytesMut::new();
+            let mut items = Vec::new();
+            for chunk in input.chunks(chunk_size) {
+                buf.extend_from_slice(chunk);
+                while let Some(item) = codec.decode(&mut buf).unwrap() {
+                    items.push(item);
+                }
+            }
+            assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+
+            assert_eq!(items, test_structures);
+        }
+
+        let mut codec = ProtobufFixedSizeCodec::<MyFixedSizeStructure>::new(message_size);
+        let mut buf = BytesMut::from(&input[..message_size + 3]);
+        assert!(codec.decode_eof(&mut buf).unwrap().is_some());
+        let err = codec.decode_eof(&mut buf).expect_err("CodecError");
+        assert!(err.is_codec());
+    }
+
+    #[test]
+    fn decode_empty_input() {
+        for length_prefix in [
+            LengthPrefix::Varint,
+            LengthPrefix::U32Be,
+            LengthPrefix::U32Le,
+        ] {
+            let mut codec = ProtobufLenPrefixCodec::new_with_max_length(1024, length_prefix);
+            assert_eq!(decode_in_chunks(&mut codec, &[], 1), vec![]);
+        }
+    }
+
+    #[test]
+    fn decode_truncated_input() {
+        let test_structure = generate_test_structures().remove(2);
+        let mut input = Vec::new();
+        prost::Message::encode_length_delimited(&test_structure, &mut input).unwrap();
+        let prefix_len = input.len() - prost::Message::encoded_len(&test_structure);
+
+        // The length prefix, then fewer bytes than promised
+        let mut codec = ProtobufLenPrefixCodec::<MyTestStructure>::new_with_max_length(
+            1024,
+            LengthPrefix::Varint,
+        );
+        let mut buf = BytesMut::from(&input[..prefix_len + 10]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        let err = codec.decode_eof(&mut buf).expect_err("UnexpectedEofError");
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.position(), Some(prefix_len + 10));
+
+        // A partial length prefix
+        let mut codec = ProtobufLenPrefixCodec::<MyTestStructure>::new_with_max_length(
+            1024,
+            LengthPrefix::Varint,
+        );
+        let mut buf = BytesMut::from(&input[..1]);
+        let err = codec.decode_eof(&mut buf).expect_err("UnexpectedEofError");
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.position(), Some(1));
+
+        let mut codec = ProtobufLenPrefixCodec::<MyTestStructure>::new_with_max_length(
+            1024,
+            LengthPrefix::U32Be,
+        );
+        let mut buf = BytesMut::from(&[0u8, 0][..]);
+        let err = codec.decode_eof(&mut buf).expect_err("UnexpectedEofError");
+        assert!(err.is_unexpected_eof());
+    }
+
+    #[test]
+    fn decode_u32_be_prefix() {
+        let test_structures = generate_test_structures();
+        let input = encode_test_structures(&test_structures, u32::to_be_bytes);
+
+        for chunk_size in [1, 3, input.len()] {
+            let mut codec = ProtobufLenPrefixCodec::new_with_max_length(1024, LengthPrefix::U32Be);
+            assert_eq!(
+                decode_in_chunks(&mut codec, &input, chunk_size),
+                test_structures
+            );
+        }
+    }
+
+    #[test]
+    fn decode_u32_le_prefix() {
+        let test_structures = generate_test_structures();
+        let input = encode_test_structures(&test_structures, u32::to_le_bytes);
+
+        for chunk_size in [1, 3, input.len()] {
+            let mut codec = ProtobufLenPrefixCodec::new_with_max_length(1024, LengthPrefix::U32Le);
+            assert_eq!(
+                decode_in_chunks(&mut codec, &input, chunk_size),
+                test_structures
+            );
+        }
+    }
 }
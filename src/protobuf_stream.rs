@@ -1,10 +1,14 @@
-use crate::protobuf_len_codec::ProtobufLenPrefixCodec;
+use crate::codec_stream::stream_with_codec_and_capacity;
+use crate::protobuf_len_codec::{ProtobufFixedSizeCodec, ProtobufLenPrefixCodec};
+use crate::response_reader::{check_content_type, INITIAL_CAPACITY};
+use crate::stream_tracing::traced_codec;
 
+use crate::CodecStreamResponse;
 use crate::StreamBodyResult;
 use async_trait::*;
 use futures::stream::BoxStream;
-use futures::TryStreamExt;
-use tokio_util::io::StreamReader;
+
+pub(crate) const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
 
 /// Extension trait for [`reqwest::Response`] that provides streaming support for the [Protobuf
 /// format].
@@ -15,7 +19,8 @@ pub trait ProtobufStreamResponse {
     /// Streams the response as batches of Protobuf messages.
     ///
     /// The stream will deserialize [`prost::Message`]s as type `T` with a maximum size of
-    /// `max_obj_len` bytes.
+    /// `max_obj_len` bytes. A body ending with an incomplete length prefix or message is a
+    /// [`crate::error::StreamBodyKind::UnexpectedEofError`] error.
     ///
     /// # Example
     ///
@@ -44,6 +49,180 @@ pub trait ProtobufStreamResponse {
     fn protobuf_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
     where
         T: prost::Message + Default + Send + 'b;
+
+    /// Streams the response as batches of Protobuf messages.
+    ///
+    /// See [`ProtobufStreamResponse::protobuf_stream`] for the details. `buf_capacity` is the
+    /// initial capacity of the stream's decoding buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ProtobufStreamResponse as _;
+    ///
+    /// #[derive(Clone, prost::Message)]
+    /// struct MyTestStructure {
+    ///     #[prost(string, tag = "1")]
+    ///     some_test_field: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const INITIAL_BUF_CAPACITY: usize = 16 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/protobuf")
+    ///         .await?
+    ///         .protobuf_stream_with_capacity::<MyTestStructure>(MAX_OBJ_LEN, INITIAL_BUF_CAPACITY);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn protobuf_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b;
+
+    /// Streams the response as batches of Protobuf messages, checking the content type of the
+    /// response first.
+    ///
+    /// See [`ProtobufStreamResponse::protobuf_stream`] for the details. If the media type of the
+    /// response isn't `content_type` (`application/x-protobuf` if `None`), the stream yields a
+    /// single [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error instead of
+    /// decoding the body.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ProtobufStreamResponse as _;
+    ///
+    /// #[derive(Clone, prost::Message)]
+    /// struct MyTestStructure {
+    ///     #[prost(string, tag = "1")]
+    ///     some_test_field: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/protobuf")
+    ///         .await?
+    ///         .protobuf_stream_strict::<MyTestStructure>(MAX_OBJ_LEN, None);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn protobuf_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b;
+
+    /// Streams the response as batches of Protobuf messages prefixed with the given kind of
+    /// length.
+    ///
+    /// See [`ProtobufStreamResponse::protobuf_stream`] for the details. The fixed 4-byte
+    /// prefixes are used by gRPC-style framings and servers writing lengths with
+    /// Go's `binary.Write`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::{LengthPrefix, ProtobufStreamResponse as _};
+    ///
+    /// #[derive(Clone, prost::Message)]
+    /// struct MyTestStructure {
+    ///     #[prost(string, tag = "1")]
+    ///     some_test_field: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/protobuf")
+    ///         .await?
+    ///         .protobuf_stream_with_length_prefix::<MyTestStructure>(
+    ///             MAX_OBJ_LEN,
+    ///             LengthPrefix::U32Be,
+    ///         );
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn protobuf_stream_with_length_prefix<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        length_prefix: LengthPrefix,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b;
+
+    /// Streams the response as concatenated Protobuf messages of the same `message_size`
+    /// bytes, without a length prefix.
+    ///
+    /// This suits the producers of constant-size messages, such as the ones with only fixed
+    /// width fields (`fixed32`, `fixed64`, `double`, ...) often used for telemetry. A body
+    /// ending with an incomplete message is a codec error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::ProtobufStreamResponse as _;
+    ///
+    /// #[derive(Clone, prost::Message)]
+    /// struct MySample {
+    ///     #[prost(fixed64, tag = "1")]
+    ///     timestamp: u64,
+    ///     #[prost(double, tag = "2")]
+    ///     value: f64,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     // The tags and the fixed width values of both fields
+    ///     const MESSAGE_SIZE: usize = 18;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/samples")
+    ///         .await?
+    ///         .protobuf_stream_fixed_size::<MySample>(MESSAGE_SIZE);
+    ///     let _samples: Vec<MySample> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn protobuf_stream_fixed_size<'a, 'b, T>(
+        self,
+        message_size: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b;
+}
+
+/// The encoding of the length prefixing each message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthPrefix {
+    /// A LEB128 varint, as written by [`prost::Message::encode_length_delimited`].
+    Varint,
+
+    /// A fixed 32-bit big-endian unsigned integer.
+    U32Be,
+
+    /// A fixed 32-bit little-endian unsigned integer.
+    U32Le,
 }
 
 #[async_trait]
@@ -52,15 +231,71 @@ impl ProtobufStreamResponse for reqwest::Response {
     where
         T: prost::Message + Default + Send + 'b,
     {
-        let reader = StreamReader::new(
-            self.bytes_stream()
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)),
+        self.protobuf_stream_with_capacity(max_obj_len, INITIAL_CAPACITY)
+    }
+
+    fn protobuf_stream_with_capacity<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        buf_capacity: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b,
+    {
+        let codec = traced_codec(
+            ProtobufLenPrefixCodec::<T>::new_with_max_length(max_obj_len, LengthPrefix::Varint),
+            "protobuf",
+            max_obj_len,
         );
 
-        let codec = ProtobufLenPrefixCodec::<T>::new_with_max_length(max_obj_len);
-        let frames_reader = tokio_util::codec::FramedRead::new(reader, codec);
+        stream_with_codec_and_capacity(self, codec, buf_capacity)
+    }
 
-        Box::pin(frames_reader.into_stream())
+    fn protobuf_stream_strict<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        content_type: Option<&str>,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b,
+    {
+        match check_content_type(&self, content_type.unwrap_or(PROTOBUF_CONTENT_TYPE)) {
+            Ok(()) => self.protobuf_stream(max_obj_len),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    fn protobuf_stream_with_length_prefix<'a, 'b, T>(
+        self,
+        max_obj_len: usize,
+        length_prefix: LengthPrefix,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b,
+    {
+        let codec = traced_codec(
+            ProtobufLenPrefixCodec::<T>::new_with_max_length(max_obj_len, length_prefix),
+            "protobuf",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn protobuf_stream_fixed_size<'a, 'b, T>(
+        self,
+        message_size: usize,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b,
+    {
+        let codec = traced_codec(
+            ProtobufFixedSizeCodec::<T>::new(message_size),
+            "protobuf",
+            message_size,
+        );
+
+        self.stream_with_codec(codec)
     }
 }
 
@@ -70,7 +305,7 @@ mod tests {
     use crate::test_client::*;
     use axum::{routing::*, Router};
     use axum_streams::*;
-    use futures::stream;
+    use futures::{stream, StreamExt, TryStreamExt};
 
     #[derive(Clone, prost::Message, PartialEq, Eq)]
     struct MyTestStructure {
@@ -127,8 +362,188 @@ mod tests {
             .await
             .unwrap()
             .protobuf_stream::<MyTestStructure>(10);
-        res.try_collect::<Vec<MyTestStructure>>()
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
             .await
             .expect_err("MaxLenReachedError");
+
+        assert_eq!(err.limit(), Some(10));
+        assert_eq!(err.position(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn deserialize_proto_stream_with_capacity() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::protobuf(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream_with_capacity::<MyTestStructure>(1024, 4);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_proto_stream_strict() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::protobuf(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        // The server uses a content type of its own for the Protobuf streams
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream_strict::<MyTestStructure>(1024, Some("application/x-protobuf-stream"));
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream_strict::<MyTestStructure>(1024, None);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+    }
+
+    #[tokio::test]
+    async fn deserialize_proto_stream_with_u32_be_prefix() {
+        let test_stream_vec = generate_test_structures();
+
+        let mut body = Vec::new();
+        for test_structure in test_stream_vec.iter() {
+            let bytes = prost::Message::encode_to_vec(test_structure);
+            body.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            body.extend_from_slice(&bytes);
+        }
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream_with_length_prefix::<MyTestStructure>(1024, LengthPrefix::U32Be);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_proto_stream_fixed_size() {
+        #[derive(Clone, prost::Message, PartialEq)]
+        struct MySample {
+            #[prost(fixed64, tag = "1")]
+            timestamp: u64,
+            #[prost(double, tag = "2")]
+            value: f64,
+        }
+
+        let test_stream_vec: Vec<MySample> = (1..=100)
+            .map(|idx| MySample {
+                timestamp: 1_700_000_000 + idx,
+                value: idx as f64 * 1.5,
+            })
+            .collect();
+
+        let body: Vec<u8> = test_stream_vec
+            .iter()
+            .flat_map(prost::Message::encode_to_vec)
+            .collect();
+        assert_eq!(body.len(), 18 * test_stream_vec.len());
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream_fixed_size::<MySample>(18);
+        let items: Vec<MySample> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_proto_stream_empty_body() {
+        let app = Router::new().route("/", get(|| async { "" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert!(items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn deserialize_proto_stream_with_trailing_bytes() {
+        let test_stream_vec = generate_test_structures();
+
+        // The frames are followed by bytes that aren't a frame, such as a leaked trailer
+        let mut body = Vec::new();
+        for test_structure in test_stream_vec.iter() {
+            body.extend(prost::Message::encode_length_delimited_to_vec(
+                test_structure,
+            ));
+        }
+        body.extend_from_slice(b"grpc-status: 0\r\n");
+        let body_len = body.len();
+
+        let app = Router::new().route("/", get(|| async { body }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .protobuf_stream::<MyTestStructure>(1024);
+        let results: Vec<StreamBodyResult<MyTestStructure>> = res.collect().await;
+
+        assert_eq!(results.len(), test_stream_vec.len() + 1);
+        for (result, test_structure) in results.iter().zip(test_stream_vec.iter()) {
+            assert_eq!(result.as_ref().unwrap(), test_structure);
+        }
+        let err = results
+            .last()
+            .unwrap()
+            .as_ref()
+            .expect_err("UnexpectedEofError");
+        assert!(err.is_unexpected_eof());
+        assert_eq!(err.position(), Some(body_len));
     }
 }
@@ -0,0 +1,390 @@
+use crate::StreamBodyError;
+use std::sync::Arc;
+
+/// The maximum number of bytes read from the response body, stored in the extensions of the
+/// response.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MaxTotalBytes(pub usize);
+
+/// The maximum number of bytes of the response body read ahead of the stream, stored in the
+/// extensions of the response.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ReadBuffer(pub usize);
+
+/// The conversion of the errors reading the response body, stored in the extensions of the
+/// response.
+#[derive(Clone)]
+pub(crate) struct IoErrorMapping(pub Arc<dyn Fn(std::io::Error) -> StreamBodyError + Send + Sync>);
+
+/// Extension trait for [`reqwest::Response`] that limits the response body read by the streams
+/// of all the formats.
+pub trait StreamLimitsResponse {
+    /// Limits the number of bytes read from the response body to `max_total_bytes`.
+    ///
+    /// Unlike the maximum object length of the formats, this caps the whole body, so a stream
+    /// fails with a [`crate::error::StreamBodyKind::TotalLimitReachedError`] error once more
+    /// bytes were read, even if no object was completed. The bytes are counted as they are
+    /// passed to the format, after the decompression of the `compression` feature, so a small
+    /// compressed body can't expand past the limit.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamLimitsResponse as _};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const MAX_TOTAL_BYTES: usize = 64 * 1024 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .with_max_total_bytes(MAX_TOTAL_BYTES)
+    ///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn with_max_total_bytes(self, max_total_bytes: usize) -> Self;
+
+    /// Reads the response body ahead of the stream, buffering at most `read_buffer` bytes.
+    ///
+    /// The streams are pull-based: by default, the body is read only when the stream is polled
+    /// for its next item, so a slow consumer already applies backpressure to the server, and
+    /// the body is only buffered by the decoding buffer of the stream and by the connection of
+    /// reqwest, within the limits of its read buffer and of the flow control of HTTP/2.
+    ///
+    /// With a read buffer, a task reads the body concurrently with the consumer, so the
+    /// network isn't idle while the items are processed, until `read_buffer` bytes are waiting
+    /// to be decoded. The task then pauses until the stream consumes them, so the buffered
+    /// bytes are hard-capped, besides the chunk being received. A chunk larger than
+    /// `read_buffer` bytes is buffered alone. The task stops when the stream is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamLimitsResponse as _};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///     const READ_BUFFER: usize = 1024 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .with_read_buffer(READ_BUFFER)
+    ///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn with_read_buffer(self, read_buffer: usize) -> Self;
+
+    /// Converts the errors reading the response body with `on_io_error`, instead of the default
+    /// conversion to [`crate::error::StreamBodyKind::InputOutputError`] or
+    /// [`crate::error::StreamBodyKind::TimeoutError`] errors.
+    ///
+    /// The [`std::io::ErrorKind`] of the errors passed to `on_io_error` is the category of the
+    /// [`reqwest::Error`] they wrap, which is available by downcasting their inner error. This
+    /// allows to classify the errors, such as to tell the retryable ones apart. The errors of
+    /// the decoding and of the limits of the stream aren't passed to `on_io_error`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::error::{StreamBodyError, StreamBodyKind};
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamLimitsResponse as _};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .with_io_error_mapping(|err| match err.kind() {
+    ///             std::io::ErrorKind::ConnectionReset => StreamBodyError::new(
+    ///                 StreamBodyKind::InputOutputError,
+    ///                 Some(Box::new(err)),
+    ///                 Some("Retryable connection reset".to_string()),
+    ///             ),
+    ///             _ => StreamBodyError::from(err),
+    ///         })
+    ///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn with_io_error_mapping<F>(self, on_io_error: F) -> Self
+    where
+        F: Fn(std::io::Error) -> StreamBodyError + Send + Sync + 'static;
+}
+
+impl StreamLimitsResponse for reqwest::Response {
+    fn with_max_total_bytes(mut self, max_total_bytes: usize) -> Self {
+        self.extensions_mut().insert(MaxTotalBytes(max_total_bytes));
+        self
+    }
+
+    fn with_read_buffer(mut self, read_buffer: usize) -> Self {
+        self.extensions_mut().insert(ReadBuffer(read_buffer));
+        self
+    }
+
+    fn with_io_error_mapping<F>(mut self, on_io_error: F) -> Self
+    where
+        F: Fn(std::io::Error) -> StreamBodyError + Send + Sync + 'static,
+    {
+        self.extensions_mut()
+            .insert(IoErrorMapping(Arc::new(on_io_error)));
+        self
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::error::StreamBodyKind;
+    use crate::test_client::*;
+    use crate::{JsonStreamResponse, StreamMetrics};
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use futures::{stream, StreamExt, TryStreamExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn total_limit_reached_before_object_end() {
+        // A single object that never ends, sent in small chunks
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = std::iter::once("[{\"a\":\"".to_string())
+                    .chain(std::iter::repeat("x".repeat(64)).take(1024))
+                    .map(Ok::<_, axum::Error>);
+                Body::from_stream(stream::iter(chunks))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .with_max_total_bytes(4096)
+            .json_array_stream::<serde_json::Value>(1024 * 1024);
+        let err = res
+            .try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("TotalLimitReachedError");
+
+        assert!(err.is_total_limit());
+        assert_eq!(err.limit(), Some(4096));
+    }
+
+    #[tokio::test]
+    async fn total_limit_not_reached() {
+        let app = Router::new().route("/", get(|| async { "{\"a\":1}\n{\"a\":2}\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .with_max_total_bytes(16)
+            .json_nl_stream::<serde_json::Value>(1024);
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn total_limit_reached_after_decompression() {
+        use async_compression::tokio::bufread::GzipEncoder;
+        use tokio::io::AsyncReadExt;
+
+        // A single line of 1 MiB compressing to about 1 KiB
+        let body = format!("\"{}\"\n", "x".repeat(1024 * 1024));
+        let mut compressed_body = Vec::new();
+        GzipEncoder::new(body.as_bytes())
+            .read_to_end(&mut compressed_body)
+            .await
+            .unwrap();
+        assert!(compressed_body.len() < 4096);
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                (
+                    [(axum::http::header::CONTENT_ENCODING, "gzip")],
+                    compressed_body,
+                )
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .with_max_total_bytes(4096)
+            .json_nl_stream::<serde_json::Value>(2 * 1024 * 1024);
+        let err = res
+            .try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("TotalLimitReachedError");
+
+        assert!(err.is_total_limit());
+        assert_eq!(err.limit(), Some(4096));
+    }
+
+    #[derive(Default)]
+    struct BytesCounter(AtomicUsize);
+
+    impl StreamMetrics for BytesCounter {
+        fn on_bytes(&self, len: usize) {
+            self.0.fetch_add(len, Ordering::Relaxed);
+        }
+    }
+
+    /// Reads a JSON array of 16 MiB with a consumer pausing after a few items, returning the
+    /// number of bytes read from the body during the pause.
+    async fn bytes_read_by_slow_consumer(read_buffer: Option<usize>) -> usize {
+        const ITEMS: usize = 16 * 1024;
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = (0..ITEMS)
+                    .map(|idx| {
+                        let delimiter = if idx == 0 { "[" } else { "," };
+                        format!("{}\"{}\"", delimiter, "x".repeat(1022))
+                    })
+                    .chain(std::iter::once("]".to_string()))
+                    .map(Ok::<_, axum::Error>);
+                Body::from_stream(stream::iter(chunks))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let mut res = client.get("/").send().await.unwrap();
+        if let Some(read_buffer) = read_buffer {
+            res = res.with_read_buffer(read_buffer);
+        }
+        let bytes_counter = Arc::new(BytesCounter::default());
+        let mut items_stream =
+            res.json_array_stream_with_metrics::<String>(1024 * 1024, bytes_counter.clone());
+
+        for _ in 0..10 {
+            items_stream.try_next().await.unwrap().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let bytes_read = bytes_counter.0.load(Ordering::Relaxed);
+
+        let remaining_items = items_stream.count().await;
+        assert_eq!(remaining_items, ITEMS - 10);
+
+        bytes_read
+    }
+
+    #[tokio::test]
+    async fn slow_consumer_applies_backpressure() {
+        let bytes_read = bytes_read_by_slow_consumer(None).await;
+
+        // Only the bytes of the items consumed and of the decoding buffer
+        assert!(bytes_read < 64 * 1024, "{}", bytes_read);
+    }
+
+    #[tokio::test]
+    async fn read_buffer_bounds_read_ahead() {
+        let bytes_read = bytes_read_by_slow_consumer(Some(256 * 1024)).await;
+
+        // The bytes consumed by the items and the decoding buffer, the read buffer and the
+        // chunk being received
+        assert!(bytes_read < 512 * 1024, "{}", bytes_read);
+        assert!(bytes_read > 256 * 1024, "{}", bytes_read);
+    }
+
+    #[tokio::test]
+    async fn io_error_mapping_overrides_body_error() {
+        // The server aborts the body after the first element
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = stream::once(async { Ok("[{\"a\":1},".to_string()) }).chain(
+                    stream::once(async {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Err(axum::Error::new(std::io::Error::from(
+                            std::io::ErrorKind::BrokenPipe,
+                        )))
+                    }),
+                );
+                Body::from_stream(chunks)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .with_io_error_mapping(|err| match err.kind() {
+                std::io::ErrorKind::UnexpectedEof => StreamBodyError::new(
+                    StreamBodyKind::TimeoutError,
+                    Some(Box::new(err)),
+                    Some("Retryable truncated body".to_string()),
+                ),
+                _ => StreamBodyError::from(err),
+            })
+            .json_array_stream::<serde_json::Value>(1024);
+        let results: Vec<_> = res.collect().await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &serde_json::json!({"a": 1}));
+        let err = results[1].as_ref().expect_err("TimeoutError");
+        assert_eq!(err.kind(), StreamBodyKind::TimeoutError);
+        assert_eq!(err.message(), Some("Retryable truncated body"));
+        let io_err = err
+            .source()
+            .and_then(|source| source.downcast_ref::<std::io::Error>())
+            .expect("I/O error source");
+        assert_eq!(io_err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,481 @@
+use crate::response_limits::{IoErrorMapping, MaxTotalBytes, ReadBuffer};
+use crate::{StreamBodyError, StreamMetrics};
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::Semaphore;
+use tokio_util::io::StreamReader;
+
+// This is the default capacity of the buffer used by FramedRead
+pub(crate) const INITIAL_CAPACITY: usize = 8 * 1024;
+
+pub(crate) type ResponseReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Converts the response body into an [`AsyncRead`] to be consumed by the format codecs.
+///
+/// With the `compression` feature the body is transparently decompressed according to the
+/// `Content-Encoding` header of the response (`gzip`, `deflate`, `br` and `zstd`), and other
+/// encodings fail with a [`crate::error::StreamBodyKind::CodecError`] error. Note that
+/// if reqwest already decompressed the body itself, it removes this header, so the body isn't
+/// decompressed twice.
+///
+/// The errors of reqwest are classified by [`reqwest_io_error`]. Timeouts are reported as
+/// [`std::io::ErrorKind::TimedOut`] errors, so they are converted to
+/// [`crate::error::StreamBodyKind::TimeoutError`] errors. The conversion unwraps the
+/// [`reqwest::Error`] from the I/O error, so it's the source of the converted error.
+///
+/// If the response has a maximum total length, set with
+/// [`crate::StreamLimitsResponse::with_max_total_bytes`], the reader fails with a
+/// [`crate::error::StreamBodyKind::TotalLimitReachedError`] error once more bytes are read,
+/// counting the bytes after the decompression.
+///
+/// If the response has an error mapping, set with
+/// [`crate::StreamLimitsResponse::with_io_error_mapping`], the errors reading the body are
+/// converted by it.
+///
+/// If the response has a read buffer, set with [`crate::StreamLimitsResponse::with_read_buffer`],
+/// the body is read ahead by [`with_read_buffer`].
+pub(crate) fn response_reader(response: reqwest::Response) -> ResponseReader {
+    response_reader_with_metrics(response, None)
+}
+
+/// Converts the response body into an [`AsyncRead`] as [`response_reader`] does, reporting the
+/// number of bytes read from the body to `metrics`.
+pub(crate) fn response_reader_with_metrics(
+    response: reqwest::Response,
+    metrics: Option<Arc<dyn StreamMetrics>>,
+) -> ResponseReader {
+    response_reader_with_options(response, metrics, None)
+}
+
+/// Converts the response body into an [`AsyncRead`] as [`response_reader`] does, failing with a
+/// [`std::io::ErrorKind::TimedOut`] error if no bytes are received within `read_timeout` while
+/// the body is read.
+#[cfg(feature = "json")]
+pub(crate) fn response_reader_with_read_timeout(
+    response: reqwest::Response,
+    read_timeout: Duration,
+) -> ResponseReader {
+    response_reader_with_options(response, None, Some(read_timeout))
+}
+
+fn response_reader_with_options(
+    response: reqwest::Response,
+    metrics: Option<Arc<dyn StreamMetrics>>,
+    read_timeout: Option<Duration>,
+) -> ResponseReader {
+    #[cfg(feature = "compression")]
+    let content_encoding = response
+        .headers()
+        .get(reqwest::header::CONTENT_ENCODING)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+
+    let max_total_bytes = response
+        .extensions()
+        .get::<MaxTotalBytes>()
+        .map(|max_total_bytes| max_total_bytes.0);
+    let read_buffer = response
+        .extensions()
+        .get::<ReadBuffer>()
+        .map(|read_buffer| read_buffer.0);
+    let io_error_mapping = response.extensions().get::<IoErrorMapping>().cloned();
+
+    let bytes_stream = response
+        .bytes_stream()
+        .inspect_ok(move |chunk| {
+            if let Some(metrics) = &metrics {
+                metrics.on_bytes(chunk.len());
+            }
+        })
+        .map_err(reqwest_io_error);
+    let bytes_stream: BoxStream<'static, std::io::Result<Bytes>> = match read_timeout {
+        Some(read_timeout) => with_read_timeout(bytes_stream, read_timeout),
+        None => Box::pin(bytes_stream),
+    };
+    // The converted errors are passed through the codecs by the conversion of the I/O errors
+    let bytes_stream = match io_error_mapping {
+        Some(IoErrorMapping(on_io_error)) => Box::pin(
+            bytes_stream.map_err(move |err| std::io::Error::new(err.kind(), on_io_error(err))),
+        ),
+        None => bytes_stream,
+    };
+    let bytes_stream = match read_buffer {
+        Some(read_buffer) => with_read_buffer(bytes_stream, read_buffer),
+        None => bytes_stream,
+    };
+    let reader = StreamReader::new(bytes_stream);
+
+    #[cfg(feature = "compression")]
+    let reader: ResponseReader = match content_encoding {
+        Some(content_encoding) => decompressing_reader(reader, content_encoding.as_str()),
+        None => Box::new(reader),
+    };
+
+    // The decoded bytes are counted, so a compressed body can't expand past the limit
+    match max_total_bytes {
+        Some(max_total_bytes) => Box::new(MaxTotalBytesReader {
+            reader,
+            max_total_bytes,
+            total_bytes: 0,
+        }),
+        None => Box::new(reader),
+    }
+}
+
+/// Fails with a [`crate::error::StreamBodyKind::TotalLimitReachedError`] error once more than
+/// `max_total_bytes` bytes are read from `reader`.
+struct MaxTotalBytesReader<R> {
+    reader: R,
+    max_total_bytes: usize,
+    total_bytes: usize,
+}
+
+impl<R> AsyncRead for MaxTotalBytesReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled_len = buf.filled().len();
+        futures::ready!(Pin::new(&mut self.reader).poll_read(cx, buf))?;
+        self.total_bytes = self
+            .total_bytes
+            .saturating_add(buf.filled().len() - filled_len);
+        if self.total_bytes > self.max_total_bytes {
+            return Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                StreamBodyError::total_limit_reached(self.max_total_bytes),
+            )));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Fails the stream of the body chunks with a [`std::io::ErrorKind::TimedOut`] error if the
+/// next chunk doesn't arrive within `read_timeout`.
+fn with_read_timeout<S>(bytes_stream: S, read_timeout: Duration) -> BoxStream<'static, S::Item>
+where
+    S: Stream<Item = std::io::Result<Bytes>> + Send + 'static,
+{
+    Box::pin(futures::stream::unfold(
+        Some(Box::pin(bytes_stream)),
+        move |bytes_stream| async move {
+            let mut bytes_stream = bytes_stream?;
+            match tokio::time::timeout(read_timeout, bytes_stream.next()).await {
+                Ok(Some(chunk)) => Some((chunk, Some(bytes_stream))),
+                Ok(None) => None,
+                Err(elapsed) => Some((
+                    Err(std::io::Error::new(std::io::ErrorKind::TimedOut, elapsed)),
+                    None,
+                )),
+            }
+        },
+    ))
+}
+
+/// Reads the body chunks in a task ahead of the consumer of the returned stream, until the
+/// chunks waiting to be consumed have `read_buffer` bytes.
+///
+/// Every chunk holds the permits of a semaphore for its length until the consumer takes it, so
+/// the task waits for the consumer before buffering more. A chunk larger than `read_buffer`
+/// takes all the permits. The task stops after an error, or when the consumer is dropped.
+fn with_read_buffer(
+    bytes_stream: BoxStream<'static, std::io::Result<Bytes>>,
+    read_buffer: usize,
+) -> BoxStream<'static, std::io::Result<Bytes>> {
+    let max_permits = read_buffer.clamp(1, u32::MAX as usize);
+    let semaphore = Arc::new(Semaphore::new(max_permits));
+    let (chunks_tx, chunks_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut bytes_stream = bytes_stream;
+        while let Some(chunk) = bytes_stream.next().await {
+            let permits = match &chunk {
+                Ok(chunk) => chunk.len().clamp(1, max_permits),
+                Err(_) => 1,
+            };
+            let permit = match semaphore.clone().acquire_many_owned(permits as u32).await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            let is_err = chunk.is_err();
+            if chunks_tx.send((chunk, permit)).is_err() || is_err {
+                break;
+            }
+        }
+    });
+
+    Box::pin(futures::stream::unfold(
+        chunks_rx,
+        |mut chunks_rx| async move {
+            let (chunk, _permit) = chunks_rx.recv().await?;
+            Some((chunk, chunks_rx))
+        },
+    ))
+}
+
+/// Converts an error of reqwest reading the body into an [`std::io::Error`], keeping its
+/// category as the [`std::io::ErrorKind`] of the error.
+///
+/// The kind of the underlying I/O error is used if there is one, such as for a connection
+/// reset by the server. Otherwise the kind is derived from the reqwest error: timeouts are
+/// [`std::io::ErrorKind::TimedOut`], connection errors [`std::io::ErrorKind::ConnectionRefused`],
+/// decoding errors [`std::io::ErrorKind::InvalidData`] and other body errors, such as a body
+/// aborted by the server, [`std::io::ErrorKind::ConnectionReset`].
+pub(crate) fn reqwest_io_error(err: reqwest::Error) -> std::io::Error {
+    let kind = if err.is_timeout() {
+        std::io::ErrorKind::TimedOut
+    } else if let Some(io_err) = source_io_error(&err) {
+        io_err.kind()
+    } else if err.is_connect() {
+        std::io::ErrorKind::ConnectionRefused
+    } else if err.is_decode() {
+        std::io::ErrorKind::InvalidData
+    } else if err.is_body() {
+        std::io::ErrorKind::ConnectionReset
+    } else {
+        std::io::ErrorKind::Other
+    };
+    std::io::Error::new(kind, err)
+}
+
+/// Finds the first [`std::io::Error`] in the chain of sources of `err`.
+fn source_io_error(err: &reqwest::Error) -> Option<&std::io::Error> {
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            return Some(io_err);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Fails with a [`crate::error::StreamBodyKind::HttpStatusError`] error if the response
+/// doesn't have a success status.
+#[cfg(feature = "json")]
+pub(crate) fn check_status(response: &reqwest::Response) -> Result<(), StreamBodyError> {
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(StreamBodyError::http_status(response.status()))
+    }
+}
+
+/// Fails with a [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error if the media
+/// type of the response isn't `expected`. The parameters of the content type, such as the
+/// charset, are ignored.
+#[cfg(any(
+    feature = "json",
+    feature = "csv",
+    feature = "protobuf",
+    feature = "arrow"
+))]
+pub(crate) fn check_content_type(
+    response: &reqwest::Response,
+    expected: &str,
+) -> Result<(), StreamBodyError> {
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    let media_type = content_type.and_then(|value| value.split(';').next());
+
+    match media_type {
+        Some(media_type) if media_type.trim().eq_ignore_ascii_case(expected) => Ok(()),
+        _ => Err(StreamBodyError::content_type_mismatch(
+            expected,
+            content_type,
+        )),
+    }
+}
+
+cfg_compression! {
+    /// Decompresses the body according to its `Content-Encoding`, ignoring the case and the
+    /// surrounding whitespace. A body with another encoding, or with several encodings applied
+    /// one after another, fails at the first read with a
+    /// [`crate::error::StreamBodyKind::CodecError`] error, rather than passing the compressed
+    /// bytes to the format.
+    fn decompressing_reader<R>(reader: R, content_encoding: &str) -> ResponseReader
+    where
+        R: tokio::io::AsyncBufRead + Send + Unpin + 'static,
+    {
+        use async_compression::tokio::bufread::*;
+
+        match content_encoding.trim().to_ascii_lowercase().as_str() {
+            "identity" => Box::new(reader),
+            "gzip" | "x-gzip" => Box::new(GzipDecoder::new(reader)),
+            "deflate" => Box::new(ZlibDecoder::new(reader)),
+            "br" => Box::new(BrotliDecoder::new(reader)),
+            "zstd" => Box::new(ZstdDecoder::new(reader)),
+            _ => {
+                let err = std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    StreamBodyError::new(
+                        crate::error::StreamBodyKind::CodecError,
+                        None,
+                        Some(format!(
+                            "Unsupported Content-Encoding: {}",
+                            content_encoding
+                        )),
+                    ),
+                );
+                let chunks = futures::stream::iter(vec![Err::<Bytes, _>(err)]);
+                Box::new(StreamReader::new(chunks))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::JsonStreamResponse;
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use futures::{stream, StreamExt};
+
+    #[tokio::test]
+    async fn body_error_kind_propagated() {
+        // The server aborts the body after the first element
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = stream::once(async { Ok("[{\"a\":1},".to_string()) }).chain(
+                    stream::once(async {
+                        // Let the response headers and the first chunk be sent
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                        Err(axum::Error::new(std::io::Error::from(
+                            std::io::ErrorKind::BrokenPipe,
+                        )))
+                    }),
+                );
+                Body::from_stream(chunks)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<serde_json::Value>(1024);
+        let results: Vec<_> = res.collect().await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &serde_json::json!({"a": 1}));
+        let err = results[1].as_ref().expect_err("InputOutputError");
+        assert!(err.is_io());
+        let reqwest_err = err
+            .source()
+            .and_then(|source| source.downcast_ref::<reqwest::Error>())
+            .expect("reqwest error source");
+        // The truncated chunked body is reported by hyper as an unexpected end of the body
+        let io_err = source_io_error(reqwest_err).expect("I/O error in the source chain");
+        assert_eq!(io_err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn body_timeout_source_is_reqwest_error() {
+        // The server stalls after the first element
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunks = stream::once(async { Ok::<_, axum::Error>("[{\"a\":1},") })
+                    .chain(stream::pending());
+                Body::from_stream(chunks)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .timeout(Duration::from_millis(200))
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<serde_json::Value>(1024);
+        let results: Vec<_> = res.collect().await;
+
+        assert_eq!(results.len(), 2);
+        let err = results[1].as_ref().expect_err("TimeoutError");
+        assert!(err.is_timeout());
+        let reqwest_err = err
+            .source()
+            .and_then(|source| source.downcast_ref::<reqwest::Error>())
+            .expect("reqwest error source");
+        assert!(reqwest_err.is_timeout());
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn unsupported_content_encoding() {
+        use async_compression::tokio::bufread::GzipEncoder;
+        use futures::TryStreamExt;
+        use tokio::io::AsyncReadExt;
+
+        let mut compressed_body = Vec::new();
+        GzipEncoder::new(&b"{\"a\":1}\n"[..])
+            .read_to_end(&mut compressed_body)
+            .await
+            .unwrap();
+
+        let app = Router::new().route(
+            "/{encoding}",
+            get(
+                |axum::extract::Path(encoding): axum::extract::Path<String>| async move {
+                    (
+                        [(axum::http::header::CONTENT_ENCODING, encoding)],
+                        compressed_body,
+                    )
+                },
+            ),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<serde_json::Value> = client
+            .get("/%20GZIP%20")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<serde_json::Value>(1024)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec![serde_json::json!({"a": 1})]);
+
+        for encoding in ["compress", "gzip,%20br"] {
+            let err = client
+                .get(&format!("/{}", encoding))
+                .send()
+                .await
+                .unwrap()
+                .json_nl_stream::<serde_json::Value>(1024)
+                .try_collect::<Vec<serde_json::Value>>()
+                .await
+                .expect_err("CodecError");
+
+            assert!(err.is_codec(), "{}", encoding);
+            assert!(
+                err.message()
+                    .unwrap()
+                    .starts_with("Unsupported Content-Encoding"),
+                "{:?}",
+                err.message()
+            );
+        }
+    }
+}
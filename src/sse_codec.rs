@@ -0,0 +1,140 @@
+use crate::sse_stream::SseEvent;
+use crate::utf8_bom::strip_utf8_bom;
+use crate::StreamBodyError;
+use bytes::BytesMut;
+
+#[derive(Clone, Debug)]
+pub struct SseCodec {
+    max_length: usize,
+    next_index: usize,
+    consumed_len: usize,
+    bom_checked: bool,
+    last_event_id: Option<String>,
+    pending: PendingEvent,
+}
+
+#[derive(Clone, Debug, Default)]
+struct PendingEvent {
+    event: Option<String>,
+    data: String,
+    retry: Option<u64>,
+}
+
+impl SseCodec {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        SseCodec {
+            max_length,
+            next_index: 0,
+            consumed_len: 0,
+            bom_checked: false,
+            last_event_id: None,
+            pending: PendingEvent::default(),
+        }
+    }
+
+    fn process_line(&mut self, line: &str) {
+        if line.starts_with(':') {
+            return; // comment
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.pending.event = Some(value.to_string()),
+            "data" => {
+                self.pending.data.push_str(value);
+                self.pending.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|ch| ch.is_ascii_digit()) => {
+                self.pending.retry = value.parse().ok();
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch_event(&mut self) -> Option<SseEvent> {
+        let mut pending = std::mem::take(&mut self.pending);
+        if pending.data.is_empty() {
+            return None;
+        }
+        pending.data.pop(); // the trailing LF
+
+        Some(SseEvent {
+            event: pending.event,
+            data: pending.data,
+            id: self.last_event_id.clone(),
+            retry: pending.retry,
+        })
+    }
+
+    fn max_len_reached(&self, position: usize) -> StreamBodyError {
+        StreamBodyError::max_len_reached(self.max_length, position)
+    }
+}
+
+impl tokio_util::codec::Decoder for SseCodec {
+    type Item = SseEvent;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<SseEvent>, StreamBodyError> {
+        if !self.bom_checked {
+            match strip_utf8_bom(buf, false) {
+                Some(bom_len) => self.consumed_len += bom_len,
+                None => return Ok(None),
+            }
+            self.bom_checked = true;
+        }
+
+        loop {
+            match buf[self.next_index..].iter().position(|ch| *ch == b'\n') {
+                Some(offset) => {
+                    let mut line = buf.split_to(self.next_index + offset + 1);
+                    self.next_index = 0;
+                    let line_pos = self.consumed_len;
+                    self.consumed_len += line.len();
+                    line.truncate(line.len() - 1);
+                    if line.last() == Some(&b'\r') {
+                        line.truncate(line.len() - 1);
+                    }
+
+                    if line.is_empty() {
+                        if let Some(event) = self.dispatch_event() {
+                            return Ok(Some(event));
+                        }
+                    } else {
+                        let line = std::str::from_utf8(&line).map_err(|err| {
+                            StreamBodyError::invalid_utf8(err, line_pos + err.valid_up_to())
+                        })?;
+                        self.process_line(line);
+                        if self.pending.data.len() > self.max_length {
+                            return Err(self.max_len_reached(self.consumed_len));
+                        }
+                    }
+                }
+                None if buf.len() > self.max_length => {
+                    return Err(
+                        self.max_len_reached(self.consumed_len.saturating_add(self.max_length))
+                    );
+                }
+                None => {
+                    self.next_index = buf.len();
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<SseEvent>, StreamBodyError> {
+        let result = self.decode(buf)?;
+        if result.is_none() {
+            // An incomplete event at the end of the stream is discarded according to the spec
+            buf.clear();
+            self.next_index = 0;
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::error::StreamBodyKind;
+use crate::sse_codec::SseCodec;
+use crate::stream_tracing::traced_codec;
+use crate::CodecStreamResponse;
+use crate::{StreamBodyError, StreamBodyResult};
+use async_trait::*;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::Deserialize;
+
+/// An event received from a [Server-Sent Events] stream.
+///
+/// [Server-Sent Events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The event type (the `event` field), if specified.
+    pub event: Option<String>,
+
+    /// The event data. Multiple `data` fields of the same event are joined with `\n`.
+    pub data: String,
+
+    /// The last event ID (the `id` field) received so far in the stream.
+    pub id: Option<String>,
+
+    /// The reconnection time in milliseconds (the `retry` field), if specified.
+    pub retry: Option<u64>,
+}
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for the
+/// [Server-Sent Events] (`text/event-stream`) format.
+///
+/// [Server-Sent Events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+#[async_trait]
+pub trait SseStreamResponse {
+    /// Streams the response as Server-Sent Events.
+    ///
+    /// Lines are accumulated until a blank line dispatches the event, comment lines starting
+    /// with `:` are ignored. Events without any data aren't dispatched. The data of an event
+    /// is limited to a maximum size of `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::{SseEvent, SseStreamResponse as _};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/sse")
+    ///         .await?
+    ///         .sse_stream(MAX_OBJ_LEN);
+    ///     let _events: Vec<SseEvent> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn sse_stream<'a>(self, max_obj_len: usize) -> BoxStream<'a, StreamBodyResult<SseEvent>>;
+
+    /// Streams the response as Server-Sent Events, where the data of each event contains a JSON
+    /// object.
+    ///
+    /// The stream will [`Deserialize`] the data of each event as type `T` with a maximum size
+    /// of `max_obj_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::SseStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/sse")
+    ///         .await?
+    ///         .sse_json_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn sse_json_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+#[async_trait]
+impl SseStreamResponse for reqwest::Response {
+    fn sse_stream<'a>(self, max_obj_len: usize) -> BoxStream<'a, StreamBodyResult<SseEvent>> {
+        let codec = traced_codec(
+            SseCodec::new_with_max_length(max_obj_len),
+            "sse",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+
+    fn sse_json_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        Box::pin(self.sse_stream(max_obj_len).map(|event_res| {
+            event_res.and_then(|event| {
+                serde_json::from_str(event.data.as_str()).map_err(|err| {
+                    StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+                })
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::response::sse::{Event, Sse};
+    use axum::{routing::*, Router};
+    use futures::{stream, TryStreamExt};
+    use serde::Serialize;
+    use std::time::Duration;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field: String,
+    }
+
+    #[tokio::test]
+    async fn deserialize_sse_stream() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                Sse::new(stream::iter(vec![
+                    Ok::<_, axum::Error>(Event::default().comment("keep-alive")),
+                    Ok(Event::default().data("first line\nsecond line")),
+                    Ok(Event::default()
+                        .event("update")
+                        .id("42")
+                        .retry(Duration::from_millis(1500))
+                        .data("single line")),
+                    Ok(Event::default().data("after id")),
+                ]))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap().sse_stream(1024);
+        let events: Vec<SseEvent> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: None,
+                    data: "first line\nsecond line".to_string(),
+                    id: None,
+                    retry: None,
+                },
+                SseEvent {
+                    event: Some("update".to_string()),
+                    data: "single line".to_string(),
+                    id: Some("42".to_string()),
+                    retry: Some(1500),
+                },
+                SseEvent {
+                    event: None,
+                    data: "after id".to_string(),
+                    id: Some("42".to_string()),
+                    retry: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_sse_stream_raw_body() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                ": comment\r\ndata:no space\r\ndata:  two spaces\r\n\r\nid: 1\r\n\r\nevent: empty\r\n\r\ndata: incomplete"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap().sse_stream(1024);
+        let events: Vec<SseEvent> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                event: None,
+                data: "no space\n two spaces".to_string(),
+                id: None,
+                retry: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_sse_json_stream() {
+        let test_stream_vec = vec![
+            MyTestStructure {
+                some_test_field: "TestValue".to_string()
+            };
+            100
+        ];
+
+        let events = test_stream_vec
+            .clone()
+            .into_iter()
+            .map(|item| Event::default().json_data(item));
+
+        let app = Router::new().route(
+            "/",
+            get(|| async { Sse::new(stream::iter(events.collect::<Vec<_>>())) }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .sse_json_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(items, test_stream_vec);
+    }
+
+    #[tokio::test]
+    async fn deserialize_sse_stream_check_max_len() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                Sse::new(stream::iter(vec![Ok::<_, axum::Error>(
+                    Event::default().data("some long data line"),
+                )]))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = client.get("/").send().await.unwrap().sse_stream(10);
+        res.try_collect::<Vec<SseEvent>>()
+            .await
+            .expect_err("MaxLenReachedError");
+    }
+}
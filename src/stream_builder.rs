@@ -0,0 +1,757 @@
+use crate::response_reader::{check_content_type, INITIAL_CAPACITY};
+use crate::StreamBodyResult;
+use futures::stream::BoxStream;
+
+#[cfg(feature = "arrow")]
+use crate::arrow_ipc_stream::ARROW_IPC_CONTENT_TYPE;
+#[cfg(feature = "json")]
+use crate::codec_stream::stream_with_codec_and_capacity;
+#[cfg(feature = "csv")]
+use crate::csv_stream::{CsvOptions, CSV_CONTENT_TYPE};
+#[cfg(feature = "json")]
+use crate::json_array_codec::JsonArrayCodec;
+#[cfg(feature = "json")]
+use crate::json_stream::{JsonNlOptions, JSON_CONTENT_TYPE, JSON_NL_CONTENT_TYPE};
+#[cfg(feature = "protobuf")]
+use crate::protobuf_stream::PROTOBUF_CONTENT_TYPE;
+#[cfg(feature = "json")]
+use crate::stream_tracing::traced_codec;
+#[cfg(any(feature = "json", feature = "csv"))]
+use crate::StreamBodyError;
+#[cfg(feature = "arrow")]
+use arrow::array::RecordBatch;
+#[cfg(any(feature = "json", feature = "csv"))]
+use serde::Deserialize;
+
+/// The default maximum size of the items of a [`StreamBuilder`].
+pub const DEFAULT_MAX_OBJ_LEN: usize = 1024 * 1024;
+
+#[cfg(feature = "json")]
+const JSONL_CONTENT_TYPE: &str = "application/jsonl";
+#[cfg(feature = "csv")]
+const TSV_CONTENT_TYPE: &str = "text/tab-separated-values";
+
+/// Extension trait for [`reqwest::Response`] that provides a [`StreamBuilder`] for the
+/// streaming formats.
+pub trait StreamBuilderResponse {
+    /// Starts building a stream of the response, with the default options.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use reqwest_streams::StreamBuilderResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .stream_builder()
+    ///         .max_obj_len(64 * 1024)
+    ///         .buf_capacity(16 * 1024)
+    ///         .strict(true)
+    ///         .json_array::<MyTestStructure>();
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn stream_builder(self) -> StreamBuilder;
+
+    /// Streams the response in the format of its content type, with a maximum size of
+    /// `max_obj_len` bytes for the items.
+    ///
+    /// See [`StreamBuilder::auto`] for the supported content types.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use reqwest_streams::StreamBuilderResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/items")
+    ///         .await?
+    ///         .auto_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(any(feature = "json", feature = "csv"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "json", feature = "csv"))))]
+    fn auto_stream<'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+impl StreamBuilderResponse for reqwest::Response {
+    fn stream_builder(self) -> StreamBuilder {
+        StreamBuilder::new(self)
+    }
+
+    #[cfg(any(feature = "json", feature = "csv"))]
+    fn auto_stream<'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        StreamBuilder::new(self).max_obj_len(max_obj_len).auto()
+    }
+}
+
+/// Builds the stream of a response with the options shared by the formats, as an alternative
+/// to the `*_with_capacity` and `*_strict` methods of the extension traits.
+///
+/// The options are set with the fluent methods, then the stream is created by the method of
+/// its format, such as [`StreamBuilder::json_array`]. By default, the items have a maximum size
+/// of [`DEFAULT_MAX_OBJ_LEN`] bytes, the decoding buffer has the default capacity, the
+/// content type of the response isn't checked and a UTF-8 byte order mark at the start of the
+/// body is stripped.
+#[derive(Debug)]
+pub struct StreamBuilder {
+    response: reqwest::Response,
+    max_obj_len: usize,
+    buf_capacity: usize,
+    strict: bool,
+    content_type: Option<String>,
+    strip_bom: bool,
+}
+
+impl StreamBuilder {
+    /// Create a builder for the stream of `response`, with the default options.
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            max_obj_len: DEFAULT_MAX_OBJ_LEN,
+            buf_capacity: INITIAL_CAPACITY,
+            strict: false,
+            content_type: None,
+            strip_bom: true,
+        }
+    }
+
+    /// Set the maximum size of the items in bytes.
+    pub fn max_obj_len(mut self, max_obj_len: usize) -> Self {
+        self.max_obj_len = max_obj_len;
+        self
+    }
+
+    /// Set the initial capacity of the stream's decoding buffer.
+    pub fn buf_capacity(mut self, buf_capacity: usize) -> Self {
+        self.buf_capacity = buf_capacity;
+        self
+    }
+
+    /// Set whether the content type of the response is checked before decoding the body.
+    ///
+    /// If the media type of the response isn't the one of the format, or the one set with
+    /// [`StreamBuilder::content_type`], the stream yields a single
+    /// [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set the media type expected in strict mode, instead of the one of the format.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Set whether a UTF-8 byte order mark at the start of the body is stripped, for the JSON
+    /// and CSV formats.
+    ///
+    /// If not, the mark is decoded as a part of the body: it's a part of the first JSON line or
+    /// CSV field, while it's skipped as a byte before a JSON array.
+    pub fn strip_bom(mut self, strip_bom: bool) -> Self {
+        self.strip_bom = strip_bom;
+        self
+    }
+
+    /// Checks the content type of the response in strict mode, returning the response and the
+    /// options if it matches.
+    fn checked(
+        self,
+        format_content_type: &str,
+    ) -> StreamBodyResult<(reqwest::Response, usize, usize)> {
+        if self.strict {
+            check_content_type(
+                &self.response,
+                self.content_type.as_deref().unwrap_or(format_content_type),
+            )?;
+        }
+        Ok((self.response, self.max_obj_len, self.buf_capacity))
+    }
+
+    /// Streams the response as a JSON array, see [`crate::JsonStreamResponse::json_array_stream`].
+    ///
+    /// The expected content type is `application/json`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_array<'b, T>(self) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let strip_bom = self.strip_bom;
+        match self.checked(JSON_CONTENT_TYPE) {
+            Ok((response, max_obj_len, buf_capacity)) => stream_with_codec_and_capacity(
+                response,
+                traced_codec(
+                    JsonArrayCodec::<T>::new_with_max_length(max_obj_len).with_strip_bom(strip_bom),
+                    "json_array",
+                    max_obj_len,
+                ),
+                buf_capacity,
+            ),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    /// Streams the response as JSON lines, see [`crate::JsonStreamResponse::json_nl_stream`].
+    ///
+    /// The expected content type is `application/x-ndjson`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json_nl<'b, T>(self) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        use crate::JsonStreamResponse;
+
+        let strip_bom = self.strip_bom;
+        match self.checked(JSON_NL_CONTENT_TYPE) {
+            Ok((response, max_obj_len, buf_capacity)) => response.json_nl_stream_with_options(
+                max_obj_len,
+                JsonNlOptions::new()
+                    .with_buf_capacity(buf_capacity)
+                    .with_strip_bom(strip_bom),
+            ),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    /// Streams the response as CSV, see [`crate::CsvStreamResponse::csv_stream`].
+    ///
+    /// The expected content type is `text/csv`.
+    #[cfg(feature = "csv")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
+    pub fn csv<'b, T>(
+        self,
+        with_csv_header: bool,
+        delimiter: u8,
+    ) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + 'b,
+    {
+        use crate::CsvStreamResponse;
+
+        let strip_bom = self.strip_bom;
+        match self.checked(CSV_CONTENT_TYPE) {
+            Ok((response, max_obj_len, buf_capacity)) => response.csv_stream_with_options(
+                max_obj_len,
+                CsvOptions::new()
+                    .with_has_headers(with_csv_header)
+                    .with_delimiter(delimiter)
+                    .with_buf_capacity(buf_capacity)
+                    .with_strip_bom(strip_bom),
+            ),
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    /// Streams the response in the format selected by its content type, for the formats
+    /// deserializing the items with serde.
+    ///
+    /// The media types of the enabled formats are:
+    /// - `application/json`: a JSON array, as [`StreamBuilder::json_array`]
+    /// - `application/x-ndjson` and `application/jsonl`: JSON lines, as [`StreamBuilder::json_nl`]
+    /// - `text/csv`: CSV with a header row, unless the content type has the `header=absent`
+    ///   parameter, as [`StreamBuilder::csv`]
+    /// - `text/tab-separated-values`: CSV delimited by tabs, with a header row likewise
+    ///
+    /// The other options of the builder apply to the selected format, except the strict mode
+    /// and the expected content type. A response with another content type, or without any,
+    /// yields a single [`crate::error::StreamBodyKind::ContentTypeMismatchError`] error listing
+    /// the supported media types.
+    #[cfg(any(feature = "json", feature = "csv"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "json", feature = "csv"))))]
+    pub fn auto<'b, T>(self) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let content_type = self
+            .response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        let mut params = content_type.as_deref().unwrap_or_default().split(';');
+        let media_type = params
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        let builder = self.strict(false);
+        match media_type.as_str() {
+            #[cfg(feature = "json")]
+            JSON_CONTENT_TYPE => return builder.json_array(),
+            #[cfg(feature = "json")]
+            JSON_NL_CONTENT_TYPE | JSONL_CONTENT_TYPE => return builder.json_nl(),
+            #[cfg(feature = "csv")]
+            CSV_CONTENT_TYPE | TSV_CONTENT_TYPE => {
+                let with_csv_header =
+                    !params.any(|param| param.trim().eq_ignore_ascii_case("header=absent"));
+                let delimiter = if media_type == TSV_CONTENT_TYPE {
+                    b'\t'
+                } else {
+                    b','
+                };
+                return builder.csv(with_csv_header, delimiter);
+            }
+            _ => {}
+        }
+
+        let err = StreamBodyError::content_type_mismatch(
+            &auto_content_types().join(", "),
+            content_type.as_deref(),
+        );
+        Box::pin(futures::stream::once(async { Err(err) }))
+    }
+
+    /// Streams the response as Protobuf messages, see
+    /// [`crate::ProtobufStreamResponse::protobuf_stream`].
+    ///
+    /// The expected content type is `application/x-protobuf`.
+    #[cfg(feature = "protobuf")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "protobuf")))]
+    pub fn protobuf<'b, T>(self) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: prost::Message + Default + Send + 'b,
+    {
+        use crate::ProtobufStreamResponse;
+
+        match self.checked(PROTOBUF_CONTENT_TYPE) {
+            Ok((response, max_obj_len, buf_capacity)) => {
+                response.protobuf_stream_with_capacity(max_obj_len, buf_capacity)
+            }
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+
+    /// Streams the response as Arrow IPC record batches, see
+    /// [`crate::ArrowIpcStreamResponse::arrow_ipc_stream`].
+    ///
+    /// The expected content type is `application/vnd.apache.arrow.stream`.
+    #[cfg(feature = "arrow")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+    pub fn arrow_ipc<'a>(self) -> BoxStream<'a, StreamBodyResult<RecordBatch>> {
+        use crate::ArrowIpcStreamResponse;
+
+        match self.checked(ARROW_IPC_CONTENT_TYPE) {
+            Ok((response, max_obj_len, buf_capacity)) => {
+                response.arrow_ipc_stream_with_capacity(max_obj_len, buf_capacity)
+            }
+            Err(err) => Box::pin(futures::stream::once(async { Err(err) })),
+        }
+    }
+}
+
+/// The media types selecting the formats of [`StreamBuilder::auto`].
+#[cfg(any(feature = "json", feature = "csv"))]
+fn auto_content_types() -> Vec<&'static str> {
+    let mut content_types = Vec::new();
+    #[cfg(feature = "json")]
+    content_types.extend([JSON_CONTENT_TYPE, JSON_NL_CONTENT_TYPE, JSONL_CONTENT_TYPE]);
+    #[cfg(feature = "csv")]
+    content_types.extend([CSV_CONTENT_TYPE, TSV_CONTENT_TYPE]);
+    content_types
+}
+
+#[cfg(all(test, any(feature = "json", feature = "csv")))]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::{routing::*, Router};
+    use axum_streams::*;
+    use futures::{stream, TryStreamExt};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field1: String,
+        some_test_field2: String,
+    }
+
+    fn generate_test_structures() -> Vec<MyTestStructure> {
+        vec![
+            MyTestStructure {
+                some_test_field1: "TestValue1".to_string(),
+                some_test_field2: "TestValue2".to_string()
+            };
+            100
+        ]
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn build_json_array_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new()
+            .route("/", get(|| async { StreamBodyAs::json_array(test_stream) }))
+            .route("/html", get(|| async { axum::response::Html("[]") }));
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .max_obj_len(1024)
+            .buf_capacity(64)
+            .strict(true)
+            .json_array::<MyTestStructure>()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let err = client
+            .get("/html")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .strict(true)
+            .json_array::<MyTestStructure>()
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+
+        // Without the strict mode, the content type isn't checked
+        let items: Vec<MyTestStructure> = client
+            .get("/html")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .json_array::<MyTestStructure>()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert!(items.is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn build_json_array_stream_max_obj_len() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new().route("/", get(|| async { StreamBodyAs::json_array(test_stream) }));
+
+        let client = TestClient::new(app).await;
+
+        let err = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .max_obj_len(10)
+            .json_array::<MyTestStructure>()
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn build_json_nl_stream_strip_bom() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                "\u{FEFF}{\"some_test_field1\":\"TestValue1\",\"some_test_field2\":\"TestValue2\"}\n"
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .json_nl::<MyTestStructure>()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, generate_test_structures()[..1]);
+
+        // Without stripping, the mark is a part of the first line
+        let err = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .strip_bom(false)
+            .json_nl::<MyTestStructure>()
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("CodecError");
+
+        assert!(err.is_codec());
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn build_csv_stream() {
+        let test_stream_vec = generate_test_structures();
+
+        let test_stream = Box::pin(stream::iter(test_stream_vec.clone()));
+
+        let app = Router::new()
+            .route("/", get(|| async { StreamBodyAs::csv(test_stream) }))
+            .route("/plain", get(|| async { "TestValue1,TestValue2\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .max_obj_len(1024)
+            .buf_capacity(64)
+            .strict(true)
+            .csv::<MyTestStructure>(false, b',')
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec);
+
+        let err = client
+            .get("/plain")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .strict(true)
+            .csv::<MyTestStructure>(false, b',')
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+
+        // The media type expected in strict mode can be overridden
+        let items: Vec<MyTestStructure> = client
+            .get("/plain")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .strict(true)
+            .content_type("text/plain")
+            .csv::<MyTestStructure>(false, b',')
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, test_stream_vec[..1]);
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn build_csv_stream_strip_bom() {
+        let app = Router::new().route("/", get(|| async { "\u{FEFF}TestValue1,TestValue2\n" }));
+
+        let client = TestClient::new(app).await;
+
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .csv::<MyTestStructure>(false, b',')
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items, generate_test_structures()[..1]);
+
+        // Without stripping, the mark is a part of the first field
+        let items: Vec<MyTestStructure> = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .stream_builder()
+            .strip_bom(false)
+            .csv::<MyTestStructure>(false, b',')
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(items[0].some_test_field1, "\u{FEFF}TestValue1");
+    }
+
+    #[tokio::test]
+    async fn auto_stream_by_content_type() {
+        let app = Router::new()
+            .route(
+                "/json",
+                get(|| async {
+                    (
+                        [("content-type", "application/json; charset=utf-8")],
+                        r#"[{"some_test_field1":"a","some_test_field2":"b"}]"#,
+                    )
+                }),
+            )
+            .route(
+                "/ndjson",
+                get(|| async {
+                    (
+                        [("content-type", "application/x-ndjson")],
+                        "{\"some_test_field1\":\"a\",\"some_test_field2\":\"b\"}\n",
+                    )
+                }),
+            )
+            .route(
+                "/jsonl",
+                get(|| async {
+                    (
+                        [("content-type", "application/jsonl")],
+                        "{\"some_test_field1\":\"a\",\"some_test_field2\":\"b\"}\n",
+                    )
+                }),
+            )
+            .route(
+                "/csv",
+                get(|| async {
+                    (
+                        [("content-type", "text/csv")],
+                        "some_test_field1,some_test_field2\na,b\n",
+                    )
+                }),
+            )
+            .route(
+                "/csv-without-header",
+                get(|| async { ([("content-type", "text/csv; header=absent")], "a,b\n") }),
+            )
+            .route(
+                "/tsv",
+                get(|| async {
+                    (
+                        [("content-type", "text/tab-separated-values")],
+                        "some_test_field1\tsome_test_field2\na\tb\n",
+                    )
+                }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let mut paths = Vec::new();
+        #[cfg(feature = "json")]
+        paths.extend(["/json", "/ndjson", "/jsonl"]);
+        #[cfg(feature = "csv")]
+        paths.extend(["/csv", "/csv-without-header", "/tsv"]);
+        for path in paths {
+            let items: Vec<MyTestStructure> = client
+                .get(path)
+                .send()
+                .await
+                .unwrap()
+                .auto_stream::<MyTestStructure>(1024)
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(
+                items,
+                vec![MyTestStructure {
+                    some_test_field1: "a".to_string(),
+                    some_test_field2: "b".to_string()
+                }],
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn auto_stream_unsupported_content_type() {
+        let app = Router::new()
+            .route("/html", get(|| async { axum::response::Html("[]") }))
+            .route("/none", get(|| async { axum::body::Body::from("[]") }));
+
+        let client = TestClient::new(app).await;
+
+        let err = client
+            .get("/html")
+            .send()
+            .await
+            .unwrap()
+            .auto_stream::<MyTestStructure>(1024)
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+        assert_eq!(
+            err.message(),
+            Some(
+                format!(
+                    "Expected content type {}, but the response has text/html; charset=utf-8",
+                    auto_content_types().join(", ")
+                )
+                .as_str()
+            )
+        );
+
+        let err = client
+            .get("/none")
+            .send()
+            .await
+            .unwrap()
+            .auto_stream::<MyTestStructure>(1024)
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("ContentTypeMismatchError");
+
+        assert!(err.is_content_type_mismatch());
+    }
+}
@@ -0,0 +1,468 @@
+use crate::error::StreamBodyKind;
+use crate::{StreamBodyError, StreamBodyResult};
+use futures::future::Either;
+use futures::stream::{BoxStream, Fuse};
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// A boxed stream of items that is [`Unpin`] and a [`futures::stream::FusedStream`], as returned by
+/// [`StreamBodyExt::into_fused`].
+///
+/// Such a stream can be polled again after it ended, so it can be used directly in the loops of
+/// `tokio::select!` and `futures::select!`.
+pub type FusedItemStream<'a, T> = Fuse<BoxStream<'a, StreamBodyResult<T>>>;
+
+/// Extension trait for the streams returned by the streaming responses.
+pub trait StreamBodyExt<'a, T>: Stream<Item = StreamBodyResult<T>> + Sized + Send + 'a {
+    /// Stops the stream after `n` successfully decoded items.
+    ///
+    /// Unlike [`StreamExt::take`], the underlying stream is dropped as soon as the `n`-th item is
+    /// produced, so no more bytes are read or decoded and the connection of the response is
+    /// closed. Errors are passed through and aren't counted as items.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamBodyExt as _};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let preview: Vec<MyTestStructure> = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN)
+    ///         .take_items(10)
+    ///         .try_collect()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn take_items(self, n: usize) -> BoxStream<'a, StreamBodyResult<T>>
+    where
+        T: Send + 'a,
+    {
+        let initial_stream = if n == 0 { None } else { Some(Box::pin(self)) };
+
+        Box::pin(futures::stream::unfold(
+            (initial_stream, n),
+            |(stream, remaining)| async move {
+                let mut stream = stream?;
+                match stream.next().await? {
+                    Ok(item) if remaining == 1 => Some((Ok(item), (None, 0))),
+                    Ok(item) => Some((Ok(item), (Some(stream), remaining - 1))),
+                    Err(err) => Some((Err(err), (Some(stream), remaining))),
+                }
+            },
+        ))
+    }
+
+    /// Fails the stream if no item arrives within `timeout`.
+    ///
+    /// The timeout is measured from the moment the next item is requested until it's decoded,
+    /// so bytes of a partially received item don't reset it. On expiry, the stream yields a
+    /// [`StreamBodyKind::TimeoutError`] error and ends.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamBodyExt as _};
+    /// use serde::Deserialize;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let _items: Vec<MyTestStructure> = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN)
+    ///         .with_item_timeout(Duration::from_secs(30))
+    ///         .try_collect()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn with_item_timeout(self, timeout: Duration) -> BoxStream<'a, StreamBodyResult<T>>
+    where
+        T: Send + 'a,
+    {
+        Box::pin(futures::stream::unfold(
+            Some(Box::pin(self)),
+            move |stream| async move {
+                let mut stream = stream?;
+                match tokio::time::timeout(timeout, stream.next()).await {
+                    Ok(Some(item)) => Some((item, Some(stream))),
+                    Ok(None) => None,
+                    Err(elapsed) => Some((
+                        Err(StreamBodyError::new(
+                            StreamBodyKind::TimeoutError,
+                            Some(Box::new(elapsed)),
+                            None,
+                        )),
+                        None,
+                    )),
+                }
+            },
+        ))
+    }
+
+    /// Ends the stream when `token` is cancelled.
+    ///
+    /// On cancellation, the underlying stream is dropped, so the connection of the response
+    /// is closed, and the stream ends without an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use reqwest_streams::{JsonStreamResponse as _, StreamBodyExt as _};
+    /// use serde::Deserialize;
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let token = CancellationToken::new();
+    ///     let _stream = reqwest::get("http://localhost:8080/json-array")
+    ///         .await?
+    ///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN)
+    ///         .with_cancellation(token.clone());
+    ///
+    ///     token.cancel();
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn with_cancellation(self, token: CancellationToken) -> BoxStream<'a, StreamBodyResult<T>>
+    where
+        T: Send + 'a,
+    {
+        Box::pin(futures::stream::unfold(
+            Some(Box::pin(self)),
+            move |stream| {
+                let token = token.clone();
+                async move {
+                    let mut stream = stream?;
+                    let cancelled = Box::pin(token.cancelled());
+                    match futures::future::select(cancelled, stream.next()).await {
+                        Either::Left(_) => None,
+                        Either::Right((item, _)) => item.map(|item| (item, Some(stream))),
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Converts the stream to a [`FusedItemStream`], which is [`Unpin`] and returns `None`
+    /// forever once it ended.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::prelude::*;
+    /// use futures::stream::FusedStream as _;
+    /// use reqwest_streams::{JsonItemStream, JsonStreamResponse as _, StreamBodyExt as _};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let mut items: JsonItemStream<MyTestStructure> =
+    ///         reqwest::get("http://localhost:8080/json-array")
+    ///             .await?
+    ///             .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN)
+    ///             .into_fused();
+    ///     let mut ticks = tokio::time::interval(std::time::Duration::from_secs(1));
+    ///
+    ///     while !items.is_terminated() {
+    ///         tokio::select! {
+    ///             Some(item) = items.next() => println!("{:?}", item?),
+    ///             _ = ticks.tick() => println!("Still streaming"),
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn into_fused(self) -> FusedItemStream<'a, T>
+    where
+        T: Send + 'a,
+    {
+        let stream: BoxStream<'a, StreamBodyResult<T>> = Box::pin(self);
+        stream.fuse()
+    }
+}
+
+impl<'a, T, S> StreamBodyExt<'a, T> for S where S: Stream<Item = StreamBodyResult<T>> + Send + 'a {}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use crate::{JsonItemStream, JsonStreamResponse};
+    use axum::body::Body;
+    use axum::{routing::*, Router};
+    use axum_streams::*;
+    use futures::stream::FusedStream;
+    use futures::{stream, TryStreamExt};
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field: String,
+    }
+
+    // Notifies when the server drops the response stream
+    struct DropGuard(mpsc::Sender<()>);
+
+    impl Drop for DropGuard {
+        fn drop(&mut self) {
+            let _ = self.0.try_send(());
+        }
+    }
+
+    #[tokio::test]
+    async fn take_items_closes_connection() {
+        let (dropped_tx, mut dropped_rx) = mpsc::channel(1);
+
+        let app = Router::new().route(
+            "/",
+            get(|| async move {
+                let guard = DropGuard(dropped_tx);
+                let test_stream = stream::repeat(MyTestStructure {
+                    some_test_field: "TestValue".to_string(),
+                })
+                .map(move |item| {
+                    let _ = &guard;
+                    item
+                });
+                StreamBodyAs::json_array(test_stream)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let mut items_stream = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024)
+            .take_items(5);
+
+        let mut items = Vec::new();
+        while let Some(item) = items_stream.try_next().await.unwrap() {
+            items.push(item);
+        }
+
+        assert_eq!(items.len(), 5);
+        // The connection is closed while the taken stream is still alive
+        assert!(
+            tokio::time::timeout(Duration::from_secs(5), dropped_rx.recv())
+                .await
+                .is_ok()
+        );
+        drop(items_stream);
+    }
+
+    #[tokio::test]
+    async fn with_cancellation_ends_stream() {
+        let (dropped_tx, mut dropped_rx) = mpsc::channel(1);
+
+        let app = Router::new().route(
+            "/",
+            get(|| async move {
+                let guard = DropGuard(dropped_tx);
+                let test_stream = stream::repeat(MyTestStructure {
+                    some_test_field: "TestValue".to_string(),
+                })
+                .then(move |item| {
+                    let _ = &guard;
+                    async move {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        item
+                    }
+                });
+                StreamBodyAs::json_array(test_stream)
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let token = CancellationToken::new();
+        let mut items_stream = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024)
+            .with_cancellation(token.clone());
+
+        for _ in 0..3 {
+            assert!(items_stream.try_next().await.unwrap().is_some());
+        }
+
+        token.cancel();
+
+        let next_item = tokio::time::timeout(Duration::from_secs(1), items_stream.next()).await;
+        assert!(matches!(next_item, Ok(None)));
+        assert!(
+            tokio::time::timeout(Duration::from_secs(5), dropped_rx.recv())
+                .await
+                .is_ok()
+        );
+    }
+
+    // Sends the chunks of the body with a delay before each of them
+    fn slow_body(chunks: Vec<(u64, &'static str)>) -> Body {
+        Body::from_stream(stream::iter(chunks).then(|(delay_ms, chunk)| async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            Ok::<_, axum::Error>(chunk)
+        }))
+    }
+
+    #[tokio::test]
+    async fn with_item_timeout_stalled_stream() {
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                slow_body(vec![
+                    (0, "{\"some_test_field\":\"TestValue\"}\n"),
+                    (2000, "{\"some_test_field\":\"TestValue\"}\n"),
+                ])
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let mut items_stream = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<MyTestStructure>(1024)
+            .with_item_timeout(Duration::from_millis(300));
+
+        assert!(items_stream.try_next().await.unwrap().is_some());
+        let err = items_stream.try_next().await.expect_err("TimeoutError");
+        assert!(err.is_timeout());
+        assert!(items_stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn with_item_timeout_partial_item() {
+        // Each chunk arrives within the timeout, but the whole item doesn't
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                slow_body(vec![
+                    (0, "{\"some_test_field\""),
+                    (200, ":\"Test"),
+                    (200, "Value\"}"),
+                    (200, "\n"),
+                ])
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let err = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_nl_stream::<MyTestStructure>(1024)
+            .with_item_timeout(Duration::from_millis(300))
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("TimeoutError");
+
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn into_fused_in_select_loop() {
+        fn test_items(value: &str, n: usize) -> Vec<MyTestStructure> {
+            vec![
+                MyTestStructure {
+                    some_test_field: value.to_string(),
+                };
+                n
+            ]
+        }
+
+        let app = Router::new()
+            .route(
+                "/a",
+                get(|| async { StreamBodyAs::json_array(stream::iter(test_items("A", 3))) }),
+            )
+            .route(
+                "/b",
+                get(|| async { StreamBodyAs::json_array(stream::iter(test_items("B", 5))) }),
+            );
+
+        let client = TestClient::new(app).await;
+
+        let mut a_stream: JsonItemStream<MyTestStructure> = client
+            .get("/a")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024)
+            .into_fused();
+        let mut b_stream: JsonItemStream<MyTestStructure> = client
+            .get("/b")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<MyTestStructure>(1024)
+            .into_fused();
+
+        let mut a_items = Vec::new();
+        let mut b_items = Vec::new();
+        loop {
+            tokio::select! {
+                Some(item) = a_stream.next() => a_items.push(item.unwrap()),
+                Some(item) = b_stream.next() => b_items.push(item.unwrap()),
+                else => break,
+            }
+        }
+
+        assert_eq!(a_items, test_items("A", 3));
+        assert_eq!(b_items, test_items("B", 5));
+        assert!(a_stream.is_terminated());
+        assert!(b_stream.is_terminated());
+        assert!(a_stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(feature = "tracing")]
+use crate::StreamBodyError;
+#[cfg(feature = "tracing")]
+use bytes::BytesMut;
+#[cfg(feature = "tracing")]
+use tokio_util::codec::Decoder;
+
+/// Wraps the `codec` of a stream to trace the frames it decodes with the `tracing` feature.
+///
+/// Without the feature, the codec is returned as is.
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) fn traced_codec<D>(codec: D, _format: &'static str, _max_obj_len: usize) -> D {
+    codec
+}
+
+/// Wraps the `codec` of a stream to trace the frames it decodes with the `tracing` feature.
+///
+/// Every stream has a `stream_body` span, with the `format` and the `max_obj_len` of the stream
+/// as fields. The decoded frames, along with the number of bytes consumed for them, are traced
+/// as `TRACE` events, and the errors as `DEBUG` events.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced_codec<D>(
+    codec: D,
+    format: &'static str,
+    max_obj_len: usize,
+) -> TracedCodec<D> {
+    TracedCodec {
+        codec,
+        span: tracing::trace_span!("stream_body", format, max_obj_len),
+        frame_len: 0,
+    }
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) struct TracedCodec<D> {
+    codec: D,
+    span: tracing::Span,
+    frame_len: usize,
+}
+
+#[cfg(feature = "tracing")]
+impl<D> TracedCodec<D> {
+    fn trace<T>(
+        &mut self,
+        buf: &mut BytesMut,
+        decode: impl FnOnce(&mut D, &mut BytesMut) -> Result<Option<T>, StreamBodyError>,
+    ) -> Result<Option<T>, StreamBodyError> {
+        let _entered = self.span.enter();
+        let buf_len = buf.len();
+        let result = decode(&mut self.codec, buf);
+        self.frame_len += buf_len.saturating_sub(buf.len());
+
+        match &result {
+            Ok(Some(_)) => {
+                tracing::trace!(frame_len = self.frame_len, "Decoded a frame");
+                self.frame_len = 0;
+            }
+            Ok(None) => {}
+            Err(err) => tracing::debug!(error = %err, "Failed to decode a frame"),
+        }
+        result
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<D> Decoder for TracedCodec<D>
+where
+    D: Decoder<Error = StreamBodyError>,
+{
+    type Item = D::Item;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<D::Item>, StreamBodyError> {
+        self.trace(buf, D::decode)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<D::Item>, StreamBodyError> {
+        self.trace(buf, D::decode_eof)
+    }
+}
+
+#[cfg(all(test, feature = "tracing", feature = "json"))]
+mod tests {
+    use crate::test_client::*;
+    use crate::JsonStreamResponse;
+    use axum::{routing::*, Router};
+    use futures::TryStreamExt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records the fields of the spans and the events as strings.
+    #[derive(Clone, Default)]
+    struct RecordingSubscriber {
+        records: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct FieldsVisitor(String);
+
+    impl Visit for FieldsVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut visitor = FieldsVisitor(format!("span {}", span.metadata().name()));
+            span.record(&mut visitor);
+            let mut records = self.records.lock().unwrap();
+            records.push(visitor.0);
+            Id::from_u64(records.len() as u64)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = FieldsVisitor(format!("event {}", event.metadata().level()));
+            event.record(&mut visitor);
+            self.records.lock().unwrap().push(visitor.0);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[tokio::test]
+    async fn trace_decoded_frames_and_errors() {
+        let app = Router::new().route("/", get(|| async { "[{\"a\":1},{\"a\":22},{\"a\":}]" }));
+
+        let client = TestClient::new(app).await;
+
+        let subscriber = RecordingSubscriber::default();
+        let _default = tracing::subscriber::set_default(subscriber.clone());
+
+        let err = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .json_array_stream::<serde_json::Value>(1024)
+            .try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("CodecError");
+        assert!(err.is_codec());
+
+        let records = subscriber.records.lock().unwrap();
+        let stream_records: Vec<&String> = records
+            .iter()
+            .filter(|record| record.starts_with("span stream_body") || record.contains("a frame"))
+            .collect();
+
+        assert_eq!(
+            stream_records[0],
+            "span stream_body format=\"json_array\" max_obj_len=1024"
+        );
+        assert_eq!(
+            stream_records[1],
+            "event TRACE message=Decoded a frame frame_len=8"
+        );
+        assert_eq!(
+            stream_records[2],
+            "event TRACE message=Decoded a frame frame_len=9"
+        );
+        assert!(stream_records[3].starts_with("event DEBUG message=Failed to decode a frame"));
+        assert_eq!(stream_records.len(), 4);
+    }
+}
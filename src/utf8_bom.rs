@@ -0,0 +1,20 @@
+use bytes::{Buf, BytesMut};
+
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Strips the UTF-8 byte order mark that some servers put at the start of text bodies,
+/// returning the number of bytes stripped.
+///
+/// Returns `None` while `buf` is too short to tell whether it starts with the mark, unless
+/// the end of the stream is reached.
+pub(crate) fn strip_utf8_bom(buf: &mut BytesMut, eof: bool) -> Option<usize> {
+    if !eof && buf.len() < UTF8_BOM.len() && UTF8_BOM.starts_with(buf) {
+        return None;
+    }
+    if buf.starts_with(UTF8_BOM) {
+        buf.advance(UTF8_BOM.len());
+        Some(UTF8_BOM.len())
+    } else {
+        Some(0)
+    }
+}
@@ -0,0 +1,1542 @@
+//! Helpers for consuming the streams of the streaming responses.
+
+#[cfg(feature = "json")]
+use crate::error::StreamBodyKind;
+#[cfg(feature = "json")]
+use crate::json_array_codec::deserialize_frame;
+#[cfg(feature = "json")]
+use crate::json_nl_codec::{JsonNlCodec, JsonNlOffsetCodec};
+use crate::response_reader::reqwest_io_error;
+#[cfg(feature = "json")]
+use crate::response_reader::response_reader;
+#[cfg(feature = "json")]
+use crate::stream_tracing::traced_codec;
+#[cfg(feature = "json")]
+use crate::JsonLineEnding;
+use crate::{StreamBodyError, StreamBodyResult};
+use futures::stream::BoxStream;
+use futures::{Future, Stream, StreamExt, TryStreamExt};
+#[cfg(feature = "json")]
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Drains the stream, partitioning the decoded items from the errors.
+///
+/// Unlike [`futures::TryStreamExt::try_collect`], the stream isn't aborted on the first
+/// error, which is handy for batch imports reporting the bad records along with the good
+/// ones. Note that the streams of this crate end after an error of the response body or of
+/// the decoding, so only errors yielded by stream adapters are followed by more items.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use reqwest_streams::util::collect_lenient;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-nl")
+///         .await?
+///         .json_nl_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let (items, errors) = collect_lenient(stream).await;
+///     println!("Imported {} items, {} errors", items.len(), errors.len());
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn collect_lenient<T, S>(stream: S) -> (Vec<T>, Vec<StreamBodyError>)
+where
+    S: Stream<Item = StreamBodyResult<T>>,
+{
+    stream
+        .fold(
+            (Vec::new(), Vec::new()),
+            |(mut items, mut errors), result| async move {
+                match result {
+                    Ok(item) => items.push(item),
+                    Err(err) => errors.push(err),
+                }
+                (items, errors)
+            },
+        )
+        .await
+}
+
+/// Collects the decoded items of the stream into a [`Vec`] of at most `max_items` items.
+///
+/// This guards [`futures::TryStreamExt::try_collect`] against responses unexpectedly larger
+/// than usual: if the stream has more items, a
+/// [`crate::error::StreamBodyKind::MaxItemsReachedError`] error is returned as soon as the next
+/// item is decoded, so the remaining items aren't read. The first error of the stream is
+/// returned as it is.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use reqwest_streams::util::collect_up_to;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///     const MAX_ITEMS: usize = 10_000;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-array")
+///         .await?
+///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let items = collect_up_to(stream, MAX_ITEMS).await?;
+///     println!("Collected {} items", items.len());
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn collect_up_to<T, S>(stream: S, max_items: usize) -> StreamBodyResult<Vec<T>>
+where
+    S: Stream<Item = StreamBodyResult<T>>,
+{
+    let mut stream = Box::pin(stream);
+    let mut items = Vec::new();
+    while let Some(item) = stream.next().await {
+        if items.len() == max_items {
+            item?;
+            return Err(StreamBodyError::max_items_reached(max_items));
+        }
+        items.push(item?);
+    }
+    Ok(items)
+}
+
+/// Collects the decoded items of the stream into a [`HashMap`] keyed by `key`.
+///
+/// Like [`futures::TryStreamExt::try_collect`], the first error of the stream is returned as it
+/// is, without reading the remaining items. An item with the key of a previous item replaces
+/// it, so the map has the last item of every key.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use reqwest_streams::util::collect_into_map;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     id: u64,
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-array")
+///         .await?
+///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let items_by_id = collect_into_map(stream, |item| item.id).await?;
+///     println!("Indexed {} items", items_by_id.len());
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn collect_into_map<T, K, S, F>(stream: S, key: F) -> StreamBodyResult<HashMap<K, T>>
+where
+    K: Eq + Hash,
+    S: Stream<Item = StreamBodyResult<T>>,
+    F: Fn(&T) -> K,
+{
+    let mut stream = Box::pin(stream);
+    let mut items = HashMap::new();
+    while let Some(item) = stream.next().await {
+        let item = item?;
+        items.insert(key(&item), item);
+    }
+    Ok(items)
+}
+
+/// Applies `f` to the decoded items of the stream, passing the errors through untouched.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::map_items;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-array")
+///         .await?
+///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let _fields: Vec<String> = map_items(stream, |item| item.some_test_field.to_lowercase())
+///         .try_collect()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn map_items<'a, T, U, S, F>(stream: S, f: F) -> BoxStream<'a, StreamBodyResult<U>>
+where
+    S: Stream<Item = StreamBodyResult<T>> + Send + 'a,
+    F: FnMut(T) -> U + Send + 'a,
+{
+    Box::pin(stream.map_ok(f))
+}
+
+/// Applies the fallible `f` to the decoded items of the stream, passing the errors through
+/// untouched.
+///
+/// The errors returned by `f`, such as [`StreamBodyError::validation`] ones, are yielded in
+/// place of the items and don't end the stream.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::error::StreamBodyError;
+/// use reqwest_streams::util::try_map_items;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-array")
+///         .await?
+///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let _items: Vec<MyTestStructure> = try_map_items(stream, |item| {
+///         if item.some_test_field.is_empty() {
+///             Err(StreamBodyError::validation("Empty test field"))
+///         } else {
+///             Ok(item)
+///         }
+///     })
+///     .try_collect()
+///     .await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn try_map_items<'a, T, U, S, F>(stream: S, mut f: F) -> BoxStream<'a, StreamBodyResult<U>>
+where
+    S: Stream<Item = StreamBodyResult<T>> + Send + 'a,
+    F: FnMut(T) -> StreamBodyResult<U> + Send + 'a,
+{
+    Box::pin(stream.map(move |result| result.and_then(&mut f)))
+}
+
+/// Groups the decoded items of the stream into batches of up to `size` items.
+///
+/// A batch is yielded as soon as it has `size` items, or, with a `max_delay`, once `max_delay`
+/// has passed since its first item was decoded, whichever comes first, so slow streams don't
+/// hold items back indefinitely. The last batch may be shorter. Errors are yielded immediately,
+/// right after the batch of the items decoded before them.
+///
+/// # Panics
+///
+/// Panics if `size` is zero.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::chunked_items;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-nl")
+///         .await?
+///         .json_nl_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let mut batches = chunked_items(stream, 1000, Some(Duration::from_secs(5)));
+///     while let Some(batch) = batches.try_next().await? {
+///         println!("Inserting {} items", batch.len());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub fn chunked_items<'a, T, S>(
+    stream: S,
+    size: usize,
+    max_delay: Option<Duration>,
+) -> BoxStream<'a, StreamBodyResult<Vec<T>>>
+where
+    T: Send + 'a,
+    S: Stream<Item = StreamBodyResult<T>> + Send + 'a,
+{
+    assert!(
+        size > 0,
+        "the size of the batches must be greater than zero"
+    );
+
+    Box::pin(futures::stream::unfold(
+        (Some(Box::pin(stream)), None),
+        move |(stream, pending_err): (Option<Pin<Box<S>>>, Option<StreamBodyError>)| async move {
+            if let Some(err) = pending_err {
+                return Some((Err(err), (stream, None)));
+            }
+            let mut stream = stream?;
+            let mut batch = Vec::new();
+            let mut deadline = None;
+            loop {
+                let next = match deadline {
+                    Some(deadline) => {
+                        match tokio::time::timeout_at(deadline, stream.next()).await {
+                            Ok(next) => next,
+                            Err(_) => return Some((Ok(batch), (Some(stream), None))),
+                        }
+                    }
+                    None => stream.next().await,
+                };
+                match next {
+                    Some(Ok(item)) => {
+                        if batch.is_empty() {
+                            deadline = max_delay.map(|max_delay| Instant::now() + max_delay);
+                        }
+                        batch.push(item);
+                        if batch.len() == size {
+                            return Some((Ok(batch), (Some(stream), None)));
+                        }
+                    }
+                    Some(Err(err)) if batch.is_empty() => {
+                        return Some((Err(err), (Some(stream), None)));
+                    }
+                    Some(Err(err)) => return Some((Ok(batch), (Some(stream), Some(err)))),
+                    None if batch.is_empty() => return None,
+                    None => return Some((Ok(batch), (None, None))),
+                }
+            }
+        },
+    ))
+}
+
+/// Drops the decoded items of the stream whose key, computed by `key`, has already been seen,
+/// keeping the order of the remaining items.
+///
+/// This is handy for the at-least-once deliveries yielding the same record more than once.
+/// Without a `capacity` every distinct key is remembered until the end of the stream. With a
+/// `capacity`, only the `capacity` most recently seen keys are remembered, so the memory stays
+/// bounded, but a duplicate seen again after more than `capacity` other keys isn't dropped.
+/// Errors are passed through.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::dedup_by_key;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     id: u64,
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-nl")
+///         .await?
+///         .json_nl_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let _items: Vec<MyTestStructure> = dedup_by_key(stream, Some(10_000), |item| item.id)
+///         .try_collect()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn dedup_by_key<'a, T, K, S, F>(
+    stream: S,
+    capacity: Option<usize>,
+    key: F,
+) -> BoxStream<'a, StreamBodyResult<T>>
+where
+    T: Send + 'a,
+    K: Eq + Hash + Clone + Send + 'a,
+    S: Stream<Item = StreamBodyResult<T>> + Send + 'a,
+    F: Fn(&T) -> K + Send + 'a,
+{
+    assert!(
+        capacity != Some(0),
+        "the capacity of the seen keys must be greater than zero"
+    );
+
+    let mut seen_keys = SeenKeys::new(capacity);
+    Box::pin(stream.filter(move |item| {
+        let keep = match item {
+            Ok(item) => seen_keys.insert(key(item)),
+            Err(_) => true,
+        };
+        futures::future::ready(keep)
+    }))
+}
+
+/// The keys seen by [`dedup_by_key`], evicting the least recently seen keys over the capacity.
+///
+/// Every key is stamped with the sequence number of its last sighting and queued with it. The
+/// queued entries of the keys seen again later are stale and skipped when evicting.
+struct SeenKeys<K> {
+    capacity: Option<usize>,
+    stamps: HashMap<K, u64>,
+    recency: VecDeque<(K, u64)>,
+    next_stamp: u64,
+}
+
+impl<K: Eq + Hash + Clone> SeenKeys<K> {
+    fn new(capacity: Option<usize>) -> Self {
+        SeenKeys {
+            capacity,
+            stamps: HashMap::new(),
+            recency: VecDeque::new(),
+            next_stamp: 0,
+        }
+    }
+
+    /// Records the sighting of `key`, returning whether it hasn't been seen before.
+    fn insert(&mut self, key: K) -> bool {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => return self.stamps.insert(key, 0).is_none(),
+        };
+
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+        let is_new = self.stamps.insert(key.clone(), stamp).is_none();
+        self.recency.push_back((key, stamp));
+
+        if is_new && self.stamps.len() > capacity {
+            while let Some((key, stamp)) = self.recency.pop_front() {
+                if self.stamps.get(&key) == Some(&stamp) {
+                    self.stamps.remove(&key);
+                    break;
+                }
+            }
+        }
+        if self.recency.len() > capacity * 2 {
+            let stamps = &self.stamps;
+            self.recency
+                .retain(|(key, stamp)| stamps.get(key) == Some(stamp));
+        }
+        is_new
+    }
+}
+
+/// Pulls the first result of the stream, returning it along with a stream that yields it
+/// again before the rest of the results.
+///
+/// This allows to sniff the first item, such as a version field, to decide how to process the
+/// whole stream. The peeked result is `None` if the stream is empty.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::peek_first;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     version: u32
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let stream = reqwest::get("http://localhost:8080/json-array")
+///         .await?
+///         .json_array_stream::<MyTestStructure>(MAX_OBJ_LEN);
+///     let (first, stream) = peek_first(stream).await;
+///     if let Some(Ok(first)) = first {
+///         println!("Processing version {}", first.version);
+///     }
+///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub async fn peek_first<'a, T, S>(
+    stream: S,
+) -> (
+    Option<StreamBodyResult<T>>,
+    BoxStream<'a, StreamBodyResult<T>>,
+)
+where
+    T: Clone + Send + 'a,
+    S: Stream<Item = StreamBodyResult<T>> + Send + 'a,
+{
+    let mut stream = Box::pin(stream);
+    match stream.next().await {
+        Some(first) => {
+            let peeked = match &first {
+                Ok(item) => Ok(item.clone()),
+                Err(err) => Err(err.duplicate()),
+            };
+            (
+                Some(peeked),
+                Box::pin(futures::stream::once(async { first }).chain(stream)),
+            )
+        }
+        None => (None, stream),
+    }
+}
+
+/// Streams the response with any of the formats, returning the headers of the response along
+/// with the stream.
+///
+/// The formats consume the response, so this keeps the headers, such as `X-Total-Count` or
+/// `ETag`, available while the stream is read.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::stream_with_headers;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let response = reqwest::get("http://localhost:8080/json-nl").await?;
+///     let (headers, stream) = stream_with_headers(response, |response| {
+///         response.json_nl_stream::<MyTestStructure>(MAX_OBJ_LEN)
+///     });
+///     println!("ETag: {:?}", headers.get(reqwest::header::ETAG));
+///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn stream_with_headers<'a, T, S>(
+    response: reqwest::Response,
+    stream: S,
+) -> (
+    reqwest::header::HeaderMap,
+    BoxStream<'a, StreamBodyResult<T>>,
+)
+where
+    S: FnOnce(reqwest::Response) -> BoxStream<'a, StreamBodyResult<T>>,
+{
+    let headers = response.headers().clone();
+    (headers, stream(response))
+}
+
+/// Streams the responses of an idempotent request, reconnecting on I/O errors and timeouts.
+///
+/// `connect` sends a fresh request, and `stream` decodes its response with any of the formats.
+/// When the connection or the response body fails with an I/O error or times out, such as a
+/// stalled body, `should_retry` is called with the error and the number of the retry (starting
+/// at 1) to decide whether to reconnect.
+///
+/// The request is restarted from scratch and the items already yielded are skipped, so this is
+/// suitable for small feeds returning the same items in the same order every time. Other errors
+/// are passed through as they are.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::reconnecting_stream;
+/// use reqwest_streams::JsonStreamResponse as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let client = reqwest::Client::new();
+///     let stream = reconnecting_stream(
+///         move || client.get("http://localhost:8080/json-array").send(),
+///         |response| response.json_array_stream::<MyTestStructure>(MAX_OBJ_LEN),
+///         |_err, retry| retry <= 3,
+///     );
+///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn reconnecting_stream<'a, T, C, F, S, R>(
+    connect: C,
+    stream: S,
+    should_retry: R,
+) -> BoxStream<'a, StreamBodyResult<T>>
+where
+    T: Send + 'a,
+    C: FnMut() -> F + Send + 'a,
+    F: Future<Output = reqwest::Result<reqwest::Response>> + Send + 'a,
+    S: FnMut(reqwest::Response) -> BoxStream<'a, StreamBodyResult<T>> + Send + 'a,
+    R: FnMut(&StreamBodyError, usize) -> bool + Send + 'a,
+{
+    let state = ReconnectingState {
+        connect,
+        stream,
+        should_retry,
+        current: None,
+        yielded: 0,
+        to_skip: 0,
+        retries: 0,
+        finished: false,
+    };
+
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        while !state.finished {
+            let current = match state.current.as_mut() {
+                Some(current) => current,
+                None => {
+                    match (state.connect)().await {
+                        Ok(response) => state.current = Some((state.stream)(response)),
+                        Err(err) => {
+                            let err = connection_error(err);
+                            if !state.retry(&err) {
+                                state.finished = true;
+                                return Some((Err(err), state));
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            match current.next().await {
+                Some(Ok(_)) if state.to_skip > 0 => state.to_skip -= 1,
+                Some(Ok(item)) => {
+                    state.yielded += 1;
+                    return Some((Ok(item), state));
+                }
+                Some(Err(err)) if state.retry(&err) => {
+                    state.current = None;
+                    state.to_skip = state.yielded;
+                }
+                Some(Err(err)) => return Some((Err(err), state)),
+                None => state.finished = true,
+            }
+        }
+        None
+    }))
+}
+
+struct ReconnectingState<'a, T, C, S, R> {
+    connect: C,
+    stream: S,
+    should_retry: R,
+    current: Option<BoxStream<'a, StreamBodyResult<T>>>,
+    yielded: usize,
+    to_skip: usize,
+    retries: usize,
+    finished: bool,
+}
+
+impl<T, C, S, R> ReconnectingState<'_, T, C, S, R>
+where
+    R: FnMut(&StreamBodyError, usize) -> bool,
+{
+    fn retry(&mut self, err: &StreamBodyError) -> bool {
+        if (err.is_io() || err.is_timeout()) && (self.should_retry)(err, self.retries + 1) {
+            self.retries += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Streams the JSON lines of an idempotent request, resuming after the last decoded line with
+/// an HTTP range request on I/O errors and timeouts.
+///
+/// The byte offset of the end of the last decoded line is tracked, and when the connection or
+/// the response body fails with an I/O error or times out, `should_retry` is called with the
+/// error and the number of the retry (starting at 1) to decide whether to resume. The `request`
+/// is then sent again with a `Range: bytes=<offset>-` header, so the resumed body starts at the
+/// beginning of the next line and the lines already decoded aren't downloaded again. This suits
+/// large exports, as long as the server supports range requests: a resumed response without
+/// the `206 Partial Content` status fails with a
+/// [`crate::error::StreamBodyKind::HttpStatusError`] error, and one whose `Content-Range`
+/// doesn't start at the offset fails with a [`crate::error::StreamBodyKind::ResumeError`]
+/// error.
+///
+/// The offsets are counted in the decoded body, so a response with a `Content-Encoding` isn't
+/// resumed: its errors are passed through without calling `should_retry`.
+///
+/// The lines are decoded as [`crate::JsonStreamResponse::json_nl_stream`] does, with a maximum
+/// size of `max_obj_len` bytes. The `request` must be clonable, so it can't have a streaming
+/// body: otherwise the stream fails at once with a [`crate::error::StreamBodyKind::ResumeError`]
+/// error, without any retry. Other errors are passed through as they are.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use futures::prelude::*;
+/// use reqwest_streams::util::resumable_json_nl_stream;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// struct MyTestStructure {
+///     some_test_field: String
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     const MAX_OBJ_LEN: usize = 64 * 1024;
+///
+///     let client = reqwest::Client::new();
+///     let stream = resumable_json_nl_stream::<MyTestStructure, _>(
+///         client.get("http://localhost:8080/export.jsonl"),
+///         MAX_OBJ_LEN,
+///         |_err, retry| retry <= 3,
+///     );
+///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub fn resumable_json_nl_stream<'a, T, R>(
+    request: reqwest::RequestBuilder,
+    max_obj_len: usize,
+    should_retry: R,
+) -> BoxStream<'a, StreamBodyResult<T>>
+where
+    T: for<'de> Deserialize<'de> + Send + 'a,
+    R: FnMut(&StreamBodyError, usize) -> bool + Send + 'a,
+{
+    // Sending the request again would never succeed, so this isn't retried
+    if request.try_clone().is_none() {
+        let err = request_not_clonable();
+        return Box::pin(futures::stream::once(async { Err(err) }));
+    }
+
+    let state = ResumableState {
+        request,
+        max_obj_len,
+        should_retry,
+        current: None,
+        offset: 0,
+        current_offset: 0,
+        content_encoded: false,
+        retries: 0,
+        finished: false,
+    };
+
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        while !state.finished {
+            let current = match state.current.as_mut() {
+                Some(current) => current,
+                None => {
+                    if let Err(err) = state.connect().await {
+                        if !state.retry(&err) {
+                            state.finished = true;
+                            return Some((Err(err), state));
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            match current.next().await {
+                Some(Ok((line, consumed_len))) => {
+                    state.offset = state.current_offset + consumed_len as u64;
+                    return Some((deserialize_frame(line.as_bytes()), state));
+                }
+                Some(Err(err)) if state.retry(&err) => state.current = None,
+                Some(Err(err)) => return Some((Err(err), state)),
+                None => state.finished = true,
+            }
+        }
+        None
+    }))
+}
+
+#[cfg(feature = "json")]
+fn request_not_clonable() -> StreamBodyError {
+    StreamBodyError::new(
+        StreamBodyKind::ResumeError,
+        None,
+        Some("The request can't be cloned to resume the stream".into()),
+    )
+}
+
+#[cfg(feature = "json")]
+struct ResumableState<'a, R> {
+    request: reqwest::RequestBuilder,
+    max_obj_len: usize,
+    should_retry: R,
+    current: Option<BoxStream<'a, StreamBodyResult<(String, usize)>>>,
+    offset: u64,
+    current_offset: u64,
+    content_encoded: bool,
+    retries: usize,
+    finished: bool,
+}
+
+#[cfg(feature = "json")]
+impl<R> ResumableState<'_, R>
+where
+    R: FnMut(&StreamBodyError, usize) -> bool,
+{
+    /// Sends the request for the rest of the lines, from the current offset.
+    async fn connect(&mut self) -> StreamBodyResult<()> {
+        let request = self.request.try_clone().ok_or_else(request_not_clonable)?;
+        let request = match self.offset {
+            0 => request,
+            offset => request.header(reqwest::header::RANGE, format!("bytes={}-", offset)),
+        };
+        let response = request.send().await.map_err(connection_error)?;
+        if self.offset > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(StreamBodyError::new(
+                StreamBodyKind::HttpStatusError,
+                None,
+                Some(format!(
+                    "Expected a 206 Partial Content response resuming at byte offset {}, got {}",
+                    self.offset,
+                    response.status()
+                )),
+            ));
+        }
+        if self.offset > 0 && content_range_start(&response) != Some(self.offset) {
+            return Err(StreamBodyError::new(
+                StreamBodyKind::ResumeError,
+                None,
+                Some(format!(
+                    "Expected a Content-Range starting at byte offset {}, got {:?}",
+                    self.offset,
+                    response.headers().get(reqwest::header::CONTENT_RANGE)
+                )),
+            ));
+        }
+        self.content_encoded = response
+            .headers()
+            .get(reqwest::header::CONTENT_ENCODING)
+            .map_or(false, |value| {
+                !String::from_utf8_lossy(value.as_bytes())
+                    .trim()
+                    .eq_ignore_ascii_case("identity")
+            });
+        if self.offset > 0 && self.content_encoded {
+            return Err(StreamBodyError::new(
+                StreamBodyKind::ResumeError,
+                None,
+                Some(format!(
+                    "Can't resume a response with a Content-Encoding at byte offset {}",
+                    self.offset
+                )),
+            ));
+        }
+
+        // A byte order mark is only expected at the start of the body
+        let codec = traced_codec(
+            JsonNlOffsetCodec::new(JsonNlCodec::new_with_max_length(
+                self.max_obj_len,
+                JsonLineEnding::Either,
+                self.offset == 0,
+            )),
+            "json_nl",
+            self.max_obj_len,
+        );
+        let frames_reader = tokio_util::codec::FramedRead::new(response_reader(response), codec);
+        self.current = Some(Box::pin(frames_reader.into_stream()));
+        self.current_offset = self.offset;
+        Ok(())
+    }
+
+    fn retry(&mut self, err: &StreamBodyError) -> bool {
+        eprintln!(
+            "DBG retry {} {:?} {}",
+            self.content_encoded, err, self.offset
+        );
+        // The range of a request counts the encoded bytes, not the decoded ones
+        if self.content_encoded {
+            return false;
+        }
+        if (err.is_io() || err.is_timeout()) && (self.should_retry)(err, self.retries + 1) {
+            self.retries += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Returns the first byte offset of the `Content-Range` of a partial response, such as 20 for
+/// `bytes 20-99/100`.
+#[cfg(feature = "json")]
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    let content_range = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let range = content_range.trim().strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
+fn connection_error(err: reqwest::Error) -> StreamBodyError {
+    StreamBodyError::from(reqwest_io_error(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StreamBodyKind;
+    use futures::stream::{self, BoxStream};
+
+    #[tokio::test]
+    async fn collect_lenient_partitions_items_and_errors() {
+        let stream: BoxStream<StreamBodyResult<i32>> = Box::pin(stream::iter(vec![
+            Ok(1),
+            Err(StreamBodyError::new(StreamBodyKind::CodecError, None, None)),
+            Ok(2),
+            Ok(3),
+            Err(StreamBodyError::max_len_reached(10, 20)),
+            Ok(4),
+        ]));
+
+        let (items, errors) = collect_lenient(stream).await;
+
+        assert_eq!(items, vec![1, 2, 3, 4]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].is_codec());
+        assert!(errors[1].is_max_len());
+    }
+
+    #[tokio::test]
+    async fn collect_up_to_stream_longer_than_max_items() {
+        let stream = stream::iter((0..100).map(Ok::<_, StreamBodyError>));
+
+        let err = collect_up_to(stream, 10)
+            .await
+            .expect_err("MaxItemsReachedError");
+
+        assert!(err.is_max_items());
+        assert_eq!(err.limit(), Some(10));
+    }
+
+    #[tokio::test]
+    async fn collect_up_to_stream_within_max_items() {
+        let items = collect_up_to(stream::iter((0..10).map(Ok::<_, StreamBodyError>)), 10)
+            .await
+            .unwrap();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+
+        let stream: BoxStream<StreamBodyResult<i32>> = Box::pin(stream::iter(vec![
+            Ok(1),
+            Err(StreamBodyError::max_len_reached(10, 20)),
+        ]));
+        let err = collect_up_to(stream, 10)
+            .await
+            .expect_err("MaxLenReachedError");
+        assert!(err.is_max_len());
+    }
+
+    #[tokio::test]
+    async fn collect_into_map_by_key() {
+        let stream = stream::iter(vec![
+            Ok((1, "Ada")),
+            Ok((2, "Alan")),
+            Ok((3, "Grace")),
+            Ok((2, "Alonzo")),
+        ]);
+
+        let items = collect_into_map(stream, |(id, _)| *id).await.unwrap();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[&1], (1, "Ada"));
+        assert_eq!(items[&2], (2, "Alonzo"));
+        assert_eq!(items[&3], (3, "Grace"));
+
+        let stream: BoxStream<StreamBodyResult<(i32, &str)>> = Box::pin(stream::iter(vec![
+            Ok((1, "Ada")),
+            Err(StreamBodyError::max_len_reached(10, 20)),
+            Ok((2, "Alan")),
+        ]));
+        let err = collect_into_map(stream, |(id, _)| *id)
+            .await
+            .expect_err("MaxLenReachedError");
+        assert!(err.is_max_len());
+    }
+
+    #[tokio::test]
+    async fn map_items_preserves_errors() {
+        struct MyTestStructure {
+            first_name: String,
+            last_name: String,
+        }
+
+        let stream = stream::iter(vec![
+            Ok(MyTestStructure {
+                first_name: "Ada".into(),
+                last_name: "Lovelace".into(),
+            }),
+            Err(StreamBodyError::new(StreamBodyKind::CodecError, None, None)),
+            Ok(MyTestStructure {
+                first_name: "Alan".into(),
+                last_name: "Turing".into(),
+            }),
+        ]);
+
+        let results: Vec<StreamBodyResult<String>> = map_items(stream, |item| {
+            format!("{} {}", item.first_name, item.last_name)
+        })
+        .collect()
+        .await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), "Ada Lovelace");
+        assert!(results[1].as_ref().unwrap_err().is_codec());
+        assert_eq!(results[2].as_ref().unwrap(), "Alan Turing");
+    }
+
+    #[tokio::test]
+    async fn try_map_items_validation_errors() {
+        let stream = stream::iter(vec![
+            Ok(1),
+            Ok(-2),
+            Err(StreamBodyError::new(StreamBodyKind::CodecError, None, None)),
+            Ok(3),
+        ]);
+
+        let results: Vec<StreamBodyResult<u32>> = try_map_items(stream, |item: i32| {
+            u32::try_from(item)
+                .map_err(|_| StreamBodyError::validation(format!("Negative item {}", item)))
+        })
+        .collect()
+        .await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap(), &1);
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.kind(), StreamBodyKind::ValidationError);
+        assert_eq!(err.message(), Some("Negative item -2"));
+        assert!(results[2].as_ref().unwrap_err().is_codec());
+        assert_eq!(results[3].as_ref().unwrap(), &3);
+    }
+
+    #[tokio::test]
+    async fn chunked_items_by_size() {
+        let stream = stream::iter(vec![
+            Ok(1),
+            Ok(2),
+            Ok(3),
+            Ok(4),
+            Ok(5),
+            Err(StreamBodyError::new(StreamBodyKind::CodecError, None, None)),
+            Ok(6),
+            Ok(7),
+        ]);
+
+        let results: Vec<StreamBodyResult<Vec<i32>>> =
+            chunked_items(stream, 2, None).collect().await;
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap(), &vec![1, 2]);
+        assert_eq!(results[1].as_ref().unwrap(), &vec![3, 4]);
+        assert_eq!(results[2].as_ref().unwrap(), &vec![5]);
+        assert!(results[3].as_ref().unwrap_err().is_codec());
+        assert_eq!(results[4].as_ref().unwrap(), &vec![6, 7]);
+    }
+
+    #[tokio::test]
+    async fn chunked_items_by_max_delay() {
+        // The items are decoded at 0ms, 0ms, 300ms and 300ms
+        let stream = stream::iter(vec![(0, 1), (0, 2), (300, 3), (0, 4)]).then(
+            |(delay_ms, item)| async move {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                Ok(item)
+            },
+        );
+
+        let batches: Vec<Vec<i32>> = chunked_items(stream, 10, Some(Duration::from_millis(100)))
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![vec![1, 2], vec![3, 4]]);
+    }
+
+    #[tokio::test]
+    async fn dedup_by_key_drops_seen_keys() {
+        let stream = stream::iter(vec![
+            Ok((1, "a")),
+            Ok((2, "b")),
+            Ok((1, "c")),
+            Err(StreamBodyError::new(StreamBodyKind::CodecError, None, None)),
+            Ok((3, "d")),
+            Ok((2, "e")),
+            Ok((1, "f")),
+        ]);
+
+        let results: Vec<StreamBodyResult<(i32, &str)>> =
+            dedup_by_key(stream, None, |(id, _)| *id).collect().await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap(), &(1, "a"));
+        assert_eq!(results[1].as_ref().unwrap(), &(2, "b"));
+        assert!(results[2].as_ref().unwrap_err().is_codec());
+        assert_eq!(results[3].as_ref().unwrap(), &(3, "d"));
+    }
+
+    #[tokio::test]
+    async fn dedup_by_key_with_capacity_forgets_least_recently_seen_keys() {
+        let stream = stream::iter(vec![1, 2, 1, 3, 1, 2, 2].into_iter().map(Ok));
+
+        let items: Vec<i32> = dedup_by_key(stream, Some(2), |id: &i32| *id)
+            .try_collect()
+            .await
+            .unwrap();
+
+        // 2 is forgotten rather than 1 when 3 is seen, as 1 has been seen again since
+        assert_eq!(items, vec![1, 2, 3, 2]);
+    }
+
+    #[tokio::test]
+    async fn peek_first_yields_peeked_item_again() {
+        let (first, stream) = peek_first(stream::iter(vec![Ok(1), Ok(2), Ok(3)])).await;
+        let items: Vec<i32> = stream.try_collect().await.unwrap();
+
+        assert_eq!(first.unwrap().unwrap(), 1);
+        assert_eq!(items, vec![1, 2, 3]);
+
+        let (first, mut stream) = peek_first(stream::iter(vec![
+            Err(StreamBodyError::validation("Missing identifier")),
+            Ok(2),
+        ]))
+        .await;
+
+        assert!(first.unwrap().unwrap_err().is_validation());
+        assert!(stream.next().await.unwrap().unwrap_err().is_validation());
+        assert_eq!(stream.next().await.unwrap().unwrap(), 2);
+
+        let (first, stream) = peek_first(stream::empty::<StreamBodyResult<i32>>()).await;
+
+        assert!(first.is_none());
+        assert_eq!(stream.count().await, 0);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn reconnecting_stream_after_dropped_connection() {
+        use crate::test_client::*;
+        use crate::JsonStreamResponse;
+        use axum::body::Body;
+        use axum::{routing::*, Router};
+        use futures::TryStreamExt;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let server_requests = requests.clone();
+
+        let app = Router::new().route(
+            "/",
+            get(move || async move {
+                let mut chunks: Vec<Result<&str, std::io::Error>> =
+                    vec![Ok("{\"a\":1}\n"), Ok("{\"a\":2}\n")];
+                // The connection of the first request is dropped in the middle of the stream
+                if server_requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                    chunks.push(Err(std::io::ErrorKind::ConnectionReset.into()));
+                } else {
+                    chunks.push(Ok("{\"a\":3}\n"));
+                }
+                Body::from_stream(stream::iter(chunks))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let retries = Arc::new(AtomicUsize::new(0));
+        let stream_retries = retries.clone();
+        let res = reconnecting_stream(
+            move || client.get("/").send(),
+            |response| response.json_nl_stream::<serde_json::Value>(1024),
+            move |err, retry| {
+                assert!(err.is_io());
+                stream_retries.store(retry, Ordering::SeqCst);
+                retry <= 1
+            },
+        );
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!({"a": 1}),
+                serde_json::json!({"a": 2}),
+                serde_json::json!({"a": 3})
+            ]
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(retries.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn reconnecting_stream_after_stalled_body() {
+        use crate::test_client::*;
+        use crate::JsonStreamResponse;
+        use axum::body::Body;
+        use axum::{routing::*, Router};
+        use futures::TryStreamExt;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let server_requests = requests.clone();
+
+        let app = Router::new().route(
+            "/",
+            get(move || async move {
+                // The body of the first request stalls in the middle of the stream
+                let delay_ms = if server_requests.fetch_add(1, Ordering::SeqCst) == 0 {
+                    2000
+                } else {
+                    0
+                };
+                let chunks = vec![(0, "{\"a\":1}\n"), (delay_ms, "{\"a\":2}\n")];
+                Body::from_stream(stream::iter(chunks).then(|(delay_ms, chunk)| async move {
+                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                    Ok::<_, axum::Error>(chunk)
+                }))
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = reconnecting_stream(
+            move || client.get("/").timeout(Duration::from_millis(500)).send(),
+            |response| response.json_nl_stream::<serde_json::Value>(1024),
+            |err, retry| {
+                assert!(err.is_timeout());
+                retry <= 1
+            },
+        );
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn resumable_json_nl_stream_after_dropped_connection() {
+        use crate::test_client::*;
+        use axum::body::Body;
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::response::IntoResponse;
+        use axum::{routing::*, Router};
+        use futures::TryStreamExt;
+        use std::sync::{Arc, Mutex};
+
+        const BODY: &str = "\u{FEFF}{\"a\":1}\n{\"a\":2}\r\n\n{\"a\":3}\n{\"a\":4}\n";
+
+        let ranges: Arc<Mutex<Vec<Option<String>>>> = Arc::new(Mutex::new(Vec::new()));
+        let server_ranges = ranges.clone();
+
+        let app = Router::new().route(
+            "/",
+            get(move |headers: HeaderMap| async move {
+                let range = headers
+                    .get("range")
+                    .map(|range| range.to_str().unwrap().to_string());
+                server_ranges.lock().unwrap().push(range.clone());
+                match range {
+                    // The connection of the first request is dropped in the middle of a line
+                    None => {
+                        let chunks =
+                            stream::once(async { Ok(&BODY[..28]) }).chain(stream::once(async {
+                                tokio::time::sleep(Duration::from_millis(50)).await;
+                                Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                            }));
+                        Body::from_stream(chunks).into_response()
+                    }
+                    Some(range) => {
+                        let offset: usize = range
+                            .strip_prefix("bytes=")
+                            .and_then(|range| range.strip_suffix('-'))
+                            .unwrap()
+                            .parse()
+                            .unwrap();
+                        let content_range =
+                            format!("bytes {}-{}/{}", offset, BODY.len() - 1, BODY.len());
+                        (
+                            StatusCode::PARTIAL_CONTENT,
+                            [("content-range", content_range)],
+                            &BODY[offset..],
+                        )
+                            .into_response()
+                    }
+                }
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = resumable_json_nl_stream::<serde_json::Value, _>(
+            client.get("/"),
+            1024,
+            |err, retry| {
+                assert!(err.is_io());
+                retry <= 1
+            },
+        );
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!({"a": 1}),
+                serde_json::json!({"a": 2}),
+                serde_json::json!({"a": 3}),
+                serde_json::json!({"a": 4})
+            ]
+        );
+        // The BOM and the first two lines were decoded before the connection was dropped
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![None, Some("bytes=20-".to_string())]
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn resumable_json_nl_stream_unclonable_request() {
+        use futures::TryStreamExt;
+
+        let body =
+            reqwest::Body::wrap_stream(stream::empty::<Result<bytes::Bytes, std::io::Error>>());
+        let request = reqwest::Client::new()
+            .post("http://127.0.0.1:1/")
+            .body(body);
+
+        let mut retries = 0;
+        let err =
+            resumable_json_nl_stream::<serde_json::Value, _>(request, 1024, |_err, _retry| {
+                retries += 1;
+                true
+            })
+            .try_collect::<Vec<serde_json::Value>>()
+            .await
+            .expect_err("ResumeError");
+
+        assert!(err.is_resume());
+        assert_eq!(retries, 0);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn resumable_json_nl_stream_after_stalled_body() {
+        use crate::test_client::*;
+        use axum::body::Body;
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::response::IntoResponse;
+        use axum::{routing::*, Router};
+        use futures::TryStreamExt;
+
+        const BODY: &str = "{\"a\":1}\n{\"a\":2}\n";
+
+        let app = Router::new().route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                match headers.get("range") {
+                    // The body of the first request stalls after the first line
+                    None => {
+                        let chunks = vec![(0, &BODY[..8]), (2000, &BODY[8..])];
+                        Body::from_stream(stream::iter(chunks).then(
+                            |(delay_ms, chunk)| async move {
+                                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                                Ok::<_, axum::Error>(chunk)
+                            },
+                        ))
+                        .into_response()
+                    }
+                    Some(_) => (
+                        StatusCode::PARTIAL_CONTENT,
+                        [("content-range", "bytes 8-15/16")],
+                        &BODY[8..],
+                    )
+                        .into_response(),
+                }
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let res = resumable_json_nl_stream::<serde_json::Value, _>(
+            client.get("/").timeout(Duration::from_millis(500)),
+            1024,
+            |err, retry| {
+                assert!(err.is_timeout());
+                retry <= 1
+            },
+        );
+        let items: Vec<serde_json::Value> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})]
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn resumable_json_nl_stream_mismatched_content_range() {
+        use crate::test_client::*;
+        use axum::body::Body;
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::response::IntoResponse;
+        use axum::{routing::*, Router};
+        use futures::TryStreamExt;
+
+        const BODY: &str = "{\"a\":1}\n{\"a\":2}\n";
+
+        let app = Router::new().route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                match headers.get("range") {
+                    None => {
+                        let chunks =
+                            stream::once(async { Ok(&BODY[..8]) }).chain(stream::once(async {
+                                tokio::time::sleep(Duration::from_millis(50)).await;
+                                Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                            }));
+                        Body::from_stream(chunks).into_response()
+                    }
+                    // The server ignores the requested offset
+                    Some(_) => (
+                        StatusCode::PARTIAL_CONTENT,
+                        [("content-range", "bytes 0-15/16")],
+                        BODY,
+                    )
+                        .into_response(),
+                }
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let mut res = resumable_json_nl_stream::<serde_json::Value, _>(
+            client.get("/"),
+            1024,
+            |_err, retry| retry <= 1,
+        );
+
+        assert_eq!(
+            res.try_next().await.unwrap(),
+            Some(serde_json::json!({"a": 1}))
+        );
+        let err = res.try_next().await.expect_err("ResumeError");
+        assert!(err.is_resume());
+    }
+
+    #[cfg(all(feature = "json", feature = "compression"))]
+    #[tokio::test]
+    async fn resumable_json_nl_stream_content_encoding() {
+        use crate::test_client::*;
+        use axum::body::Body;
+        use axum::response::IntoResponse;
+        use axum::{routing::*, Router};
+        use flate2::write::GzEncoder;
+        use futures::TryStreamExt;
+        use std::io::Write;
+
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                // The connection is dropped after the first line of the compressed body
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(b"{\"a\":1}\n").unwrap();
+                encoder.flush().unwrap();
+                let chunks = stream::once(async move { Ok(encoder.get_ref().clone()) }).chain(
+                    stream::once(async {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                    }),
+                );
+                ([("content-encoding", "gzip")], Body::from_stream(chunks)).into_response()
+            }),
+        );
+
+        let client = TestClient::new(app).await;
+
+        let mut retries = 0;
+        let mut res = resumable_json_nl_stream::<serde_json::Value, _>(
+            client.get("/"),
+            1024,
+            |_err, retry| {
+                retries += 1;
+                retry <= 1
+            },
+        );
+
+        assert_eq!(
+            res.try_next().await.unwrap(),
+            Some(serde_json::json!({"a": 1}))
+        );
+        let err = res.try_next().await.expect_err("InputOutputError");
+        assert!(err.is_io());
+        drop(res);
+        assert_eq!(retries, 0);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::error::StreamBodyKind;
+use crate::StreamBodyError;
+use bytes::{Buf, BytesMut};
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// Decodes YAML documents separated by `---` lines.
+///
+/// Only the lines starting with `---` at column zero and followed by nothing but whitespace
+/// or a comment are separators, so `---` inside indented block scalars belongs to the document.
+#[derive(Clone, Debug)]
+pub struct YamlDocCodec<T> {
+    max_length: usize,
+    next_index: usize,
+    consumed_len: usize,
+    _ph: PhantomData<T>,
+}
+
+impl<T> YamlDocCodec<T> {
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        YamlDocCodec {
+            max_length,
+            next_index: 0,
+            consumed_len: 0,
+            _ph: PhantomData,
+        }
+    }
+
+    fn max_len_reached(&self) -> StreamBodyError {
+        StreamBodyError::max_len_reached(
+            self.max_length,
+            self.consumed_len.saturating_add(self.max_length),
+        )
+    }
+}
+
+fn is_separator(line: &[u8]) -> bool {
+    match line.strip_prefix(b"---") {
+        Some(rest) if rest.is_empty() || rest[0].is_ascii_whitespace() => {
+            match rest.iter().find(|ch| !ch.is_ascii_whitespace()) {
+                Some(ch) => *ch == b'#',
+                None => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+impl<T> YamlDocCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    /// Deserializes the document, returning `None` for the empty ones.
+    fn decode_doc(&mut self, doc: BytesMut) -> Result<Option<T>, StreamBodyError> {
+        self.consumed_len += doc.len();
+
+        if doc.iter().all(|ch| ch.is_ascii_whitespace()) {
+            return Ok(None);
+        }
+        serde_yaml::from_slice::<T>(&doc).map(Some).map_err(|err| {
+            StreamBodyError::new(StreamBodyKind::CodecError, Some(Box::new(err)), None)
+        })
+    }
+}
+
+impl<T> tokio_util::codec::Decoder for YamlDocCodec<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    type Item = T;
+    type Error = StreamBodyError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        loop {
+            let line_len = match buf[self.next_index..].iter().position(|ch| *ch == b'\n') {
+                Some(offset) => offset + 1,
+                None if buf.len() > self.max_length => return Err(self.max_len_reached()),
+                None => return Ok(None),
+            };
+
+            if is_separator(&buf[self.next_index..self.next_index + line_len]) {
+                let doc = buf.split_to(self.next_index);
+                buf.advance(line_len);
+                self.next_index = 0;
+                let result = self.decode_doc(doc);
+                self.consumed_len += line_len;
+                if let Some(item) = result? {
+                    return Ok(Some(item));
+                }
+            } else {
+                self.next_index += line_len;
+                if self.next_index > self.max_length {
+                    return Err(self.max_len_reached());
+                }
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<T>, StreamBodyError> {
+        match self.decode(buf)? {
+            Some(item) => Ok(Some(item)),
+            None if buf.is_empty() => Ok(None),
+            None if is_separator(&buf[self.next_index..]) => {
+                let doc = buf.split_to(self.next_index);
+                buf.clear();
+                self.next_index = 0;
+                self.decode_doc(doc)
+            }
+            None => {
+                let doc = buf.split();
+                self.next_index = 0;
+                self.decode_doc(doc)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::Decoder;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        name: String,
+        description: String,
+    }
+
+    fn decode_in_chunks(input: &[u8], chunk_size: usize) -> Vec<MyTestStructure> {
+        let mut codec = YamlDocCodec::<MyTestStructure>::new_with_max_length(1024);
+        let mut buf = BytesMut::new();
+        let mut items = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            buf.extend_from_slice(chunk);
+            while let Some(item) = codec.decode(&mut buf).unwrap() {
+                items.push(item);
+            }
+        }
+        while let Some(item) = codec.decode_eof(&mut buf).unwrap() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn decode_two_documents() {
+        let input =
+            b"---\nname: first\ndescription: one\n--- # second\nname: second\ndescription: two\n";
+
+        for chunk_size in [1, 5, input.len()] {
+            assert_eq!(
+                decode_in_chunks(input, chunk_size),
+                vec![
+                    MyTestStructure {
+                        name: "first".into(),
+                        description: "one".into()
+                    },
+                    MyTestStructure {
+                        name: "second".into(),
+                        description: "two".into()
+                    }
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_block_scalar_with_separator() {
+        let input = b"name: first\ndescription: |\n  ---\n  text ---\n---\nname: second\ndescription: two\n---";
+
+        for chunk_size in [1, 5, input.len()] {
+            assert_eq!(
+                decode_in_chunks(input, chunk_size),
+                vec![
+                    MyTestStructure {
+                        name: "first".into(),
+                        description: "---\ntext ---\n".into()
+                    },
+                    MyTestStructure {
+                        name: "second".into(),
+                        description: "two".into()
+                    }
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_max_len_reached() {
+        let mut codec = YamlDocCodec::<MyTestStructure>::new_with_max_length(30);
+        let mut buf = BytesMut::from(
+            &b"name: a\ndescription: b\n---\nname: some long name\ndescription: b\n"[..],
+        );
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(MyTestStructure {
+                name: "a".into(),
+                description: "b".into()
+            })
+        );
+        let err = codec.decode(&mut buf).expect_err("MaxLenReachedError");
+
+        assert!(err.is_max_len());
+    }
+}
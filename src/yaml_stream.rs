@@ -0,0 +1,138 @@
+use crate::stream_tracing::traced_codec;
+use crate::yaml_codec::YamlDocCodec;
+use crate::CodecStreamResponse;
+use crate::StreamBodyResult;
+use async_trait::*;
+use futures::stream::BoxStream;
+use serde::Deserialize;
+
+/// Extension trait for [`reqwest::Response`] that provides streaming support for [YAML]
+/// multi-document streams.
+///
+/// [YAML]: https://yaml.org/spec/1.2.2/#streams
+#[async_trait]
+pub trait YamlStreamResponse {
+    /// Streams the response as YAML documents separated by `---` lines.
+    ///
+    /// The stream will [`Deserialize`] documents as type `T` with a maximum size of
+    /// `max_obj_len` bytes. Only `---` at the start of its own line separates documents, so
+    /// indented block scalars may contain it. Empty documents are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use futures::{prelude::*, stream::BoxStream as _};
+    /// use reqwest_streams::YamlStreamResponse as _;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Clone, Deserialize)]
+    /// struct MyTestStructure {
+    ///     some_test_field: String
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     const MAX_OBJ_LEN: usize = 64 * 1024;
+    ///
+    ///     let stream = reqwest::get("http://localhost:8080/yaml")
+    ///         .await?
+    ///         .yaml_stream::<MyTestStructure>(MAX_OBJ_LEN);
+    ///     let _items: Vec<MyTestStructure> = stream.try_collect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn yaml_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b;
+}
+
+#[async_trait]
+impl YamlStreamResponse for reqwest::Response {
+    fn yaml_stream<'a, 'b, T>(self, max_obj_len: usize) -> BoxStream<'b, StreamBodyResult<T>>
+    where
+        T: for<'de> Deserialize<'de> + Send + 'b,
+    {
+        let codec = traced_codec(
+            YamlDocCodec::<T>::new_with_max_length(max_obj_len),
+            "yaml",
+            max_obj_len,
+        );
+
+        self.stream_with_codec(codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_client::*;
+    use axum::{routing::*, Router};
+    use futures::TryStreamExt;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+    struct MyTestStructure {
+        some_test_field: String,
+        some_test_text: String,
+    }
+
+    const TEST_DOCUMENTS: &str = "\
+some_test_field: TestValue1
+some_test_text: |
+  Some text
+  ---
+  with a separator
+---
+some_test_field: TestValue2
+some_test_text: Plain
+";
+
+    #[tokio::test]
+    async fn deserialize_yaml_stream() {
+        let app = Router::new().route("/", get(|| async { TEST_DOCUMENTS }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .yaml_stream::<MyTestStructure>(1024);
+        let items: Vec<MyTestStructure> = res.try_collect().await.unwrap();
+
+        assert_eq!(
+            items,
+            vec![
+                MyTestStructure {
+                    some_test_field: "TestValue1".into(),
+                    some_test_text: "Some text\n---\nwith a separator\n".into(),
+                },
+                MyTestStructure {
+                    some_test_field: "TestValue2".into(),
+                    some_test_text: "Plain".into(),
+                }
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deserialize_yaml_stream_check_max_len() {
+        let app = Router::new().route("/", get(|| async { TEST_DOCUMENTS }));
+
+        let client = TestClient::new(app).await;
+
+        let res = client
+            .get("/")
+            .send()
+            .await
+            .unwrap()
+            .yaml_stream::<MyTestStructure>(10);
+        let err = res
+            .try_collect::<Vec<MyTestStructure>>()
+            .await
+            .expect_err("MaxLenReachedError");
+
+        assert_eq!(err.limit(), Some(10));
+    }
+}
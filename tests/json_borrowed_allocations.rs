@@ -0,0 +1,106 @@
+// The paths tracked by the `json-path-errors` feature are allocated for every item
+#![cfg(all(feature = "json", not(feature = "json-path-errors")))]
+
+use reqwest_streams::{JsonFrame, JsonStreamResponse};
+use serde::Deserialize;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the allocations of each thread, so only the allocations of a test are counted.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+fn count_allocation() {
+    let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Deserialize)]
+struct MyBorrowedStructure<'a> {
+    some_test_field: &'a str,
+}
+
+/// Counts the allocations reading `items_len` items borrowed from a response received in a
+/// single chunk, as a JSON array or as JSON lines.
+async fn borrowed_items_allocations(items_len: usize, json_nl: bool) -> usize {
+    let items: Vec<String> = (0..items_len)
+        .map(|idx| format!("{{\"some_test_field\":\"TestValue{}\"}}", idx))
+        .collect();
+    let body = if json_nl {
+        items.join("\n")
+    } else {
+        format!("[{}]", items.join(","))
+    };
+    let response = reqwest::Response::from(axum::http::Response::new(body));
+
+    let mut total_len = 0;
+    let read_item = |frame: JsonFrame<'_>| {
+        let item: MyBorrowedStructure = frame.deserialize()?;
+        total_len += item.some_test_field.len();
+        Ok(())
+    };
+    // The runtime of the test runs the response on the thread of the test
+    let allocations = ALLOCATIONS.with(|allocations| allocations.get());
+    if json_nl {
+        response.json_nl_for_each_borrowed(1024, read_item).await
+    } else {
+        response.json_array_for_each_borrowed(1024, read_item).await
+    }
+    .unwrap();
+    let allocations = ALLOCATIONS.with(|allocations| allocations.get()) - allocations;
+
+    let expected_len: usize = (0..items_len)
+        .map(|idx| format!("TestValue{}", idx).len())
+        .sum();
+    assert_eq!(total_len, expected_len);
+    allocations
+}
+
+#[tokio::test]
+async fn json_array_for_each_borrowed_without_item_allocation() {
+    let few_items_allocations = borrowed_items_allocations(100, false).await;
+    let many_items_allocations = borrowed_items_allocations(10_000, false).await;
+
+    // Only the decoding buffer grows with the response
+    assert!(
+        many_items_allocations < few_items_allocations + 100,
+        "{} {}",
+        few_items_allocations,
+        many_items_allocations
+    );
+}
+
+#[tokio::test]
+async fn json_nl_for_each_borrowed_without_item_allocation() {
+    let few_items_allocations = borrowed_items_allocations(100, true).await;
+    let many_items_allocations = borrowed_items_allocations(10_000, true).await;
+
+    // Only the decoding buffer grows with the response
+    assert!(
+        many_items_allocations < few_items_allocations + 100,
+        "{} {}",
+        few_items_allocations,
+        many_items_allocations
+    );
+}